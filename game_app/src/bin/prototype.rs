@@ -1,19 +1,26 @@
+//! prototype: a bare tile grid for trying out camera and touch controls,
+//! without the simulation.
+
+use game_app::render_grid::world_camera;
+use game_core::{TilePos, TileRect, TILE_SIZE};
+use game_logic::camera::Camera;
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
-const TILE_SIZE: f32 = 32.0;
 const GRID_WIDTH: i32 = 100;
 const GRID_HEIGHT: i32 = 100;
 
 #[macroquad::main("FactoryGame")]
 async fn main() {
-    let mut camera = Camera2D {
-        target: vec2(0.0, 0.0),
-        zoom: vec2(1.0 / screen_width() * 2.0, -1.0 / screen_height() * 2.0),
-        ..Default::default()
-    };
-
-    let mut zoom: f32 = 1.0;
+    let mut camera = Camera::new(screen_width(), screen_height());
+    camera.set_bounds(Some(TileRect {
+        min: TilePos { x: 0, y: 0 },
+        max: TilePos {
+            x: GRID_WIDTH - 1,
+            y: GRID_HEIGHT - 1,
+        },
+    }));
+    let mut last_mouse: Vec2 = mouse_position().into();
 
     // Track previous touch positions (from the previous frame)
     let mut prev_touches: HashMap<u64, Vec2> = HashMap::new();
@@ -27,6 +34,7 @@ async fn main() {
 
     loop {
         let touches = touches();
+        camera.set_viewport(screen_width(), screen_height());
 
         // -----------------------------
         // Touch controls (mobile)
@@ -40,7 +48,7 @@ async fn main() {
 
             if let Some(last_pos) = prev_touches.get(&touch.id) {
                 let delta = pos - *last_pos;
-                camera.target -= vec2(delta.x, -delta.y) / zoom;
+                camera.pan(delta.x, delta.y);
             }
 
             // When there's one touch, we won't consider pinch scaling
@@ -48,27 +56,15 @@ async fn main() {
         }
 
         if touches.len() == 2 {
-            let p1 = touches[0].position;
-            let p2 = touches[1].position;
-
-            let current_distance = p1.distance(p2);
-
-            // A simple pinch implementation based on distance between two touches
-            // We don't persist pinch distance across frames here; instead derive zoom from positions
-            // To make it smoother you'd store last pinch distance similar to prev_touches
-            // For now, compute a relative zoom factor from the previous frame's positions if available
             if let (Some(prev_p1), Some(prev_p2)) = (
                 prev_touches.get(&touches[0].id),
                 prev_touches.get(&touches[1].id),
             ) {
-                let last_distance = prev_p1.distance(*prev_p2);
-                if last_distance > 0.0 {
-                    let zoom_delta = current_distance / last_distance;
-                    zoom *= zoom_delta;
-                    zoom = zoom.clamp(0.2, 5.0);
-
-                    camera.zoom = vec2(zoom / screen_width() * 2.0, -zoom / screen_height() * 2.0);
-                }
+                let (p1, p2) = (touches[0].position, touches[1].position);
+                camera.pinch(
+                    [(prev_p1.x, prev_p1.y), (prev_p2.x, prev_p2.y)],
+                    [(p1.x, p1.y), (p2.x, p2.y)],
+                );
             }
 
             // Record starts for both touches if not present
@@ -85,28 +81,22 @@ async fn main() {
 
             // Pan while holding left mouse button
             if is_mouse_button_down(MouseButton::Left) {
-                let delta = vec2(mouse_pos.0, mouse_pos.1)
-                    - vec2(screen_width() / 2.0, screen_height() / 2.0);
-                camera.target -= delta / zoom * 0.01;
+                camera.pan(mouse_pos.0 - last_mouse.x, mouse_pos.1 - last_mouse.y);
             }
 
             // Mouse click selects a tile (pressed, not held)
             if is_mouse_button_pressed(MouseButton::Left) {
-                let screen = vec2(mouse_pos.0, mouse_pos.1);
-                let world = screen_to_world(screen, &camera, zoom);
-                if let Some((tx, ty)) = world_to_tile(world) {
-                    selected_tile = Some((tx, ty));
+                if let Some(tile) = screen_to_tile(&camera, mouse_pos) {
+                    selected_tile = Some(tile);
                 }
             }
 
             let (_mx, scroll) = mouse_wheel();
             if scroll != 0.0 {
-                zoom *= 1.1_f32.powf(scroll);
-                zoom = zoom.clamp(0.2, 5.0);
-
-                camera.zoom = vec2(zoom / screen_width() * 2.0, -zoom / screen_height() * 2.0);
+                camera.wheel(mouse_pos, scroll.signum());
             }
         }
+        last_mouse = mouse_position().into();
 
         // Detect ended touches and treat small-movement ends as taps
         {
@@ -130,9 +120,8 @@ async fn main() {
 
                     if start_pos.distance(end_pos) < TAP_MAX_MOVEMENT {
                         // treat as tap
-                        let world = screen_to_world(end_pos, &camera, zoom);
-                        if let Some((tx, ty)) = world_to_tile(world) {
-                            selected_tile = Some((tx, ty));
+                        if let Some(tile) = screen_to_tile(&camera, (end_pos.x, end_pos.y)) {
+                            selected_tile = Some(tile);
                         }
                     }
                 }
@@ -152,7 +141,7 @@ async fn main() {
         // -----------------------------
         // Draw
         // -----------------------------
-        set_camera(&camera);
+        set_camera(&world_camera(&camera));
         clear_background(DARKGRAY);
 
         for y in 0..GRID_HEIGHT {
@@ -186,27 +175,8 @@ async fn main() {
     }
 }
 
-fn screen_to_world(screen_pos: Vec2, camera: &Camera2D, zoom: f32) -> Vec2 {
-    let screen_center = vec2(screen_width() / 2.0, screen_height() / 2.0);
-    let relative = screen_pos - screen_center;
-
-    let world_x = camera.target.x + relative.x / zoom;
-    let world_y = camera.target.y - relative.y / zoom;
-
-    vec2(world_x, world_y)
-}
-
-fn world_to_tile(world_pos: Vec2) -> Option<(i32, i32)> {
-    if world_pos.x < 0.0 || world_pos.y < 0.0 {
-        return None;
-    }
-
-    let tx = (world_pos.x / TILE_SIZE).floor() as i32;
-    let ty = (world_pos.y / TILE_SIZE).floor() as i32;
-
-    if tx < 0 || ty < 0 || tx >= GRID_WIDTH || ty >= GRID_HEIGHT {
-        return None;
-    }
-
-    Some((tx, ty))
+/// The grid tile under a screen point, if it is on the map.
+fn screen_to_tile(camera: &Camera, screen_pos: (f32, f32)) -> Option<(i32, i32)> {
+    let TilePos { x, y } = camera.screen_to_tile(screen_pos);
+    ((0..GRID_WIDTH).contains(&x) && (0..GRID_HEIGHT).contains(&y)).then_some((x, y))
 }
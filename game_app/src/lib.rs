@@ -0,0 +1,5 @@
+//! Rendering and event subscribers shared by the game binary and the
+//! `prototype` binary. Both depend on `macroquad` through this crate.
+
+pub mod render_grid;
+pub mod subscribers;
@@ -1,16 +1,20 @@
 //! game_app: Macroquad application glue.
 //! - captures platform input and fills `InputFrame`
-//! - calls `game_logic::update_world`
+//! - drives `game_logic::simulation::Simulation` with real frame time
 //! - performs rendering using Macroquad APIs
 //!
 //! Only this crate depends on `macroquad`.
 //!
 
-use game_logic::{update_world, InputFrame};
+use game_logic::camera::Camera;
+use game_logic::events::dispatch;
+use game_logic::simulation::Simulation;
+use game_logic::InputFrame;
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
-mod render_grid;
+use game_app::render_grid;
+use game_app::subscribers::{Cue, EventLog, Hud, Sounds};
 
 #[macroquad::main("FactoryGame - Macroquad")]
 async fn main() {
@@ -19,14 +23,27 @@ async fn main() {
     world.spawn_player(200.0, 200.0);
     world.spawn_enemy(500.0, 200.0);
     world.spawn_enemy(500.0, 400.0);
+    seed_demo_terrain(&mut world.grid);
+    let mut sim = Simulation::new(world);
+
+    // View onto the world: wheel or pinch zooms, right-drag pans
+    let mut camera = Camera::new(screen_width(), screen_height());
+    camera.set_bounds(sim.world.grid.bounds());
+    let mut last_mouse: Vec2 = mouse_position().into();
 
     // Touch tap detection state (for mobile taps -> action)
     let mut prev_touches: HashMap<u64, Vec2> = HashMap::new();
     let mut touch_start: HashMap<u64, Vec2> = HashMap::new();
     const TAP_MAX_MOVEMENT: f32 = 10.0;
 
+    // Reactions to what the simulation reports each frame
+    let mut hud = Hud::default();
+    let mut sounds = Sounds::default();
+    let mut log = EventLog;
+
     loop {
         let dt = get_frame_time();
+        camera.set_viewport(screen_width(), screen_height());
 
         // Build a platform-agnostic InputFrame from Macroquad input APIs.
         let mut input = InputFrame::default();
@@ -100,6 +117,17 @@ async fn main() {
             }
         }
 
+        // Two fingers pinch to zoom and move together to pan
+        if let [a, b] = touches_now.as_slice() {
+            if let (Some(pa), Some(pb)) = (prev_touches.get(&a.id), prev_touches.get(&b.id)) {
+                camera.pinch(
+                    [(pa.x, pa.y), (pb.x, pb.y)],
+                    [(a.position.x, a.position.y), (b.position.x, b.position.y)],
+                );
+            }
+            touch_pointer = None;
+        }
+
         // Update prev_touches to current touches for the next frame
         prev_touches.clear();
         for t in &touches_now {
@@ -115,36 +143,186 @@ async fn main() {
             }
         }
 
-        // Update game state using platform-agnostic logic
-        update_world(&mut world, &input, dt);
+        // Mouse: wheel zooms around the cursor, right button drags the view
+        let mouse: Vec2 = mouse_position().into();
+        let (_, scroll) = mouse_wheel();
+        if scroll != 0.0 {
+            camera.wheel((mouse.x, mouse.y), scroll.signum());
+        }
+        if is_mouse_button_down(MouseButton::Right) {
+            camera.pan(mouse.x - last_mouse.x, mouse.y - last_mouse.y);
+        }
+        last_mouse = mouse;
+
+        // The pointer was gathered in screen pixels; logic works in world units
+        let screen_pointer = input.pointer;
+        input.pointer = screen_pointer.map(|p| camera.screen_to_world(p));
+
+        // Quick save / load (F5 binary, F6 readable text, F9 load)
+        if is_key_pressed(KeyCode::F5) {
+            quick_save(&sim.world, false);
+        }
+        if is_key_pressed(KeyCode::F6) {
+            quick_save(&sim.world, true);
+        }
+        if is_key_pressed(KeyCode::F9) {
+            if let Some(loaded) = quick_load() {
+                sim = Simulation::new(loaded);
+                camera.set_bounds(sim.world.grid.bounds());
+            }
+        }
+
+        // F8 starts / stops recording a replay of this session
+        if is_key_pressed(KeyCode::F8) {
+            toggle_recording(&mut sim);
+        }
+
+        // Run however many fixed ticks of platform-agnostic logic are due
+        sim.advance(dt, &input);
+        let events = sim.take_events();
+        dispatch(&events, &mut [&mut hud, &mut sounds, &mut log]);
+        hud.update(dt);
+        sounds.update(dt);
+        for cue in sounds.drain() {
+            play_cue(cue);
+        }
 
         // --- Rendering (platform-specific) ---
-        // We'll render the grid (top-left aligned) and then other HUD on top.
-        let grid_snapshot = game_logic::placement::TileGridSnapshot {
-            width: 128,
-            height: 128,
-            instances: Vec::new(),
-        };
+        // The grid and entities are drawn through the camera, the HUD on top
+        // in screen space.
+        let grid_snapshot =
+            game_logic::placement::grid_snapshot(&sim.world.grid, camera.visible_tiles());
 
         // Determine hovered tile from pointer
-        let hover_tile = if let Some((sx, sy)) = input.pointer {
-            let tx = (sx / crate::render_grid::TILE_PX).floor() as i32;
-            let ty = (sy / crate::render_grid::TILE_PX).floor() as i32;
-            Some(game_core::TilePos { x: tx, y: ty })
-        } else {
-            None
-        };
+        let hover_tile = screen_pointer.map(|p| camera.screen_to_tile(p));
+
+        set_camera(&render_grid::world_camera(&camera));
+        render_grid::draw_grid(&grid_snapshot, hover_tile);
+
+        // Entities, blended between the last two ticks for smooth motion
+        for (e, (x, y)) in sim.interpolated_entities() {
+            let color = match e.ty {
+                game_core::EntityType::Player => Color::new(0.3, 0.9, 0.4, 1.0),
+                game_core::EntityType::Enemy => Color::new(0.9, 0.3, 0.3, 1.0),
+                game_core::EntityType::Projectile => Color::new(1.0, 0.95, 0.6, 1.0),
+            };
+            draw_circle(x, y, e.radius, color);
+            // health bar once damaged
+            if let Some(h) = e.health.filter(|h| h.current < h.max) {
+                let w = e.radius * 2.0;
+                let (bx, by) = (x - e.radius, y - e.radius - 6.0);
+                draw_rectangle(bx, by, w, 3.0, Color::new(0.0, 0.0, 0.0, 0.7));
+                let fill = (h.current / h.max).clamp(0.0, 1.0);
+                draw_rectangle(bx, by, w * fill, 3.0, Color::new(0.2, 0.9, 0.2, 1.0));
+            }
+        }
 
-        crate::render_grid::draw_grid(&grid_snapshot, hover_tile);
+        hud.draw_effects();
+        set_default_camera();
 
         // HUD: draw simple pointer marker
-        if let Some((px, py)) = input.pointer {
+        if let Some((px, py)) = screen_pointer {
             draw_circle(px, py, 6.0, Color::new(1.0, 1.0, 0.0, 1.0));
         }
 
         // Simple text showing instructions (no mobile joystick)
-        draw_text("Click or tap to select tiles", 20.0, 20.0, 20.0, WHITE);
+        draw_text(
+            "Click or tap to shoot | wheel or pinch: zoom | right-drag: pan",
+            20.0,
+            20.0,
+            20.0,
+            WHITE,
+        );
+        hud.draw();
 
         next_frame().await
     }
 }
+
+/// Play a sound cue. macroquad is built without its `audio` feature, so
+/// there is no backend to play through yet and cues are dropped here.
+fn play_cue(_cue: Cue) {}
+
+const QUICKSAVE_BINARY: &str = "quicksave.fgsv";
+const QUICKSAVE_TEXT: &str = "quicksave.ron";
+
+/// Write the world to the working directory; errors are only logged.
+fn quick_save(world: &game_core::World, text: bool) {
+    let result = if text {
+        world
+            .save_text()
+            .map_err(|e| e.to_string())
+            .and_then(|s| std::fs::write(QUICKSAVE_TEXT, s).map_err(|e| e.to_string()))
+    } else {
+        world
+            .save_binary()
+            .map_err(|e| e.to_string())
+            .and_then(|b| std::fs::write(QUICKSAVE_BINARY, b).map_err(|e| e.to_string()))
+    };
+    if let Err(e) = result {
+        eprintln!("quick save failed: {}", e);
+    }
+}
+
+/// Load the newest quick save, whichever format it was written in.
+fn quick_load() -> Option<game_core::World> {
+    let newest = [QUICKSAVE_BINARY, QUICKSAVE_TEXT]
+        .into_iter()
+        .filter_map(|p| Some((std::fs::metadata(p).ok()?.modified().ok()?, p)))
+        .max()?;
+    let bytes = std::fs::read(newest.1).ok()?;
+    match game_core::World::load(&bytes) {
+        Ok(world) => Some(world),
+        Err(e) => {
+            eprintln!("quick load failed: {}", e);
+            None
+        }
+    }
+}
+
+const REPLAY_FILE: &str = "session.fgrp";
+
+fn toggle_recording(sim: &mut Simulation) {
+    let result = match sim.stop_recording() {
+        Some(replay) => replay
+            .to_bytes()
+            .map_err(|e| e.to_string())
+            .and_then(|b| std::fs::write(REPLAY_FILE, b).map_err(|e| e.to_string())),
+        None => sim
+            .start_recording(game_logic::replay::CHECKPOINT_INTERVAL)
+            .map_err(|e| e.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("replay recording failed: {}", e);
+    }
+}
+
+/// Scatter a lake and a couple of ore patches so the map isn't empty.
+fn seed_demo_terrain(grid: &mut game_core::TileGrid) {
+    use game_core::{Deposit, TerrainKind, TerrainTile, TilePos};
+
+    let item_id = |name: &str| grid.items().by_name(name).map(|d| d.item_id);
+    let (iron, copper) = (item_id("iron-ore"), item_id("copper-ore"));
+
+    let mut paint = |cx: i32, cy: i32, r: i32, tile: TerrainTile| {
+        for y in cy - r..=cy + r {
+            for x in cx - r..=cx + r {
+                if (x - cx) * (x - cx) + (y - cy) * (y - cy) <= r * r {
+                    // patches may run off the map; those tiles stay unset
+                    let _ = grid.set_terrain(TilePos { x, y }, tile);
+                }
+            }
+        }
+    };
+    let water = TerrainTile {
+        kind: TerrainKind::Water,
+        deposit: None,
+    };
+    let ore = |item: Option<u32>| TerrainTile {
+        kind: TerrainKind::Ground,
+        deposit: item.map(|item| Deposit { item, amount: 500 }),
+    };
+    paint(30, 20, 5, water);
+    paint(12, 10, 3, ore(iron));
+    paint(20, 4, 2, ore(copper));
+}
@@ -1,76 +1,184 @@
-use game_core::{Rotation, Size2, TilePos};
+use game_core::{DrillStatus, MachineStatus, TerrainKind, TilePos};
+use game_logic::camera::Camera;
 
 use macroquad::prelude::*;
 
-pub const TILE_PX: f32 = 32.0;
+/// One world unit is one pixel, so a tile is `TILE_SIZE` pixels across.
+pub const TILE_PX: f32 = game_core::TILE_SIZE;
+
+/// A macroquad camera showing what `camera` sees; world drawing goes
+/// through it so it lines up with `Camera::screen_to_world`.
+pub fn world_camera(camera: &Camera) -> Camera2D {
+    let (w, h) = camera.viewport;
+    Camera2D {
+        target: vec2(camera.target.0, camera.target.1),
+        zoom: vec2(2.0 * camera.zoom / w, -2.0 * camera.zoom / h),
+        ..Default::default()
+    }
+}
 
 pub fn draw_grid(snapshot: &game_logic::placement::TileGridSnapshot, hover: Option<TilePos>) {
-    let width = snapshot.width as i32;
-    let height = snapshot.height as i32;
-    let _screen_w = screen_width();
-    let _screen_h = screen_height();
+    let view = snapshot.view;
+    let (left, top) = (view.min.x as f32 * TILE_PX, view.min.y as f32 * TILE_PX);
+    let (right, bottom) = (
+        (view.max.x + 1) as f32 * TILE_PX,
+        (view.max.y + 1) as f32 * TILE_PX,
+    );
 
-    // draw background for grid area (top-left aligned)
+    // draw background behind the whole view
     clear_background(Color::from_rgba(20, 20, 20, 255));
 
+    // terrain and ore deposits underneath everything else
+    for (pos, tile) in &snapshot.terrain {
+        let x = pos.x as f32 * TILE_PX;
+        let y = pos.y as f32 * TILE_PX;
+        match tile.kind {
+            TerrainKind::Water => {
+                draw_rectangle(x, y, TILE_PX, TILE_PX, Color::new(0.1, 0.3, 0.6, 1.0))
+            }
+            TerrainKind::Cliff => {
+                draw_rectangle(x, y, TILE_PX, TILE_PX, Color::new(0.35, 0.3, 0.25, 1.0))
+            }
+            TerrainKind::Ground => {}
+        }
+        if let Some(deposit) = tile.deposit {
+            let (r, g, b, _) = snapshot
+                .items
+                .get(deposit.item)
+                .map_or((0.7, 0.7, 0.7, 1.0), |d| d.color);
+            draw_rectangle(x, y, TILE_PX, TILE_PX, Color::new(r, g, b, 0.35));
+        }
+    }
+
     // draw tile lines
     let line_color = Color::new(0.7, 0.7, 0.7, 0.18);
-    for x in 0..=width {
+    for x in view.min.x..=view.max.x + 1 {
         let sx = x as f32 * TILE_PX;
-        draw_line(sx, 0.0, sx, (height as f32) * TILE_PX, 1.0, line_color);
+        draw_line(sx, top, sx, bottom, 1.0, line_color);
     }
-    for y in 0..=height {
+    for y in view.min.y..=view.max.y + 1 {
         let sy = y as f32 * TILE_PX;
-        draw_line(0.0, sy, (width as f32) * TILE_PX, sy, 1.0, line_color);
+        draw_line(left, sy, right, sy, 1.0, line_color);
     }
 
     // darker major grid lines every 8 tiles
     let major_color = Color::new(0.6, 0.6, 0.6, 0.25);
-    for x in (0..=width).step_by(8) {
+    for x in (view.min.x..=view.max.x + 1).filter(|x| x.rem_euclid(8) == 0) {
         let sx = x as f32 * TILE_PX;
-        draw_line(sx, 0.0, sx, (height as f32) * TILE_PX, 2.0, major_color);
+        draw_line(sx, top, sx, bottom, 2.0, major_color);
     }
-    for y in (0..=height).step_by(8) {
+    for y in (view.min.y..=view.max.y + 1).filter(|y| y.rem_euclid(8) == 0) {
         let sy = y as f32 * TILE_PX;
-        draw_line(0.0, sy, (width as f32) * TILE_PX, sy, 2.0, major_color);
+        draw_line(left, sy, right, sy, 2.0, major_color);
     }
 
     // draw existing instances as filled rects
     for inst in &snapshot.instances {
-        // determine footprint size based on spec id (same mapping as core)
-        let size = match inst.spec_id {
-            1 => Size2 { w: 1, h: 1 },
-            2 => Size2 { w: 2, h: 2 },
-            3 => Size2 { w: 3, h: 3 },
-            _ => Size2 { w: 1, h: 1 },
-        };
-        let rs = match inst.rotation {
-            Rotation::R0 | Rotation::R180 => size,
-            Rotation::R90 | Rotation::R270 => Size2 {
-                w: size.h,
-                h: size.w,
-            },
+        // footprint and color come from the spec registry
+        let Some(spec) = snapshot.specs.get(inst.spec_id) else {
+            continue;
         };
+        let rs = spec.size.rotated(inst.rotation);
         let x = inst.origin.x as f32 * TILE_PX;
         let y = inst.origin.y as f32 * TILE_PX;
         let w = rs.w as f32 * TILE_PX;
         let h = rs.h as f32 * TILE_PX;
-        let color = match inst.spec_id {
-            1 => Color::new(0.8, 0.8, 0.8, 0.9),
-            2 => Color::new(0.9, 0.6, 0.3, 0.9),
-            3 => Color::new(0.3, 0.8, 0.4, 0.9),
-            _ => Color::new(0.7, 0.7, 0.7, 0.9),
-        };
+        let (r, g, b, a) = spec.color;
+        let color = Color::new(r, g, b, a);
         draw_rectangle(x, y, w, h, color);
     }
 
+    // machine progress bars along the bottom edge of each footprint
+    for view in &snapshot.machines {
+        let Some(inst) = snapshot.instances.iter().find(|i| i.id == view.id) else {
+            continue;
+        };
+        let Some(spec) = snapshot.specs.get(inst.spec_id) else {
+            continue;
+        };
+        let rs = spec.size.rotated(inst.rotation);
+        let x = inst.origin.x as f32 * TILE_PX;
+        let y = (inst.origin.y + rs.h as i32) as f32 * TILE_PX - 6.0;
+        let w = rs.w as f32 * TILE_PX;
+        let fill = match view.status {
+            MachineStatus::OutputBlocked => Color::new(0.9, 0.2, 0.2, 1.0),
+            _ => Color::new(0.2, 0.6, 1.0, 1.0),
+        };
+        draw_rectangle(x, y, w, 4.0, Color::new(0.0, 0.0, 0.0, 0.6));
+        draw_rectangle(x, y, w * view.progress, 4.0, fill);
+    }
+
+    // status dot in the corner of drills that have stopped
+    for (id, status) in &snapshot.stalled_drills {
+        let Some(inst) = snapshot.instances.iter().find(|i| i.id == *id) else {
+            continue;
+        };
+        let color = match status {
+            DrillStatus::Depleted => Color::new(0.9, 0.2, 0.2, 1.0),
+            _ => Color::new(1.0, 0.7, 0.1, 1.0),
+        };
+        let x = inst.origin.x as f32 * TILE_PX + 6.0;
+        let y = inst.origin.y as f32 * TILE_PX + 6.0;
+        draw_circle(x, y, 4.0, color);
+    }
+
+    // fill gauge along the left edge of buildings holding fluid
+    for &(id, fluid, fill) in &snapshot.fluid_levels {
+        let Some(inst) = snapshot.instances.iter().find(|i| i.id == id) else {
+            continue;
+        };
+        let Some(spec) = snapshot.specs.get(inst.spec_id) else {
+            continue;
+        };
+        let h = spec.size.rotated(inst.rotation).h as f32 * TILE_PX - 4.0;
+        let x = inst.origin.x as f32 * TILE_PX + 2.0;
+        let y = inst.origin.y as f32 * TILE_PX + 2.0;
+        let (r, g, b, a) = snapshot
+            .items
+            .get(fluid)
+            .map_or((0.2, 0.45, 0.9, 1.0), |d| d.color);
+        draw_rectangle(x, y, 4.0, h, Color::new(0.0, 0.0, 0.0, 0.6));
+        draw_rectangle(
+            x,
+            y + h * (1.0 - fill),
+            4.0,
+            h * fill,
+            Color::new(r, g, b, a),
+        );
+    }
+
+    // lightning marker on consumers short of power
+    for id in &snapshot.underpowered {
+        let Some(inst) = snapshot.instances.iter().find(|i| i.id == *id) else {
+            continue;
+        };
+        let x = inst.origin.x as f32 * TILE_PX + 4.0;
+        let y = inst.origin.y as f32 * TILE_PX + 4.0;
+        draw_triangle(
+            vec2(x + 6.0, y),
+            vec2(x, y + 10.0),
+            vec2(x + 12.0, y + 10.0),
+            Color::new(1.0, 0.85, 0.1, 1.0),
+        );
+    }
+
+    // items riding on belts
+    for &(ix, iy, item) in &snapshot.belt_items {
+        let (r, g, b, a) = snapshot
+            .items
+            .get(item)
+            .map_or((0.95, 0.85, 0.3, 1.0), |d| d.color);
+        draw_circle(
+            ix * TILE_PX,
+            iy * TILE_PX,
+            TILE_PX * 0.15,
+            Color::new(r, g, b, a),
+        );
+    }
+
     // hover highlight
     if let Some(h) = hover {
-        if h.x >= 0
-            && h.y >= 0
-            && (h.x as usize) < snapshot.width
-            && (h.y as usize) < snapshot.height
-        {
+        if view.contains(h) {
             let rx = h.x as f32 * TILE_PX;
             let ry = h.y as f32 * TILE_PX;
             draw_rectangle_lines(
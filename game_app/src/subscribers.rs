@@ -0,0 +1,199 @@
+//! Event subscribers: the HUD, sound cues and the console log each react to
+//! the `GameEvent`s the simulation reports.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use game_core::EntityType;
+use game_logic::events::{EventSubscriber, GameEvent};
+use macroquad::prelude::*;
+
+const HIT_EFFECT_SECS: f32 = 0.3;
+const MESSAGE_SECS: f32 = 4.0;
+const MAX_MESSAGES: usize = 5;
+
+struct HitEffect {
+    x: f32,
+    y: f32,
+    /// Seconds since the hit
+    age: f32,
+    death: bool,
+}
+
+/// Hit flashes, a kill and production tally, and a short message feed.
+#[derive(Default)]
+pub struct Hud {
+    effects: Vec<HitEffect>,
+    /// Newest last, with seconds shown so far
+    messages: VecDeque<(String, f32)>,
+    kills: u32,
+    produced: u64,
+}
+
+impl Hud {
+    fn say(&mut self, text: String) {
+        self.messages.push_back((text, 0.0));
+        while self.messages.len() > MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+    }
+
+    /// Age effects and messages by `dt` seconds of real time.
+    pub fn update(&mut self, dt: f32) {
+        self.effects.retain_mut(|fx| {
+            fx.age += dt;
+            fx.age < HIT_EFFECT_SECS
+        });
+        self.messages.retain_mut(|(_, age)| {
+            *age += dt;
+            *age < MESSAGE_SECS
+        });
+    }
+
+    /// Hit flashes, drawn in world space.
+    pub fn draw_effects(&self) {
+        for fx in &self.effects {
+            let t = fx.age / HIT_EFFECT_SECS;
+            let size = if fx.death { 28.0 } else { 10.0 };
+            let color = if fx.death {
+                Color::new(1.0, 0.5, 0.1, 1.0 - t)
+            } else {
+                Color::new(1.0, 1.0, 1.0, 1.0 - t)
+            };
+            draw_circle_lines(fx.x, fx.y, size * (0.5 + t), 2.0, color);
+        }
+    }
+
+    /// Tallies and the message feed, drawn in screen space.
+    pub fn draw(&self) {
+        let tally = format!("Kills: {}   Produced: {}", self.kills, self.produced);
+        draw_text(&tally, 20.0, 44.0, 20.0, WHITE);
+        for (i, (text, age)) in self.messages.iter().enumerate() {
+            let fade = (1.0 - age / MESSAGE_SECS).clamp(0.0, 1.0);
+            let y = 72.0 + i as f32 * 20.0;
+            draw_text(text, 20.0, y, 18.0, Color::new(1.0, 0.9, 0.5, fade));
+        }
+    }
+}
+
+impl EventSubscriber for Hud {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::EntityDamaged { x, y, .. } => self.effects.push(HitEffect {
+                x,
+                y,
+                age: 0.0,
+                death: false,
+            }),
+            GameEvent::EntityDied { ty, x, y, .. } => {
+                self.effects.push(HitEffect {
+                    x,
+                    y,
+                    age: 0.0,
+                    death: true,
+                });
+                match ty {
+                    EntityType::Enemy => self.kills += 1,
+                    EntityType::Player => self.say("You died".to_string()),
+                    EntityType::Projectile => {}
+                }
+            }
+            GameEvent::ItemProduced { count, .. } => self.produced += count as u64,
+            GameEvent::PowerShortage {
+                production, demand, ..
+            } => self.say(format!("Low power: {} / {} kW", production, demand)),
+            GameEvent::ResearchFinished { tech } => {
+                self.say(format!("Research complete: technology {}", tech))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Sounds the game can play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cue {
+    Shot,
+    Hit,
+    Death,
+    Build,
+    Demolish,
+    Alarm,
+    Research,
+}
+
+/// Minimum seconds between two plays of the same cue.
+const CUE_COOLDOWN: f32 = 0.08;
+
+/// Picks a cue for each event, dropping repeats of a cue that just played
+/// so a burst of hits does not stack up.
+#[derive(Default)]
+pub struct Sounds {
+    /// Seconds until each cue may play again
+    cooldowns: HashMap<Cue, f32>,
+    queued: Vec<Cue>,
+}
+
+impl Sounds {
+    pub fn update(&mut self, dt: f32) {
+        self.cooldowns.retain(|_, left| {
+            *left -= dt;
+            *left > 0.0
+        });
+    }
+
+    /// Cues to play now, oldest first.
+    pub fn drain(&mut self) -> Vec<Cue> {
+        std::mem::take(&mut self.queued)
+    }
+}
+
+impl EventSubscriber for Sounds {
+    fn on_event(&mut self, event: &GameEvent) {
+        let cue = match event {
+            GameEvent::ProjectileFired { .. } => Cue::Shot,
+            GameEvent::EntityDamaged { .. } => Cue::Hit,
+            GameEvent::EntityDied { .. } => Cue::Death,
+            GameEvent::BuildingPlaced { .. } => Cue::Build,
+            GameEvent::BuildingRemoved { .. } => Cue::Demolish,
+            GameEvent::PowerShortage { .. } => Cue::Alarm,
+            GameEvent::ResearchFinished { .. } => Cue::Research,
+            _ => return,
+        };
+        if let Entry::Vacant(slot) = self.cooldowns.entry(cue) {
+            slot.insert(CUE_COOLDOWN);
+            self.queued.push(cue);
+        }
+    }
+}
+
+/// Writes notable events to stderr. Per-tick noise such as damage and
+/// production is left out.
+pub struct EventLog;
+
+impl EventSubscriber for EventLog {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::BuildingPlaced {
+                id,
+                spec_id,
+                origin,
+            } => eprintln!(
+                "placed building {} (spec {}) at {},{}",
+                id, spec_id, origin.x, origin.y
+            ),
+            GameEvent::BuildingRemoved { id, .. } => eprintln!("removed building {}", id),
+            GameEvent::EntityDied { id, ty, .. } => eprintln!("{:?} {} died", ty, id),
+            GameEvent::PowerShortage {
+                network,
+                production,
+                demand,
+            } => eprintln!(
+                "power network {} short: {} of {} kW",
+                network, production, demand
+            ),
+            GameEvent::ResearchFinished { tech } => eprintln!("researched technology {}", tech),
+            _ => {}
+        }
+    }
+}
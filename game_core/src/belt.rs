@@ -0,0 +1,204 @@
+//! Conveyor belt simulation. Each conveyor instance carries items along
+//! its rotation and hands them to whatever occupies the tile in front.
+
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, TileGrid};
+use crate::item::ItemId;
+
+/// Length of one belt tile in belt steps.
+pub const BELT_TILE_LEN: u32 = 256;
+/// Minimum distance between two items on a belt (4 items per tile).
+pub const BELT_ITEM_SPACING: u32 = BELT_TILE_LEN / 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeltItem {
+    pub item: ItemId,
+    /// Distance travelled from the belt entry, 0..=BELT_TILE_LEN
+    pub pos: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Belt {
+    pub speed: u32,
+    /// Items ordered front (nearest the exit) to back
+    items: VecDeque<BeltItem>,
+}
+
+impl Belt {
+    pub fn new(speed: u32) -> Self {
+        Self {
+            speed,
+            items: VecDeque::new(),
+        }
+    }
+
+    /// Items ordered front to back.
+    pub fn items(&self) -> impl Iterator<Item = &BeltItem> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Push an item onto the back of the belt at up to `pos`, keeping spacing.
+    /// Returns false if the entry is still blocked by the last item.
+    fn push_back(&mut self, item: ItemId, pos: u32) -> bool {
+        let pos = match self.items.back() {
+            Some(last) if last.pos < BELT_ITEM_SPACING => return false,
+            Some(last) => pos.min(last.pos - BELT_ITEM_SPACING),
+            None => pos.min(BELT_TILE_LEN),
+        };
+        self.items.push_back(BeltItem { item, pos });
+        true
+    }
+
+    /// Whether an item could be put onto the belt entry right now.
+    pub fn has_entry_room(&self) -> bool {
+        self.items
+            .back()
+            .is_none_or(|last| last.pos >= BELT_ITEM_SPACING)
+    }
+
+    /// Remove the frontmost item for which `want` holds, wherever it is.
+    pub fn take_first<F>(&mut self, want: F) -> Option<ItemId>
+    where
+        F: Fn(ItemId) -> bool,
+    {
+        let index = self.items.iter().position(|i| want(i.item))?;
+        self.items.remove(index).map(|i| i.item)
+    }
+
+    /// Remove the front item if it has reached the end of the belt.
+    pub fn take_front(&mut self) -> Option<ItemId> {
+        match self.items.front() {
+            Some(front) if front.pos >= BELT_TILE_LEN => self.items.pop_front().map(|i| i.item),
+            _ => None,
+        }
+    }
+
+    /// Move every item forward by `speed`, compressing behind the one ahead.
+    /// The front item stops at the end of the belt; the returned value is
+    /// how far past the end it would have travelled, if at all.
+    fn advance(&mut self) -> Option<u32> {
+        let mut overflow = None;
+        let mut limit = u32::MAX;
+        for (i, it) in self.items.iter_mut().enumerate() {
+            let wanted = it.pos + self.speed;
+            if i == 0 && wanted > BELT_TILE_LEN {
+                overflow = Some(wanted - BELT_TILE_LEN);
+            }
+            it.pos = it.pos.max(wanted.min(limit).min(BELT_TILE_LEN));
+            limit = it.pos.saturating_sub(BELT_ITEM_SPACING);
+        }
+        overflow.or_else(|| match self.items.front() {
+            Some(f) if f.pos >= BELT_TILE_LEN => Some(0),
+            _ => None,
+        })
+    }
+}
+
+/// Where a belt delivers the item at its front.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum BeltTarget {
+    Belt(InstanceId),
+    Building(InstanceId),
+    None,
+}
+
+impl TileGrid {
+    pub fn belt(&self, id: InstanceId) -> Option<&Belt> {
+        self.belts.get(&id)
+    }
+
+    /// Put an item onto the entry of belt `id`. Returns false if the belt does
+    /// not exist or its entry is blocked.
+    pub fn belt_insert(&mut self, id: InstanceId, item: ItemId) -> bool {
+        match self.belts.get_mut(&id) {
+            Some(belt) => belt.push_back(item, 0),
+            None => false,
+        }
+    }
+
+    fn belt_target(&self, id: InstanceId) -> BeltTarget {
+        let Some(inst) = self.instances.get(&id) else {
+            return BeltTarget::None;
+        };
+        let Some(next) = self.tile_occupant(inst.origin.step(inst.rotation)) else {
+            return BeltTarget::None;
+        };
+        if !self.belts.contains_key(&next) {
+            return BeltTarget::Building(next);
+        }
+        // two belts facing each other would bounce items forever
+        match self.instances.get(&next) {
+            Some(n) if n.rotation == inst.rotation.opposite() => BeltTarget::None,
+            _ => BeltTarget::Belt(next),
+        }
+    }
+
+    /// Belt ids ordered so every belt is updated before the belts feeding it.
+    /// Loops are broken at the lowest id so the order stays deterministic.
+    fn belt_update_order(&self) -> Vec<InstanceId> {
+        let mut order = Vec::with_capacity(self.belts.len());
+        let mut visited = HashSet::new();
+        for &start in self.belts.keys() {
+            let mut chain = Vec::new();
+            let mut cur = start;
+            while visited.insert(cur) {
+                chain.push(cur);
+                match self.belt_target(cur) {
+                    BeltTarget::Belt(next) => cur = next,
+                    _ => break,
+                }
+            }
+            order.extend(chain.into_iter().rev());
+        }
+        order
+    }
+
+    /// Advance every belt by one tick.
+    ///
+    /// Items that reach the end of a belt move to the next belt, or into the
+    /// building in front via `TileGrid::offer_item`. Buildings that do not take
+    /// items themselves are offered them through `sink`, which returns whether
+    /// it took the item. Items that cannot move wait at the end and the belt
+    /// backs up.
+    pub fn tick_belts<F>(&mut self, mut sink: F)
+    where
+        F: FnMut(InstanceId, ItemId) -> bool,
+    {
+        for id in self.belt_update_order() {
+            let target = self.belt_target(id);
+            let Some(belt) = self.belts.get_mut(&id) else {
+                continue;
+            };
+            let Some(overflow) = belt.advance() else {
+                continue;
+            };
+            let item = belt.items[0].item;
+            let delivered = match target {
+                BeltTarget::Belt(next) => self
+                    .belts
+                    .get_mut(&next)
+                    .is_some_and(|b| b.push_back(item, overflow)),
+                BeltTarget::Building(b) => {
+                    self.offer_item(b, item).unwrap_or_else(|| sink(b, item))
+                }
+                BeltTarget::None => false,
+            };
+            if delivered {
+                if let Some(belt) = self.belts.get_mut(&id) {
+                    belt.items.pop_front();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Blueprints: a rectangle of buildings copied off the grid so it can be
+//! pasted elsewhere, turned, or shared as text.
+//!
+//! Offsets are measured from the top-left corner of the blueprint. The text
+//! form is `FBP<version>:` followed by the bincode-encoded blueprint in
+//! URL-safe base64, which survives being pasted into chat.
+
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, PlacementError, Rotation, Size2, TileGrid, TilePos, TileRect};
+use crate::item::ItemId;
+use crate::recipe::RecipeId;
+use crate::spec::{SpecId, SpecRegistry};
+
+/// Version written by `Blueprint::to_text`.
+pub const BLUEPRINT_VERSION: u32 = 1;
+
+/// Start of every blueprint string, before the version.
+pub const BLUEPRINT_PREFIX: &str = "FBP";
+
+#[derive(Debug)]
+pub enum BlueprintError {
+    /// These tiles are out of bounds, occupied, unbuildable or would mix fluids
+    Conflicts(Vec<TilePos>),
+    /// A building could not be placed for another reason, e.g. an unknown spec
+    Placement(PlacementError),
+    /// The blueprint could not be serialized
+    Encode(String),
+    /// The text could not be decoded
+    Decode(String),
+    /// Text that does not start with `BLUEPRINT_PREFIX`
+    NotABlueprint,
+    /// Written by a format version this build cannot read
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for BlueprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlueprintError::Conflicts(tiles) => {
+                write!(f, "blueprint conflicts with {} tile(s)", tiles.len())
+            }
+            BlueprintError::Placement(e) => write!(f, "cannot place blueprint: {:?}", e),
+            BlueprintError::Encode(msg) => write!(f, "failed to encode blueprint: {}", msg),
+            BlueprintError::Decode(msg) => write!(f, "failed to decode blueprint: {}", msg),
+            BlueprintError::NotABlueprint => write!(f, "text is not a blueprint"),
+            BlueprintError::UnsupportedVersion(v) => {
+                write!(f, "unsupported blueprint version {}", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlueprintError {}
+
+/// One building of a blueprint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlueprintEntry {
+    pub spec_id: SpecId,
+    /// Origin relative to the blueprint's top-left corner
+    pub offset: TilePos,
+    pub rotation: Rotation,
+    /// Recipe chosen for a machine that does not pick its own
+    pub recipe: Option<RecipeId>,
+    /// Inserter item filter
+    pub filter: Option<ItemId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blueprint {
+    /// Tiles covered by the captured rectangle
+    pub size: Size2,
+    pub entries: Vec<BlueprintEntry>,
+}
+
+impl Blueprint {
+    /// The whole blueprint turned clockwise by `rot`, offsets and buildings
+    /// alike. `specs` supplies footprints; unknown specs count as 1x1.
+    pub fn rotated(&self, rot: Rotation, specs: &SpecRegistry) -> Blueprint {
+        let mut out = self.clone();
+        for _ in 0..rot.quarter_turns() {
+            // (x, y) -> (h - 1 - y, x); an entry's new origin is the image
+            // of its footprint's bottom-left corner
+            let height = out.size.h as i32;
+            for e in &mut out.entries {
+                let foot = specs
+                    .get(e.spec_id)
+                    .map(|s| s.size.rotated(e.rotation))
+                    .unwrap_or(Size2 { w: 1, h: 1 });
+                e.offset = TilePos {
+                    x: height - e.offset.y - foot.h as i32,
+                    y: e.offset.x,
+                };
+                e.rotation = e.rotation.turned(Rotation::R90);
+            }
+            out.size = out.size.rotated(Rotation::R90);
+        }
+        out
+    }
+
+    /// Shareable text form, see the module docs.
+    pub fn to_text(&self) -> Result<String, BlueprintError> {
+        let bytes = bincode::serialize(self).map_err(|e| BlueprintError::Encode(e.to_string()))?;
+        Ok(format!(
+            "{}{}:{}",
+            BLUEPRINT_PREFIX,
+            BLUEPRINT_VERSION,
+            URL_SAFE_NO_PAD.encode(bytes)
+        ))
+    }
+
+    /// Parse the output of `to_text`. Surrounding whitespace is ignored.
+    pub fn from_text(text: &str) -> Result<Blueprint, BlueprintError> {
+        let rest = text
+            .trim()
+            .strip_prefix(BLUEPRINT_PREFIX)
+            .ok_or(BlueprintError::NotABlueprint)?;
+        let (version, body) = rest.split_once(':').ok_or(BlueprintError::NotABlueprint)?;
+        let version: u32 = version.parse().map_err(|_| BlueprintError::NotABlueprint)?;
+        if version != BLUEPRINT_VERSION {
+            return Err(BlueprintError::UnsupportedVersion(version));
+        }
+        let bytes = URL_SAFE_NO_PAD
+            .decode(body)
+            .map_err(|e| BlueprintError::Decode(e.to_string()))?;
+        bincode::deserialize(&bytes).map_err(|e| BlueprintError::Decode(e.to_string()))
+    }
+}
+
+impl TileGrid {
+    /// Copy every building lying wholly inside the rectangle spanned by the
+    /// two corner tiles (inclusive, in any order).
+    pub fn capture_blueprint(&self, a: TilePos, b: TilePos) -> Blueprint {
+        let rect = TileRect::spanning(a, b);
+        let min = rect.min;
+        let entries = self
+            .instances
+            .values()
+            .filter(|inst| {
+                self.footprint(inst.id)
+                    .is_some_and(|tiles| tiles.iter().all(|&t| rect.contains(t)))
+            })
+            .map(|inst| BlueprintEntry {
+                spec_id: inst.spec_id,
+                offset: TilePos {
+                    x: inst.origin.x - min.x,
+                    y: inst.origin.y - min.y,
+                },
+                rotation: inst.rotation,
+                recipe: self
+                    .machine(inst.id)
+                    .filter(|m| !m.auto_select)
+                    .and_then(|m| m.recipe()),
+                filter: self.inserter(inst.id).and_then(|i| i.filter),
+            })
+            .collect();
+        Blueprint {
+            size: Size2 {
+                w: rect.width(),
+                h: rect.height(),
+            },
+            entries,
+        }
+    }
+
+    /// Tiles that stop `blueprint` from being pasted with its top-left
+    /// corner at `at`, sorted and without duplicates. Empty means it fits.
+    pub fn blueprint_conflicts(&self, blueprint: &Blueprint, at: TilePos) -> Vec<TilePos> {
+        let mut conflicts = Vec::new();
+        for e in &blueprint.entries {
+            let Some(spec) = self.specs().get(e.spec_id) else {
+                continue;
+            };
+            let origin = offset_by(at, e.offset);
+            let tiles = Self::footprint_tiles(spec.size, origin, e.rotation);
+            if self.fluid_conflict(e.spec_id, origin, e.rotation).is_some() {
+                conflicts.extend(tiles);
+                continue;
+            }
+            conflicts.extend(tiles.into_iter().filter(|&t| {
+                !self.in_bounds(t)
+                    || self.tile_occupant(t).is_some()
+                    || !self.terrain(t).kind.is_buildable()
+            }));
+        }
+        conflicts.sort();
+        conflicts.dedup();
+        conflicts
+    }
+
+    /// Build `blueprint` with its top-left corner at `at` and apply each
+    /// building's settings. Either every building is placed or none is.
+    /// Settings that do not apply here (e.g. a recipe this grid lacks or has
+    /// not unlocked) are dropped.
+    pub fn paste_blueprint(
+        &mut self,
+        blueprint: &Blueprint,
+        at: TilePos,
+    ) -> Result<Vec<InstanceId>, BlueprintError> {
+        if let Some(e) = blueprint
+            .entries
+            .iter()
+            .find(|e| self.specs().get(e.spec_id).is_none())
+        {
+            return Err(BlueprintError::Placement(PlacementError::UnknownSpec(
+                e.spec_id,
+            )));
+        }
+        if let Some(e) = blueprint
+            .entries
+            .iter()
+            .find(|e| !self.is_spec_unlocked(e.spec_id))
+        {
+            return Err(BlueprintError::Placement(PlacementError::Locked(e.spec_id)));
+        }
+        let conflicts = self.blueprint_conflicts(blueprint, at);
+        if !conflicts.is_empty() {
+            return Err(BlueprintError::Conflicts(conflicts));
+        }
+
+        let mut placed = Vec::with_capacity(blueprint.entries.len());
+        for e in &blueprint.entries {
+            match self.place(e.spec_id, offset_by(at, e.offset), e.rotation) {
+                Ok(id) => placed.push(id),
+                Err(err) => {
+                    for id in placed {
+                        self.remove(id);
+                    }
+                    return Err(BlueprintError::Placement(err));
+                }
+            }
+        }
+        for (e, &id) in blueprint.entries.iter().zip(&placed) {
+            if e.recipe.is_some_and(|r| self.is_recipe_unlocked(r)) {
+                let _ = self.set_recipe(id, e.recipe);
+            }
+            if let Some(inserter) = self.inserter_mut(id) {
+                inserter.filter = e.filter;
+            }
+        }
+        Ok(placed)
+    }
+}
+
+fn offset_by(at: TilePos, offset: TilePos) -> TilePos {
+    TilePos {
+        x: at.x + offset.x,
+        y: at.y + offset.y,
+    }
+}
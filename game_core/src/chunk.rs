@@ -0,0 +1,154 @@
+//! Sparse tile occupancy. The map is cut into `CHUNK_SIZE` square chunks
+//! that are allocated when a building first touches them and dropped again
+//! once empty, so the grid can grow in any direction, negative included.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::grid::{InstanceId, TilePos, TileRect};
+
+/// Width and height of a chunk in tiles.
+pub const CHUNK_SIZE: i32 = 32;
+
+/// Chunk coordinates: chunk (0, 0) holds tiles (0..32, 0..32), chunk
+/// (-1, 0) holds tiles (-32..0, 0..32).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkPos {
+    /// The chunk containing `pos`.
+    pub fn of(pos: TilePos) -> ChunkPos {
+        ChunkPos {
+            x: pos.x.div_euclid(CHUNK_SIZE),
+            y: pos.y.div_euclid(CHUNK_SIZE),
+        }
+    }
+
+    /// Top-left tile of the chunk.
+    pub fn origin(self) -> TilePos {
+        TilePos {
+            x: self.x * CHUNK_SIZE,
+            y: self.y * CHUNK_SIZE,
+        }
+    }
+}
+
+struct Chunk {
+    tiles: Vec<Option<InstanceId>>,
+    /// Occupied tiles, so empty chunks can be freed
+    used: u32,
+}
+
+impl Chunk {
+    fn new() -> Self {
+        Self {
+            tiles: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+            used: 0,
+        }
+    }
+}
+
+fn local_index(pos: TilePos) -> usize {
+    (pos.y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + pos.x.rem_euclid(CHUNK_SIZE)) as usize
+}
+
+/// Which building covers each tile. Not saved; rebuilt from the instances.
+#[derive(Default)]
+pub(crate) struct Occupancy {
+    chunks: HashMap<ChunkPos, Chunk>,
+    /// Bumped on every change, so callers can tell when cached layout is stale
+    revision: u64,
+}
+
+impl Occupancy {
+    pub(crate) fn get(&self, pos: TilePos) -> Option<InstanceId> {
+        self.chunks.get(&ChunkPos::of(pos))?.tiles[local_index(pos)]
+    }
+
+    /// Mark `pos` as covered by `id`. The tile must be free.
+    pub(crate) fn set(&mut self, pos: TilePos, id: InstanceId) {
+        let chunk = self
+            .chunks
+            .entry(ChunkPos::of(pos))
+            .or_insert_with(Chunk::new);
+        let tile = &mut chunk.tiles[local_index(pos)];
+        debug_assert!(tile.is_none(), "tile {:?} already occupied", pos);
+        if tile.replace(id).is_none() {
+            chunk.used += 1;
+        }
+        self.revision += 1;
+    }
+
+    /// Free `pos` if `id` covers it.
+    pub(crate) fn clear(&mut self, pos: TilePos, id: InstanceId) {
+        let key = ChunkPos::of(pos);
+        let Some(chunk) = self.chunks.get_mut(&key) else {
+            return;
+        };
+        let tile = &mut chunk.tiles[local_index(pos)];
+        if *tile == Some(id) {
+            *tile = None;
+            chunk.used -= 1;
+            if chunk.used == 0 {
+                self.chunks.remove(&key);
+            }
+            self.revision += 1;
+        }
+    }
+
+    /// Free every tile covered by `id`, wherever it is.
+    pub(crate) fn clear_all(&mut self, id: InstanceId) {
+        for chunk in self.chunks.values_mut() {
+            for tile in chunk.tiles.iter_mut().filter(|t| **t == Some(id)) {
+                *tile = None;
+                chunk.used -= 1;
+            }
+        }
+        self.chunks.retain(|_, c| c.used > 0);
+        self.revision += 1;
+    }
+
+    /// Every id covering a tile of `rect`. Only allocated chunks that
+    /// overlap `rect` are looked at.
+    pub(crate) fn ids_in(&self, rect: TileRect) -> BTreeSet<InstanceId> {
+        let (lo, hi) = (ChunkPos::of(rect.min), ChunkPos::of(rect.max));
+        let overlapping = (hi.x - lo.x + 1) as u64 * (hi.y - lo.y + 1) as u64;
+        let chunks: Vec<(ChunkPos, &Chunk)> = if overlapping > self.chunks.len() as u64 {
+            self.chunks
+                .iter()
+                .filter(|(c, _)| c.x >= lo.x && c.x <= hi.x && c.y >= lo.y && c.y <= hi.y)
+                .map(|(&c, chunk)| (c, chunk))
+                .collect()
+        } else {
+            (lo.y..=hi.y)
+                .flat_map(|y| (lo.x..=hi.x).map(move |x| ChunkPos { x, y }))
+                .filter_map(|c| Some((c, self.chunks.get(&c)?)))
+                .collect()
+        };
+        let mut ids = BTreeSet::new();
+        for (c, chunk) in chunks {
+            let o = c.origin();
+            for y in rect.min.y.max(o.y)..=rect.max.y.min(o.y + CHUNK_SIZE - 1) {
+                for x in rect.min.x.max(o.x)..=rect.max.x.min(o.x + CHUNK_SIZE - 1) {
+                    ids.extend(chunk.tiles[local_index(TilePos { x, y })]);
+                }
+            }
+        }
+        ids
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.chunks.clear();
+        self.revision += 1;
+    }
+
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub(crate) fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+}
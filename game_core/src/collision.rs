@@ -0,0 +1,250 @@
+//! Circle collision for entities, against each other and against tiles
+//! covered by buildings.
+//!
+//! Movement is integrated in steps no longer than half the entity's radius,
+//! each followed by pushing the circle out of any building tile it overlaps.
+//! The push removes only the part of the motion heading into the wall, so
+//! entities slide along it. Overlapping entity pairs are then separated in a
+//! few passes, always visiting entities in `entities` order so the result is
+//! deterministic. `CollisionRules` decide which types take part.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::TilePos;
+use crate::{EntityType, World};
+
+/// Side length of one grid tile in world units.
+pub const TILE_SIZE: f32 = 32.0;
+
+/// Passes of pairwise separation per physics update.
+pub const COLLISION_ITERATIONS: usize = 4;
+
+/// Which entity types collide with each other and with buildings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionRules {
+    /// Colliding type pairs, smaller type first
+    pairs: BTreeSet<(EntityType, EntityType)>,
+    /// Types that cannot walk through building tiles
+    solid_buildings: BTreeSet<EntityType>,
+}
+
+impl Default for CollisionRules {
+    /// Players and enemies collide with each other and with buildings.
+    /// Projectiles collide with nothing; combat handles their hits.
+    fn default() -> Self {
+        let all = [EntityType::Player, EntityType::Enemy];
+        let mut rules = Self::none();
+        for a in all {
+            for b in all {
+                rules.set_entities_collide(a, b, true);
+            }
+            rules.set_blocked_by_buildings(a, true);
+        }
+        rules
+    }
+}
+
+impl CollisionRules {
+    /// Rules under which nothing collides.
+    pub fn none() -> Self {
+        Self {
+            pairs: BTreeSet::new(),
+            solid_buildings: BTreeSet::new(),
+        }
+    }
+
+    pub fn entities_collide(&self, a: EntityType, b: EntityType) -> bool {
+        self.pairs.contains(&ordered(a, b))
+    }
+
+    pub fn set_entities_collide(&mut self, a: EntityType, b: EntityType, collide: bool) {
+        if collide {
+            self.pairs.insert(ordered(a, b));
+        } else {
+            self.pairs.remove(&ordered(a, b));
+        }
+    }
+
+    pub fn blocked_by_buildings(&self, ty: EntityType) -> bool {
+        self.solid_buildings.contains(&ty)
+    }
+
+    pub fn set_blocked_by_buildings(&mut self, ty: EntityType, blocked: bool) {
+        if blocked {
+            self.solid_buildings.insert(ty);
+        } else {
+            self.solid_buildings.remove(&ty);
+        }
+    }
+}
+
+fn ordered(a: EntityType, b: EntityType) -> (EntityType, EntityType) {
+    (a.min(b), a.max(b))
+}
+
+/// The tile containing world point (x, y).
+pub fn tile_at(x: f32, y: f32) -> TilePos {
+    TilePos {
+        x: (x / TILE_SIZE).floor() as i32,
+        y: (y / TILE_SIZE).floor() as i32,
+    }
+}
+
+impl World {
+    /// Whether a circle at (x, y) overlaps any tile covered by a building.
+    pub fn circle_hits_buildings(&self, x: f32, y: f32, radius: f32) -> bool {
+        tiles_under(x, y, radius)
+            .any(|t| self.grid.tile_occupant(t).is_some() && tile_push(t, x, y, radius).is_some())
+    }
+
+    /// Move entity `slot` by its velocity for `dt` seconds, sliding along
+    /// building tiles if its type is blocked by them.
+    pub(crate) fn move_entity(&mut self, slot: usize, dt: f32) {
+        let e = &self.entities[slot];
+        let (dx, dy) = (e.velocity.vx * dt, e.velocity.vy * dt);
+        if !self.collision.blocked_by_buildings(e.ty) {
+            let e = &mut self.entities[slot];
+            e.transform.x += dx;
+            e.transform.y += dy;
+            return;
+        }
+        let max_step = (e.radius * 0.5).max(1.0);
+        let steps = ((dx.hypot(dy) / max_step).ceil() as u32).max(1);
+        let (sx, sy) = (dx / steps as f32, dy / steps as f32);
+        for _ in 0..steps {
+            let e = &mut self.entities[slot];
+            e.transform.x += sx;
+            e.transform.y += sy;
+            self.push_out_of_buildings(slot);
+        }
+    }
+
+    /// Push entity `slot` out of every building tile it overlaps, nearest
+    /// tiles first, and drop the velocity heading into them.
+    fn push_out_of_buildings(&mut self, slot: usize) {
+        let e = &self.entities[slot];
+        let (x, y, r) = (e.transform.x, e.transform.y, e.radius);
+        let mut solid: Vec<TilePos> = tiles_under(x, y, r)
+            .filter(|&t| self.grid.tile_occupant(t).is_some())
+            .collect();
+        solid.sort_by(|a, b| {
+            tile_distance(*a, x, y)
+                .total_cmp(&tile_distance(*b, x, y))
+                .then(a.cmp(b))
+        });
+        let e = &mut self.entities[slot];
+        for t in solid {
+            let Some((nx, ny, depth)) = tile_push(t, e.transform.x, e.transform.y, e.radius) else {
+                continue;
+            };
+            e.transform.x += nx * depth;
+            e.transform.y += ny * depth;
+            let into = e.velocity.vx * nx + e.velocity.vy * ny;
+            if into < 0.0 {
+                e.velocity.vx -= nx * into;
+                e.velocity.vy -= ny * into;
+            }
+        }
+    }
+
+    /// Separate overlapping entities whose types collide. Each pair moves
+    /// apart by half the overlap; pairs at the same point split along x.
+    pub(crate) fn resolve_entity_overlaps(&mut self) {
+        let max_radius = self.max_entity_radius();
+        for _ in 0..COLLISION_ITERATIONS {
+            self.reindex();
+            let mut moved = false;
+            for i in 0..self.entities.len() {
+                let (x, y, r) = {
+                    let e = &self.entities[i];
+                    (e.transform.x, e.transform.y, e.radius)
+                };
+                let reach = r + max_radius;
+                for j in self.slots_in_box((x - reach, y - reach), (x + reach, y + reach)) {
+                    if j <= i {
+                        continue;
+                    }
+                    moved |= self.separate(i, j);
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+        self.reindex();
+    }
+
+    /// Push entities `i` and `j` apart if they overlap and collide.
+    fn separate(&mut self, i: usize, j: usize) -> bool {
+        let (a, b) = (&self.entities[i], &self.entities[j]);
+        if !self.collision.entities_collide(a.ty, b.ty) {
+            return false;
+        }
+        let (dx, dy) = (b.transform.x - a.transform.x, b.transform.y - a.transform.y);
+        let dist = dx.hypot(dy);
+        let overlap = a.radius + b.radius - dist;
+        if overlap <= 0.0 {
+            return false;
+        }
+        let (nx, ny) = if dist > f32::EPSILON {
+            (dx / dist, dy / dist)
+        } else {
+            (1.0, 0.0)
+        };
+        let half = overlap * 0.5;
+        for (slot, sign) in [(i, -1.0), (j, 1.0)] {
+            let e = &mut self.entities[slot];
+            e.transform.x += sign * nx * half;
+            e.transform.y += sign * ny * half;
+            if self.collision.blocked_by_buildings(self.entities[slot].ty) {
+                self.push_out_of_buildings(slot);
+            }
+        }
+        true
+    }
+}
+
+/// Tiles overlapping the bounding box of a circle, row by row.
+fn tiles_under(x: f32, y: f32, r: f32) -> impl Iterator<Item = TilePos> {
+    let (lo, hi) = (tile_at(x - r, y - r), tile_at(x + r, y + r));
+    (lo.y..=hi.y).flat_map(move |ty| (lo.x..=hi.x).map(move |tx| TilePos { x: tx, y: ty }))
+}
+
+/// Nearest point of tile `t` to (x, y) and the distance to it.
+fn tile_closest(t: TilePos, x: f32, y: f32) -> (f32, f32, f32) {
+    let (left, top) = (t.x as f32 * TILE_SIZE, t.y as f32 * TILE_SIZE);
+    let cx = x.clamp(left, left + TILE_SIZE);
+    let cy = y.clamp(top, top + TILE_SIZE);
+    (cx, cy, (x - cx).hypot(y - cy))
+}
+
+fn tile_distance(t: TilePos, x: f32, y: f32) -> f32 {
+    tile_closest(t, x, y).2
+}
+
+/// Unit direction and distance that move a circle at (x, y) clear of tile
+/// `t`, or `None` if they do not overlap.
+fn tile_push(t: TilePos, x: f32, y: f32, r: f32) -> Option<(f32, f32, f32)> {
+    let (cx, cy, dist) = tile_closest(t, x, y);
+    if dist >= r {
+        return None;
+    }
+    if dist > f32::EPSILON {
+        return Some(((x - cx) / dist, (y - cy) / dist, r - dist));
+    }
+    // centre inside the tile: leave through the nearest edge
+    let (left, top) = (t.x as f32 * TILE_SIZE, t.y as f32 * TILE_SIZE);
+    let exits = [
+        (x - left, (-1.0, 0.0)),
+        (left + TILE_SIZE - x, (1.0, 0.0)),
+        (y - top, (0.0, -1.0)),
+        (top + TILE_SIZE - y, (0.0, 1.0)),
+    ];
+    let (gap, (nx, ny)) = exits
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap_or((0.0, (1.0, 0.0)));
+    Some((nx, ny, gap + r))
+}
@@ -0,0 +1,212 @@
+//! Health, projectiles and damage.
+//!
+//! Projectiles are ordinary entities of type `Projectile` carrying a
+//! `Projectile` component. `World::tick_combat` runs after `update_physics`:
+//! it checks each projectile against the entities around it, applies damage,
+//! applies contact damage from enemies touching the player, and despawns
+//! whatever died or expired. Everything that happened is returned as
+//! `CombatEvent`s in the order it happened.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Entity, EntityId, EntityType, Transform, Velocity, World};
+
+/// Hit points of a newly spawned player.
+pub const PLAYER_HEALTH: f32 = 100.0;
+/// Hit points of a newly spawned enemy.
+pub const ENEMY_HEALTH: f32 = 50.0;
+/// Damage per second an enemy deals while touching the player.
+pub const ENEMY_CONTACT_DPS: f32 = 20.0;
+
+/// Projectile speed in world units per second.
+pub const PROJECTILE_SPEED: f32 = 600.0;
+/// Seconds a projectile flies before it disappears.
+pub const PROJECTILE_LIFETIME: f32 = 1.0;
+pub const PROJECTILE_DAMAGE: f32 = 25.0;
+pub const PROJECTILE_RADIUS: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Projectile {
+    /// Entity that fired it; never hit by its own shots
+    pub owner: EntityId,
+    pub damage: f32,
+    /// Seconds left before it disappears
+    pub remaining: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CombatEvent {
+    /// `target` lost `damage` hit points at (x, y)
+    Damaged {
+        target: EntityId,
+        damage: f32,
+        x: f32,
+        y: f32,
+    },
+    /// `id` ran out of health and was removed
+    Died {
+        id: EntityId,
+        ty: EntityType,
+        x: f32,
+        y: f32,
+    },
+}
+
+impl World {
+    /// Fire a projectile from entity `shooter` toward world point (x, y).
+    /// `None` if the shooter does not exist or the point is its centre.
+    pub fn fire_projectile(&mut self, shooter: EntityId, x: f32, y: f32) -> Option<EntityId> {
+        let from = self.entities.iter().find(|e| e.id == shooter)?;
+        let (dx, dy) = (x - from.transform.x, y - from.transform.y);
+        let len = dx.hypot(dy);
+        if len <= f32::EPSILON {
+            return None;
+        }
+        let (nx, ny) = (dx / len, dy / len);
+        // start just clear of the shooter so it does not hit itself
+        let offset = from.radius + PROJECTILE_RADIUS + 1.0;
+        let transform = Transform {
+            x: from.transform.x + nx * offset,
+            y: from.transform.y + ny * offset,
+        };
+        let id = self.alloc_id();
+        self.push_entity(Entity {
+            id,
+            ty: EntityType::Projectile,
+            transform,
+            velocity: Velocity {
+                vx: nx * PROJECTILE_SPEED,
+                vy: ny * PROJECTILE_SPEED,
+            },
+            radius: PROJECTILE_RADIUS,
+            inventory: None,
+            health: None,
+            projectile: Some(Projectile {
+                owner: shooter,
+                damage: PROJECTILE_DAMAGE,
+                remaining: PROJECTILE_LIFETIME,
+            }),
+        });
+        Some(id)
+    }
+
+    /// Resolve hits, contact damage and deaths for one tick of `dt` seconds.
+    pub fn tick_combat(&mut self, dt: f32) -> Vec<CombatEvent> {
+        let mut events = Vec::new();
+        let mut gone = vec![false; self.entities.len()];
+
+        for p in 0..self.entities.len() {
+            let Some(shot) = self.entities[p].projectile else {
+                continue;
+            };
+            let (px, py, pr) = {
+                let e = &self.entities[p];
+                (e.transform.x, e.transform.y, e.radius)
+            };
+            let remaining = shot.remaining - dt;
+            if remaining <= 0.0 || self.circle_hits_buildings(px, py, pr) {
+                gone[p] = true;
+                continue;
+            }
+            if let Some(projectile) = &mut self.entities[p].projectile {
+                projectile.remaining = remaining;
+            }
+            let reach = pr + self.max_entity_radius();
+            let target = self
+                .slots_in_box((px - reach, py - reach), (px + reach, py + reach))
+                .into_iter()
+                .find(|&t| {
+                    let e = &self.entities[t];
+                    !gone[t]
+                        && e.id != shot.owner
+                        && e.health.is_some_and(|h| !h.is_dead())
+                        && (e.transform.x - px).hypot(e.transform.y - py) <= pr + e.radius
+                });
+            if let Some(t) = target {
+                gone[p] = true;
+                self.damage(t, shot.damage, &mut events);
+            }
+        }
+
+        // enemies hurt the player they are touching
+        if let Some(player) = self.player_slot() {
+            let (x, y, r) = {
+                let e = &self.entities[player];
+                (e.transform.x, e.transform.y, e.radius)
+            };
+            let reach = r + self.max_entity_radius() + 1.0;
+            let touching = self
+                .slots_in_box((x - reach, y - reach), (x + reach, y + reach))
+                .into_iter()
+                .filter(|&t| {
+                    let e = &self.entities[t];
+                    !gone[t]
+                        && e.ty == EntityType::Enemy
+                        && (e.transform.x - x).hypot(e.transform.y - y) <= r + e.radius + 1.0
+                })
+                .count();
+            if touching > 0 {
+                self.damage(
+                    player,
+                    ENEMY_CONTACT_DPS * dt * touching as f32,
+                    &mut events,
+                );
+            }
+        }
+
+        for (slot, e) in self.entities.iter().enumerate() {
+            if e.health.is_some_and(|h| h.is_dead()) {
+                gone[slot] = true;
+                events.push(CombatEvent::Died {
+                    id: e.id,
+                    ty: e.ty,
+                    x: e.transform.x,
+                    y: e.transform.y,
+                });
+            }
+        }
+        if gone.iter().any(|&g| g) {
+            let mut slot = 0;
+            self.entities.retain(|_| {
+                slot += 1;
+                !gone[slot - 1]
+            });
+            self.reindex();
+        }
+        events
+    }
+
+    fn damage(&mut self, slot: usize, amount: f32, events: &mut Vec<CombatEvent>) {
+        let e = &mut self.entities[slot];
+        let Some(health) = &mut e.health else {
+            return;
+        };
+        health.current -= amount;
+        events.push(CombatEvent::Damaged {
+            target: e.id,
+            damage: amount,
+            x: e.transform.x,
+            y: e.transform.y,
+        });
+    }
+
+    pub(crate) fn max_entity_radius(&self) -> f32 {
+        self.entities.iter().map(|e| e.radius).fold(0.0, f32::max)
+    }
+}
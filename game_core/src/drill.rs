@@ -0,0 +1,172 @@
+//! Mining drills: extract ore from deposits under their footprint and push it
+//! out of their front face.
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, Rotation, TileGrid, TilePos};
+use crate::item::ItemId;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrillStatus {
+    Working,
+    /// Mined item is waiting for room in front of the drill
+    OutputBlocked,
+    /// No ore left under the footprint; the drill has stopped
+    Depleted,
+}
+
+/// Per-instance mining state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Drill {
+    /// Ticks between extracted units
+    pub interval: u32,
+    timer: u32,
+    /// Mined item not yet handed off
+    held: Option<ItemId>,
+    status: DrillStatus,
+}
+
+impl Drill {
+    pub fn new(interval: u32) -> Self {
+        Self {
+            interval: interval.max(1),
+            timer: 0,
+            held: None,
+            status: DrillStatus::Working,
+        }
+    }
+
+    pub fn status(&self) -> DrillStatus {
+        self.status
+    }
+
+    pub fn held(&self) -> Option<ItemId> {
+        self.held
+    }
+}
+
+impl TileGrid {
+    pub fn drill(&self, id: InstanceId) -> Option<&Drill> {
+        self.drills.get(&id)
+    }
+
+    /// Every drill, in id order.
+    pub fn drills(&self) -> impl Iterator<Item = (InstanceId, &Drill)> {
+        self.drills.iter().map(|(&id, d)| (id, d))
+    }
+
+    /// Remove up to one unit from the deposit at `pos`, clearing the deposit
+    /// once it runs out. Returns the item mined.
+    pub fn take_from_deposit(&mut self, pos: TilePos) -> Option<ItemId> {
+        let tile = self.terrain.get_mut(&pos)?;
+        let deposit = tile.deposit.as_mut()?;
+        if deposit.amount == 0 {
+            tile.deposit = None;
+            return None;
+        }
+        let item = deposit.item;
+        deposit.amount -= 1;
+        if deposit.amount == 0 {
+            tile.deposit = None;
+        }
+        Some(item)
+    }
+
+    /// Tile a multi-tile building outputs into: just past its front edge,
+    /// aligned with the middle of that edge (rounding toward the origin).
+    pub fn output_tile(&self, id: InstanceId) -> Option<TilePos> {
+        let inst = self.instances.get(&id)?;
+        let size = self.specs().get(inst.spec_id)?.size.rotated(inst.rotation);
+        let (w, h) = (size.w as i32, size.h as i32);
+        let o = inst.origin;
+        let (mx, my) = ((w - 1) / 2, (h - 1) / 2);
+        Some(match inst.rotation {
+            Rotation::R0 => TilePos {
+                x: o.x + w,
+                y: o.y + my,
+            },
+            Rotation::R90 => TilePos {
+                x: o.x + mx,
+                y: o.y + h,
+            },
+            Rotation::R180 => TilePos {
+                x: o.x - 1,
+                y: o.y + my,
+            },
+            Rotation::R270 => TilePos {
+                x: o.x + mx,
+                y: o.y - 1,
+            },
+        })
+    }
+
+    /// Hand `item` to whatever stands on `pos`: a belt entry or a building
+    /// that stores items.
+    fn push_to_tile(&mut self, pos: TilePos, item: ItemId) -> bool {
+        let Some(target) = self.tile_occupant(pos) else {
+            return false;
+        };
+        if self.belts.contains_key(&target) {
+            return self.belt_insert(target, item);
+        }
+        self.offer_item(target, item).unwrap_or(false)
+    }
+
+    /// Advance every drill by one tick. Underpowered drills skip ticks.
+    /// Returns each unit mined, with the drill that mined it.
+    pub fn tick_drills(&mut self) -> Vec<(InstanceId, ItemId)> {
+        let mut mined_by = Vec::new();
+        let ids: Vec<InstanceId> = self.drills.keys().copied().collect();
+        for id in ids {
+            if !self.draw_power(id) {
+                continue;
+            }
+            let Some(drill) = self.drills.get_mut(&id) else {
+                continue;
+            };
+            if drill.status == DrillStatus::Depleted {
+                continue;
+            }
+
+            if drill.held.is_none() {
+                drill.timer += 1;
+                if drill.timer < drill.interval {
+                    continue;
+                }
+                drill.timer = 0;
+                let mined = self
+                    .footprint(id)
+                    .into_iter()
+                    .flatten()
+                    .find_map(|pos| self.take_from_deposit(pos));
+                let Some(drill) = self.drills.get_mut(&id) else {
+                    continue;
+                };
+                match mined {
+                    Some(item) => {
+                        drill.held = Some(item);
+                        mined_by.push((id, item));
+                    }
+                    None => {
+                        drill.status = DrillStatus::Depleted;
+                        continue;
+                    }
+                }
+            }
+
+            let item = self.drills[&id].held.expect("drill holds an item here");
+            let delivered = self
+                .output_tile(id)
+                .is_some_and(|pos| self.push_to_tile(pos, item));
+            if let Some(drill) = self.drills.get_mut(&id) {
+                if delivered {
+                    drill.held = None;
+                    drill.status = DrillStatus::Working;
+                } else {
+                    drill.status = DrillStatus::OutputBlocked;
+                }
+            }
+        }
+        mined_by
+    }
+}
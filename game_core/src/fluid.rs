@@ -0,0 +1,293 @@
+//! Fluids: fluid boxes on buildings, adjacency links between them, pumps and
+//! the flow simulation.
+//!
+//! Every building whose spec has a `fluid_capacity` owns one fluid box and
+//! connects to any orthogonally adjacent building that also has one. Pressure
+//! is a box's fill fraction; each tick fluid moves from fuller to emptier
+//! boxes. Flow uses integer amounts and is computed from the state at the
+//! start of the tick, so the result does not depend on iteration order.
+//! Rounding leaves neighbours settled a few units apart rather than equal.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, Rotation, TileGrid, TilePos};
+use crate::item::ItemId;
+use crate::spec::{BuildingKind, SpecId};
+use crate::terrain::TerrainKind;
+
+/// Name of the fluid item pumps extract from water tiles.
+pub const WATER_FLUID: &str = "water";
+
+/// Fluid storage attached to a building. Holds at most one fluid at a time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FluidBox {
+    pub capacity: u32,
+    /// Only this fluid may enter when set
+    pub filter: Option<ItemId>,
+    fluid: Option<ItemId>,
+    amount: u32,
+}
+
+impl FluidBox {
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            filter: None,
+            fluid: None,
+            amount: 0,
+        }
+    }
+
+    pub fn fluid(&self) -> Option<ItemId> {
+        self.fluid
+    }
+
+    pub fn amount(&self) -> u32 {
+        self.amount
+    }
+
+    /// Whether `fluid` may enter this box now.
+    pub fn accepts(&self, fluid: ItemId) -> bool {
+        self.filter.is_none_or(|f| f == fluid) && self.fluid.is_none_or(|f| f == fluid)
+    }
+
+    /// Add up to `amount` of `fluid`. Returns how much went in.
+    pub fn insert(&mut self, fluid: ItemId, amount: u32) -> u32 {
+        if !self.accepts(fluid) {
+            return 0;
+        }
+        let added = amount.min(self.capacity - self.amount);
+        if added > 0 {
+            self.fluid = Some(fluid);
+            self.amount += added;
+        }
+        added
+    }
+
+    /// Remove up to `amount`. Returns how much came out.
+    pub fn extract(&mut self, amount: u32) -> u32 {
+        let taken = amount.min(self.amount);
+        self.amount -= taken;
+        if self.amount == 0 {
+            self.fluid = None;
+        }
+        taken
+    }
+}
+
+/// Per-instance state for `Pump` specs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pump {
+    /// Fluid units extracted per tick
+    pub rate: u32,
+}
+
+impl TileGrid {
+    pub fn fluid_box(&self, id: InstanceId) -> Option<&FluidBox> {
+        self.fluid_boxes.get(&id)
+    }
+
+    pub fn fluid_box_mut(&mut self, id: InstanceId) -> Option<&mut FluidBox> {
+        self.fluid_boxes.get_mut(&id)
+    }
+
+    pub fn pump(&self, id: InstanceId) -> Option<&Pump> {
+        self.pumps.get(&id)
+    }
+
+    /// Pairs of connected fluid boxes, lower id first.
+    pub fn fluid_links(&self) -> impl Iterator<Item = (InstanceId, InstanceId)> + '_ {
+        self.fluid_links
+            .iter()
+            .flat_map(|(&a, ns)| ns.range(a + 1..).map(move |&b| (a, b)))
+    }
+
+    /// Instances with fluid boxes touching the given footprint from outside.
+    fn fluid_neighbours(&self, tiles: &[TilePos]) -> BTreeSet<InstanceId> {
+        let mut out = BTreeSet::new();
+        for &t in tiles {
+            for rot in [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270] {
+                let n = t.step(rot);
+                if tiles.contains(&n) {
+                    continue;
+                }
+                if let Some(id) = self.tile_occupant(n) {
+                    if self.fluid_boxes.contains_key(&id) {
+                        out.insert(id);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Every fluid box connected to `start`, including itself.
+    pub fn fluid_network(&self, start: InstanceId) -> BTreeSet<InstanceId> {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            for n in self.fluid_links.get(&id).into_iter().flatten() {
+                if !seen.contains(n) {
+                    stack.push(*n);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Fluids a network is committed to: its contents and any box filters.
+    fn network_fluids(&self, members: &BTreeSet<InstanceId>) -> BTreeSet<ItemId> {
+        members
+            .iter()
+            .filter_map(|id| self.fluid_boxes.get(id))
+            .flat_map(|b| b.fluid.into_iter().chain(b.filter))
+            .collect()
+    }
+
+    /// Whether the rest of `id`'s network could hold `fluid` alongside it.
+    pub(crate) fn fluid_fits(&self, id: InstanceId, fluid: ItemId) -> bool {
+        let mut members = self.fluid_network(id);
+        members.remove(&id);
+        self.network_fluids(&members).iter().all(|&f| f == fluid)
+    }
+
+    /// Fluid a newly placed building of `kind` is fixed to, if any.
+    fn placed_fluid(&self, kind: BuildingKind) -> Option<ItemId> {
+        match kind {
+            BuildingKind::Pump { .. } => self.items.by_name(WATER_FLUID).map(|d| d.item_id),
+            _ => None,
+        }
+    }
+
+    /// Two different fluids that placing `spec_id` here would connect.
+    pub(crate) fn fluid_conflict(
+        &self,
+        spec_id: SpecId,
+        origin: TilePos,
+        rot: Rotation,
+    ) -> Option<(ItemId, ItemId)> {
+        let spec = self.specs().get(spec_id)?;
+        if spec.fluid_capacity == 0 {
+            return None;
+        }
+        let tiles = Self::footprint_tiles(spec.size, origin, rot);
+        let mut members = BTreeSet::new();
+        for n in self.fluid_neighbours(&tiles) {
+            if !members.contains(&n) {
+                members.extend(self.fluid_network(n));
+            }
+        }
+        let mut fluids = self.network_fluids(&members);
+        fluids.extend(self.placed_fluid(spec.kind));
+        let mut it = fluids.into_iter();
+        Some((it.next()?, it.next()?))
+    }
+
+    /// Hook for `TileGrid::place`: attach a fluid box and link it to its
+    /// neighbours.
+    pub(crate) fn fluid_on_place(&mut self, id: InstanceId, capacity: u32, kind: BuildingKind) {
+        if let BuildingKind::Pump { rate } = kind {
+            self.pumps.insert(id, Pump { rate });
+        }
+        if capacity == 0 {
+            return;
+        }
+        let mut fluid_box = FluidBox::new(capacity);
+        fluid_box.filter = self.placed_fluid(kind);
+        self.fluid_boxes.insert(id, fluid_box);
+        let tiles = self.footprint(id).unwrap_or_default();
+        for n in self.fluid_neighbours(&tiles) {
+            self.fluid_links.entry(id).or_default().insert(n);
+            self.fluid_links.entry(n).or_default().insert(id);
+        }
+    }
+
+    /// Hook for `TileGrid::remove`. Fluid in the box is lost.
+    pub(crate) fn fluid_on_remove(&mut self, id: InstanceId) {
+        self.pumps.remove(&id);
+        self.fluid_boxes.remove(&id);
+        for n in self.fluid_links.remove(&id).unwrap_or_default() {
+            if let Some(ns) = self.fluid_links.get_mut(&n) {
+                ns.remove(&id);
+            }
+        }
+    }
+
+    /// Run pumps, then move fluid along every link toward lower pressure.
+    pub fn tick_fluids(&mut self) {
+        let pumps: Vec<(InstanceId, u32)> =
+            self.pumps.iter().map(|(id, p)| (*id, p.rate)).collect();
+        for (id, rate) in pumps {
+            let Some(inst) = self.instances.get(&id) else {
+                continue;
+            };
+            let intake = inst.origin.step(inst.rotation.opposite());
+            if self.terrain(intake).kind != TerrainKind::Water {
+                continue;
+            }
+            if let Some(fluid_box) = self.fluid_boxes.get_mut(&id) {
+                if let Some(water) = fluid_box.filter {
+                    fluid_box.insert(water, rate);
+                }
+            }
+        }
+
+        let degree = |id: InstanceId| self.fluid_links.get(&id).map_or(0, |ns| ns.len() as i64);
+
+        // (from, to, fluid, amount), all computed from the start-of-tick state
+        let mut transfers = Vec::new();
+        for (a, b) in self.fluid_links() {
+            let (Some(ba), Some(bb)) = (self.fluid_boxes.get(&a), self.fluid_boxes.get(&b)) else {
+                continue;
+            };
+            let (ca, cb) = (ba.capacity as i64, bb.capacity as i64);
+            if ca + cb == 0 {
+                continue;
+            }
+            // amount that would leave both boxes equally full
+            let equalize = (ba.amount as i64 * cb - bb.amount as i64 * ca) / (ca + cb);
+            let (from, to, src, dst) = if equalize > 0 {
+                (a, b, ba, bb)
+            } else {
+                (b, a, bb, ba)
+            };
+            let Some(fluid) = src.fluid else {
+                continue;
+            };
+            if !dst.accepts(fluid) {
+                continue;
+            }
+            // dividing by the busier endpoint's link count keeps every box
+            // within 0..=capacity however many neighbours it has
+            let share = degree(a).max(degree(b)) + 1;
+            let amount = equalize.abs() / share;
+            if amount > 0 {
+                transfers.push((from, to, fluid, amount as u32));
+            }
+        }
+
+        // apply net changes so no box is clamped part way through
+        let mut delta: BTreeMap<InstanceId, (i64, Option<ItemId>)> = BTreeMap::new();
+        for (from, to, fluid, amount) in transfers {
+            delta.entry(from).or_insert((0, None)).0 -= amount as i64;
+            let d = delta.entry(to).or_insert((0, None));
+            d.0 += amount as i64;
+            d.1 = Some(fluid);
+        }
+        for (id, (change, incoming)) in delta {
+            if let Some(b) = self.fluid_boxes.get_mut(&id) {
+                b.amount = (b.amount as i64 + change).clamp(0, b.capacity as i64) as u32;
+                b.fluid = if b.amount == 0 {
+                    None
+                } else {
+                    b.fluid.or(incoming)
+                };
+            }
+        }
+    }
+}
@@ -1,19 +1,95 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::belt::Belt;
+use crate::chunk::Occupancy;
+use crate::drill::Drill;
+use crate::fluid::{FluidBox, Pump};
+use crate::inserter::Inserter;
+use crate::item::{Inventory, ItemId, ItemRegistry};
+use crate::machine::Machine;
+use crate::power::PowerGrid;
+use crate::recipe::RecipeRegistry;
+use crate::research::{Lab, Research, TechTree};
+use crate::spec::{BuildingKind, BuildingSpec, SpecError, SpecId, SpecRegistry};
+use crate::terrain::TerrainTile;
 
 /// Simple integer tile position (origin top-left)
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TilePos {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Size2 {
     pub w: u32,
     pub h: u32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+impl Size2 {
+    /// Footprint size after applying `rot` (90/270 swap width and height).
+    pub fn rotated(self, rot: Rotation) -> Size2 {
+        match rot {
+            Rotation::R0 | Rotation::R180 => self,
+            Rotation::R90 | Rotation::R270 => Size2 {
+                w: self.h,
+                h: self.w,
+            },
+        }
+    }
+}
+
+/// Inclusive rectangle of tiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileRect {
+    pub min: TilePos,
+    pub max: TilePos,
+}
+
+impl TileRect {
+    /// The rectangle spanned by two corner tiles given in any order.
+    pub fn spanning(a: TilePos, b: TilePos) -> TileRect {
+        TileRect {
+            min: TilePos {
+                x: a.x.min(b.x),
+                y: a.y.min(b.y),
+            },
+            max: TilePos {
+                x: a.x.max(b.x),
+                y: a.y.max(b.y),
+            },
+        }
+    }
+
+    pub fn contains(&self, pos: TilePos) -> bool {
+        pos.x >= self.min.x && pos.x <= self.max.x && pos.y >= self.min.y && pos.y <= self.max.y
+    }
+
+    pub fn width(&self) -> u32 {
+        (self.max.x - self.min.x + 1) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.max.y - self.min.y + 1) as u32
+    }
+}
+
+impl TilePos {
+    /// The neighbouring tile in the direction `rot` faces.
+    pub fn step(self, rot: Rotation) -> TilePos {
+        let (dx, dy) = rot.offset();
+        TilePos {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+}
+
+/// Facing of a building. R0 faces east (+x); rotations are clockwise
+/// because y grows downward.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Rotation {
     R0,
     R90,
@@ -21,18 +97,68 @@ pub enum Rotation {
     R270,
 }
 
+impl Rotation {
+    /// Unit tile offset of the facing direction.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Rotation::R0 => (1, 0),
+            Rotation::R90 => (0, 1),
+            Rotation::R180 => (-1, 0),
+            Rotation::R270 => (0, -1),
+        }
+    }
+
+    /// This facing turned clockwise by `by`.
+    pub fn turned(self, by: Rotation) -> Rotation {
+        const ALL: [Rotation; 4] = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270];
+        ALL[(self.quarter_turns() + by.quarter_turns()) % 4]
+    }
+
+    /// Clockwise quarter turns from `R0`.
+    pub fn quarter_turns(self) -> usize {
+        match self {
+            Rotation::R0 => 0,
+            Rotation::R90 => 1,
+            Rotation::R180 => 2,
+            Rotation::R270 => 3,
+        }
+    }
+
+    pub fn opposite(self) -> Rotation {
+        match self {
+            Rotation::R0 => Rotation::R180,
+            Rotation::R90 => Rotation::R270,
+            Rotation::R180 => Rotation::R0,
+            Rotation::R270 => Rotation::R90,
+        }
+    }
+}
+
 pub type InstanceId = u64;
 
-#[derive(Clone, Debug)]
-pub struct BuildingSpec {
-    pub spec_id: u32,
-    pub size: Size2,
+/// Most building changes kept for `take_building_changes`; older ones are
+/// dropped when nobody collects them.
+pub const MAX_BUILDING_CHANGES: usize = 4096;
+
+/// A building appearing on or disappearing from the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildingChange {
+    Placed {
+        id: InstanceId,
+        spec_id: SpecId,
+        origin: TilePos,
+    },
+    Removed {
+        id: InstanceId,
+        spec_id: SpecId,
+        origin: TilePos,
+    },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BuildingInstance {
     pub id: InstanceId,
-    pub spec_id: u32,
+    pub spec_id: SpecId,
     pub origin: TilePos,
     pub rotation: Rotation,
 }
@@ -41,56 +167,229 @@ pub struct BuildingInstance {
 pub enum PlacementError {
     OutOfBounds,
     Occupied,
+    /// A footprint tile is water, cliff or other unbuildable terrain
+    BlockedTerrain,
+    UnknownSpec(SpecId),
+    /// The building would connect fluid networks holding these two fluids
+    MixedFluids(ItemId, ItemId),
+    /// `TileGrid::restore` was given an id that is still placed
+    IdInUse(InstanceId),
+    /// The spec needs a technology that is not researched yet
+    Locked(SpecId),
 }
 
+/// A building taken off the grid together with its per-instance state, so
+/// `TileGrid::restore` can put it back exactly, under the same id.
+#[derive(Clone, Debug)]
+pub struct RemovedBuilding {
+    pub instance: BuildingInstance,
+    belt: Option<Belt>,
+    inventory: Option<Inventory>,
+    machine: Option<Machine>,
+    drill: Option<Drill>,
+    inserter: Option<Inserter>,
+    fluid_box: Option<FluidBox>,
+    lab: Option<Lab>,
+}
+
+/// Serialized form omits `occupancy` and the data registries; loading
+/// rebuilds occupancy from `instances` and takes specs, items, recipes and
+/// technologies from the built-in data (see `World::load_binary`).
+#[derive(Serialize, Deserialize)]
 pub struct TileGrid {
-    pub width: usize,
-    pub height: usize,
-    tiles: Vec<Option<InstanceId>>,
-    pub instances: HashMap<InstanceId, BuildingInstance>,
+    /// Tiles buildings may use; `None` for a map without edges
+    bounds: Option<TileRect>,
+    #[serde(skip)]
+    occupancy: Occupancy,
+    /// Ground layer; positions missing from the map are plain ground
+    pub(crate) terrain: BTreeMap<TilePos, TerrainTile>,
+    pub instances: BTreeMap<InstanceId, BuildingInstance>,
+    #[serde(skip)]
+    specs: SpecRegistry,
+    #[serde(skip)]
+    pub(crate) items: ItemRegistry,
+    #[serde(skip)]
+    pub(crate) recipes: RecipeRegistry,
+    #[serde(skip)]
+    pub(crate) techs: TechTree,
+    /// Technologies researched and in progress
+    pub(crate) research: Research,
+    /// Per-instance belt state for conveyor buildings
+    pub(crate) belts: BTreeMap<InstanceId, Belt>,
+    /// Per-instance storage for specs with `inventory_slots`
+    pub(crate) inventories: BTreeMap<InstanceId, Inventory>,
+    /// Per-instance crafting state for `Crafter` specs
+    pub(crate) machines: BTreeMap<InstanceId, Machine>,
+    /// Per-instance mining state for `Drill` specs
+    pub(crate) drills: BTreeMap<InstanceId, Drill>,
+    /// Per-instance arm state for `Inserter` specs
+    pub(crate) inserters: BTreeMap<InstanceId, Inserter>,
+    /// Electric networks and which instances belong to them
+    pub(crate) power: PowerGrid,
+    /// Per-instance fluid storage for specs with `fluid_capacity`
+    pub(crate) fluid_boxes: BTreeMap<InstanceId, FluidBox>,
+    /// Fluid boxes each fluid box is linked to; every link is stored in
+    /// both directions
+    pub(crate) fluid_links: BTreeMap<InstanceId, BTreeSet<InstanceId>>,
+    /// Per-instance state for `Pump` specs
+    pub(crate) pumps: BTreeMap<InstanceId, Pump>,
+    /// Per-instance research state for `Lab` specs
+    pub(crate) labs: BTreeMap<InstanceId, Lab>,
     next_id: InstanceId,
+    /// Placements and removals not yet collected by `take_building_changes`
+    #[serde(skip)]
+    changes: VecDeque<BuildingChange>,
 }
 
 impl TileGrid {
+    /// Create a grid of `width` x `height` tiles starting at (0, 0) that
+    /// knows the built-in building specs.
     pub fn new(width: usize, height: usize) -> Self {
-        let tiles = vec![None; width.saturating_mul(height)];
+        Self::with_specs(width, height, SpecRegistry::builtin())
+    }
+
+    /// Create a bounded grid using a caller-provided spec registry.
+    pub fn with_specs(width: usize, height: usize, specs: SpecRegistry) -> Self {
+        let bounds = TileRect {
+            min: TilePos { x: 0, y: 0 },
+            max: TilePos {
+                x: width.min(i32::MAX as usize) as i32 - 1,
+                y: height.min(i32::MAX as usize) as i32 - 1,
+            },
+        };
+        Self::with_bounds(Some(bounds), specs)
+    }
+
+    /// Create a grid without edges that knows the built-in building specs.
+    pub fn unbounded() -> Self {
+        Self::with_bounds(None, SpecRegistry::builtin())
+    }
+
+    /// Create a grid limited to `bounds`, or without edges for `None`.
+    pub fn with_bounds(bounds: Option<TileRect>, specs: SpecRegistry) -> Self {
         Self {
-            width,
-            height,
-            tiles,
-            instances: HashMap::new(),
+            bounds,
+            occupancy: Occupancy::default(),
+            terrain: BTreeMap::new(),
+            instances: BTreeMap::new(),
+            specs,
+            items: ItemRegistry::builtin(),
+            recipes: RecipeRegistry::builtin(),
+            techs: TechTree::builtin(),
+            research: Research::default(),
+            belts: BTreeMap::new(),
+            inventories: BTreeMap::new(),
+            machines: BTreeMap::new(),
+            drills: BTreeMap::new(),
+            inserters: BTreeMap::new(),
+            power: PowerGrid::default(),
+            fluid_boxes: BTreeMap::new(),
+            fluid_links: BTreeMap::new(),
+            pumps: BTreeMap::new(),
+            labs: BTreeMap::new(),
             next_id: 1,
+            changes: VecDeque::new(),
         }
     }
 
-    fn tile_index(&self, pos: TilePos) -> Option<usize> {
-        if pos.x < 0 || pos.y < 0 {
-            return None;
+    pub fn specs(&self) -> &SpecRegistry {
+        &self.specs
+    }
+
+    /// Add or replace a spec. Returns the previous spec with the same id, if
+    /// any. Specs of placed buildings cannot change.
+    pub fn insert_spec(&mut self, spec: BuildingSpec) -> Result<Option<BuildingSpec>, SpecError> {
+        if self.instances.values().any(|i| i.spec_id == spec.spec_id) {
+            return Err(SpecError::InUse(spec.spec_id));
         }
-        let x = pos.x as usize;
-        let y = pos.y as usize;
-        if x >= self.width || y >= self.height {
-            return None;
+        Ok(self.specs.insert(spec))
+    }
+
+    pub fn items(&self) -> &ItemRegistry {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut ItemRegistry {
+        &mut self.items
+    }
+
+    pub fn inventory(&self, id: InstanceId) -> Option<&Inventory> {
+        self.inventories.get(&id)
+    }
+
+    pub fn inventory_mut(&mut self, id: InstanceId) -> Option<&mut Inventory> {
+        self.inventories.get_mut(&id)
+    }
+
+    /// Try to hand a single item to building `id`.
+    /// Returns `None` if the building has nowhere to put items at all,
+    /// otherwise whether it accepted this one.
+    pub fn offer_item(&mut self, id: InstanceId, item: ItemId) -> Option<bool> {
+        if let Some(accepted) = self.offer_machine_item(id, item) {
+            return Some(accepted);
+        }
+        if let Some(accepted) = self.offer_lab_item(id, item) {
+            return Some(accepted);
         }
-        Some(y * self.width + x)
+        let inv = self.inventories.get_mut(&id)?;
+        Some(inv.insert(&self.items, item, 1) == 0)
+    }
+
+    pub fn bounds(&self) -> Option<TileRect> {
+        self.bounds
+    }
+
+    /// Whether buildings may cover `pos`. Always true without bounds.
+    pub fn in_bounds(&self, pos: TilePos) -> bool {
+        self.bounds.is_none_or(|b| b.contains(pos))
+    }
+
+    /// Number of chunks currently holding buildings.
+    pub fn chunk_count(&self) -> usize {
+        self.occupancy.chunk_count()
+    }
+
+    /// Smallest rectangle containing every building, `None` if there are none.
+    pub fn occupied_area(&self) -> Option<TileRect> {
+        let mut tiles = self
+            .instances
+            .keys()
+            .filter_map(|&id| self.footprint(id))
+            .flatten();
+        let first = tiles.next()?;
+        Some(
+            tiles.fold(TileRect::spanning(first, first), |r, t| TileRect {
+                min: TilePos {
+                    x: r.min.x.min(t.x),
+                    y: r.min.y.min(t.y),
+                },
+                max: TilePos {
+                    x: r.max.x.max(t.x),
+                    y: r.max.y.max(t.y),
+                },
+            }),
+        )
     }
 
     pub fn tile_occupant(&self, pos: TilePos) -> Option<InstanceId> {
-        self.tile_index(pos).and_then(|i| self.tiles[i])
+        self.occupancy.get(pos)
     }
 
-    fn rotated_size(size: Size2, rot: Rotation) -> Size2 {
-        match rot {
-            Rotation::R0 | Rotation::R180 => size,
-            Rotation::R90 | Rotation::R270 => Size2 {
-                w: size.h,
-                h: size.w,
-            },
-        }
+    /// Buildings covering at least one tile of `rect`, in id order. Costs
+    /// time in proportion to `rect`, not to the whole map.
+    pub fn instances_in(&self, rect: TileRect) -> Vec<InstanceId> {
+        self.occupancy.ids_in(rect).into_iter().collect()
     }
 
-    fn footprint_tiles(size: Size2, origin: TilePos, rot: Rotation) -> Vec<TilePos> {
-        let rs = Self::rotated_size(size, rot);
+    /// Counter that changes whenever a tile gains or loses a building.
+    /// Compare against a stored value to know when layout-derived caches
+    /// such as paths need rebuilding.
+    pub fn layout_revision(&self) -> u64 {
+        self.occupancy.revision()
+    }
+
+    pub(crate) fn footprint_tiles(size: Size2, origin: TilePos, rot: Rotation) -> Vec<TilePos> {
+        let rs = size.rotated(rot);
         let mut v = Vec::with_capacity((rs.w * rs.h) as usize);
         for dy in 0..(rs.h as i32) {
             for dx in 0..(rs.w as i32) {
@@ -103,82 +402,254 @@ impl TileGrid {
         v
     }
 
-    pub fn can_place(&self, spec: &BuildingSpec, origin: TilePos, rot: Rotation) -> bool {
+    /// Tiles covered by instance `id`.
+    pub fn footprint(&self, id: InstanceId) -> Option<Vec<TilePos>> {
+        let inst = self.instances.get(&id)?;
+        let spec = self.specs.get(inst.spec_id)?;
+        Some(Self::footprint_tiles(spec.size, inst.origin, inst.rotation))
+    }
+
+    pub fn can_place(&self, spec_id: SpecId, origin: TilePos, rot: Rotation) -> bool {
+        let Some(spec) = self.specs.get(spec_id) else {
+            return false;
+        };
         let tiles = Self::footprint_tiles(spec.size, origin, rot);
         for t in tiles {
-            match self.tile_index(t) {
-                Some(idx) => {
-                    if self.tiles[idx].is_some() {
-                        return false;
-                    }
-                }
-                None => return false,
+            if !self.in_bounds(t)
+                || self.occupancy.get(t).is_some()
+                || !self.terrain(t).kind.is_buildable()
+            {
+                return false;
             }
         }
-        true
+        self.fluid_conflict(spec_id, origin, rot).is_none()
     }
 
     pub fn place(
         &mut self,
-        spec: &BuildingSpec,
+        spec_id: SpecId,
         origin: TilePos,
         rot: Rotation,
     ) -> Result<InstanceId, PlacementError> {
-        if !self.can_place(spec, origin, rot) {
-            // determine if out of bounds vs occupied
-            let tiles = Self::footprint_tiles(spec.size, origin, rot);
+        let id = self.next_id;
+        self.place_as(id, spec_id, origin, rot)?;
+        self.next_id = self.next_id.saturating_add(1);
+        Ok(id)
+    }
+
+    /// Remove building `id`, keeping everything needed to restore it.
+    pub fn take(&mut self, id: InstanceId) -> Option<RemovedBuilding> {
+        let taken = RemovedBuilding {
+            instance: self.instances.get(&id)?.clone(),
+            belt: self.belts.get(&id).cloned(),
+            inventory: self.inventories.get(&id).cloned(),
+            machine: self.machines.get(&id).cloned(),
+            drill: self.drills.get(&id).cloned(),
+            inserter: self.inserters.get(&id).cloned(),
+            fluid_box: self.fluid_boxes.get(&id).cloned(),
+            lab: self.labs.get(&id).cloned(),
+        };
+        self.remove(id);
+        Some(taken)
+    }
+
+    /// Put a building from `take` back under its original id, with the
+    /// state it had then. Its position and rotation may have been edited.
+    pub fn restore(&mut self, removed: RemovedBuilding) -> Result<InstanceId, PlacementError> {
+        let inst = removed.instance;
+        if self.instances.contains_key(&inst.id) {
+            return Err(PlacementError::IdInUse(inst.id));
+        }
+        self.place_as(inst.id, inst.spec_id, inst.origin, inst.rotation)?;
+        let id = inst.id;
+        self.next_id = self.next_id.max(id.saturating_add(1));
+        if let Some(belt) = removed.belt {
+            self.belts.insert(id, belt);
+        }
+        if let Some(inventory) = removed.inventory {
+            self.inventories.insert(id, inventory);
+        }
+        if let Some(machine) = removed.machine {
+            self.machines.insert(id, machine);
+        }
+        if let Some(drill) = removed.drill {
+            self.drills.insert(id, drill);
+        }
+        if let Some(inserter) = removed.inserter {
+            self.inserters.insert(id, inserter);
+        }
+        if let Some(lab) = removed.lab {
+            self.labs.insert(id, lab);
+        }
+        // contents that would now mix with a neighbouring network are lost
+        if let Some(fluid_box) = removed.fluid_box {
+            if fluid_box.fluid().is_none_or(|f| self.fluid_fits(id, f)) {
+                self.fluid_boxes.insert(id, fluid_box);
+            }
+        }
+        Ok(id)
+    }
+
+    fn place_as(
+        &mut self,
+        id: InstanceId,
+        spec_id: SpecId,
+        origin: TilePos,
+        rot: Rotation,
+    ) -> Result<(), PlacementError> {
+        let (size, kind, inventory_slots, power, fluid_capacity) = self
+            .specs
+            .get(spec_id)
+            .map(|s| (s.size, s.kind, s.inventory_slots, s.power, s.fluid_capacity))
+            .ok_or(PlacementError::UnknownSpec(spec_id))?;
+        if !self.can_place(spec_id, origin, rot) {
+            // determine if out of bounds vs occupied vs terrain
+            let tiles = Self::footprint_tiles(size, origin, rot);
             for t in tiles {
-                if self.tile_index(t).is_none() {
+                if !self.in_bounds(t) {
                     return Err(PlacementError::OutOfBounds);
                 }
-                if let Some(idx) = self.tile_index(t) {
-                    if self.tiles[idx].is_some() {
-                        return Err(PlacementError::Occupied);
-                    }
+                if self.occupancy.get(t).is_some() {
+                    return Err(PlacementError::Occupied);
+                }
+                if !self.terrain(t).kind.is_buildable() {
+                    return Err(PlacementError::BlockedTerrain);
                 }
             }
+            if let Some((a, b)) = self.fluid_conflict(spec_id, origin, rot) {
+                return Err(PlacementError::MixedFluids(a, b));
+            }
             return Err(PlacementError::Occupied);
         }
-        let id = self.next_id;
-        self.next_id = self.next_id.saturating_add(1);
         let instance = BuildingInstance {
             id,
-            spec_id: spec.spec_id,
+            spec_id,
             origin,
             rotation: rot,
         };
-        let tiles = Self::footprint_tiles(spec.size, origin, rot);
+        let tiles = Self::footprint_tiles(size, origin, rot);
         for t in tiles {
-            if let Some(idx) = self.tile_index(t) {
-                self.tiles[idx] = Some(id);
-            }
+            self.occupancy.set(t, id);
         }
         self.instances.insert(id, instance);
-        Ok(id)
+        self.push_change(BuildingChange::Placed {
+            id,
+            spec_id,
+            origin,
+        });
+        match kind {
+            BuildingKind::Conveyor { speed } => {
+                self.belts.insert(id, Belt::new(speed));
+            }
+            BuildingKind::Crafter {
+                category,
+                auto_select,
+            } => {
+                self.machines
+                    .insert(id, Machine::new(category, auto_select));
+            }
+            BuildingKind::Drill { interval } => {
+                self.drills.insert(id, Drill::new(interval));
+            }
+            BuildingKind::Inserter {
+                swing_ticks,
+                capacity,
+            } => {
+                self.inserters
+                    .insert(id, Inserter::new(swing_ticks, capacity));
+            }
+            BuildingKind::Lab => {
+                self.labs.insert(id, Lab::new());
+            }
+            BuildingKind::Pump { .. } | BuildingKind::Structure => {}
+        }
+        self.power_on_place(id, power);
+        self.fluid_on_place(id, fluid_capacity, kind);
+        if inventory_slots > 0 {
+            self.inventories
+                .insert(id, Inventory::new(inventory_slots as usize));
+        }
+        Ok(())
+    }
+
+    /// Replace every data registry with the built-in data, e.g. after
+    /// loading, so balance changes reach existing games.
+    pub(crate) fn load_builtin_data(&mut self) {
+        self.specs = SpecRegistry::builtin();
+        self.items = ItemRegistry::builtin();
+        self.recipes = RecipeRegistry::builtin();
+        self.techs = TechTree::builtin();
+    }
+
+    /// Recompute tile occupancy from `instances`, e.g. after loading.
+    /// Fails with the first instance that overlaps another, leaves the grid
+    /// or has an unknown spec.
+    pub(crate) fn rebuild_occupancy(&mut self) -> Result<(), InstanceId> {
+        self.occupancy.reset();
+        let placed: Vec<(InstanceId, Vec<TilePos>)> = self
+            .instances
+            .keys()
+            .map(|&id| (id, self.footprint(id).unwrap_or_default()))
+            .collect();
+        for (id, tiles) in placed {
+            if tiles.is_empty() {
+                return Err(id);
+            }
+            for t in tiles {
+                if !self.in_bounds(t) || self.occupancy.get(t).is_some() {
+                    return Err(id);
+                }
+                self.occupancy.set(t, id);
+            }
+        }
+        Ok(())
     }
 
     pub fn remove(&mut self, id: InstanceId) -> Option<BuildingInstance> {
+        if !self.instances.contains_key(&id) {
+            return None;
+        }
+        // power needs the footprint, so it goes before the instance
+        self.power_on_remove(id);
         let inst = self.instances.remove(&id)?;
-        // clear tiles occupied by this instance
-        let tiles = Self::footprint_tiles(inst_size_from_spec(&inst), inst.origin, inst.rotation);
-        for t in tiles {
-            if let Some(idx) = self.tile_index(t) {
-                if self.tiles[idx] == Some(id) {
-                    self.tiles[idx] = None;
+        self.belts.remove(&id);
+        self.inventories.remove(&id);
+        self.machines.remove(&id);
+        self.drills.remove(&id);
+        self.inserters.remove(&id);
+        self.labs.remove(&id);
+        self.fluid_on_remove(id);
+        match self.specs.get(inst.spec_id) {
+            Some(spec) => {
+                // clear tiles occupied by this instance
+                let tiles = Self::footprint_tiles(spec.size, inst.origin, inst.rotation);
+                for t in tiles {
+                    self.occupancy.clear(t, id);
                 }
             }
+            None => {
+                // unknown spec: never leave stale occupancy behind, scan every tile
+                self.occupancy.clear_all(id);
+            }
         }
+        self.push_change(BuildingChange::Removed {
+            id,
+            spec_id: inst.spec_id,
+            origin: inst.origin,
+        });
         Some(inst)
     }
-}
 
-// Helper: derive size from instance spec id using a tiny convenience mapping.
-// In a full game this would live elsewhere; for now keep a minimal mapping so remove() can compute footprint.
-fn inst_size_from_spec(inst: &BuildingInstance) -> Size2 {
-    match inst.spec_id {
-        1 => Size2 { w: 1, h: 1 }, // conveyor
-        2 => Size2 { w: 2, h: 2 }, // furnace
-        3 => Size2 { w: 3, h: 3 }, // assembler
-        _ => Size2 { w: 1, h: 1 },
+    /// Buildings placed and removed since the last call, oldest first.
+    /// Loading a save does not report the buildings in it.
+    pub fn take_building_changes(&mut self) -> Vec<BuildingChange> {
+        self.changes.drain(..).collect()
+    }
+
+    fn push_change(&mut self, change: BuildingChange) {
+        if self.changes.len() >= MAX_BUILDING_CHANGES {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(change);
     }
 }
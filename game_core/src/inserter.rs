@@ -0,0 +1,220 @@
+//! Inserters: single-tile arms that move items from the building behind them
+//! to the building in front of them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, TileGrid, TilePos};
+use crate::item::{ItemId, ItemStack};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InserterPhase {
+    /// Hand empty, looking for something to pick up behind
+    WaitingForPickup,
+    /// Carrying items toward the drop side; `u32` is ticks remaining
+    Swinging(u32),
+    /// At the drop side, waiting for the target to take the hand contents
+    WaitingForDrop,
+    /// Hand empty, travelling back; `u32` is ticks remaining
+    Returning(u32),
+}
+
+/// Per-instance arm state.
+///
+/// An unobstructed cycle takes exactly `2 * swing_ticks` ticks and moves up
+/// to `capacity` items.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Inserter {
+    /// Ticks for one half of a swing
+    pub swing_ticks: u32,
+    /// Maximum items carried per swing
+    pub capacity: u32,
+    /// Only pick up this item when set
+    pub filter: Option<ItemId>,
+    hand: Option<ItemStack>,
+    phase: InserterPhase,
+}
+
+impl Inserter {
+    pub fn new(swing_ticks: u32, capacity: u32) -> Self {
+        Self {
+            swing_ticks: swing_ticks.max(1),
+            capacity: capacity.max(1),
+            filter: None,
+            hand: None,
+            phase: InserterPhase::WaitingForPickup,
+        }
+    }
+
+    pub fn hand(&self) -> Option<ItemStack> {
+        self.hand
+    }
+
+    pub fn phase(&self) -> InserterPhase {
+        self.phase
+    }
+}
+
+impl TileGrid {
+    pub fn inserter(&self, id: InstanceId) -> Option<&Inserter> {
+        self.inserters.get(&id)
+    }
+
+    pub fn inserter_mut(&mut self, id: InstanceId) -> Option<&mut Inserter> {
+        self.inserters.get_mut(&id)
+    }
+
+    /// Source and destination tiles of an inserter: behind and in front.
+    fn inserter_tiles(&self, id: InstanceId) -> Option<(TilePos, TilePos)> {
+        let inst = self.instances.get(&id)?;
+        Some((
+            inst.origin.step(inst.rotation.opposite()),
+            inst.origin.step(inst.rotation),
+        ))
+    }
+
+    /// Whether building `id` would take one `item` right now.
+    pub fn can_accept(&self, id: InstanceId, item: ItemId) -> bool {
+        if let Some(belt) = self.belts.get(&id) {
+            return belt.has_entry_room();
+        }
+        if let Some(machine) = self.machines.get(&id) {
+            return machine.wants(&self.recipes, item)
+                && machine.input.space_for(&self.items, item) > 0;
+        }
+        if let Some(lab) = self.labs.get(&id) {
+            return self.lab_accepts(lab, item);
+        }
+        self.inventories
+            .get(&id)
+            .is_some_and(|inv| inv.space_for(&self.items, item) > 0)
+    }
+
+    /// Take up to `max` items from building `id` for which `want` holds.
+    /// Belts give their frontmost matching item, machines their output
+    /// buffer and storage buildings their inventory.
+    fn take_items<F>(&mut self, id: InstanceId, max: u32, want: F) -> Option<ItemStack>
+    where
+        F: Fn(ItemId) -> bool,
+    {
+        if let Some(belt) = self.belts.get_mut(&id) {
+            return belt
+                .take_first(&want)
+                .map(|item| ItemStack { item, count: 1 });
+        }
+        let inv = match self.machines.get_mut(&id) {
+            Some(machine) => &mut machine.output,
+            None => self.inventories.get_mut(&id)?,
+        };
+        let item = inv.stacks().find(|s| want(s.item))?.item;
+        let count = inv.extract(item, max);
+        (count > 0).then_some(ItemStack { item, count })
+    }
+
+    /// Hand items one at a time to building `id`. Returns how many it took.
+    fn give_items(&mut self, id: InstanceId, stack: ItemStack) -> u32 {
+        let mut given = 0;
+        while given < stack.count {
+            let accepted = if self.belts.contains_key(&id) {
+                self.belt_insert(id, stack.item)
+            } else {
+                self.offer_item(id, stack.item).unwrap_or(false)
+            };
+            if !accepted {
+                break;
+            }
+            given += 1;
+        }
+        given
+    }
+
+    /// Advance every inserter by one tick, in instance id order.
+    /// Underpowered inserters skip ticks.
+    pub fn tick_inserters(&mut self) {
+        let ids: Vec<InstanceId> = self.inserters.keys().copied().collect();
+        for id in ids {
+            if !self.draw_power(id) {
+                continue;
+            }
+            let Some((from, to)) = self.inserter_tiles(id) else {
+                continue;
+            };
+            let Some(ins) = self.inserters.get(&id) else {
+                continue;
+            };
+            let (mut phase, mut hand) = (ins.phase, ins.hand);
+            let (swing, capacity, filter) = (ins.swing_ticks, ins.capacity, ins.filter);
+            let source = self.tile_occupant(from).filter(|&s| s != id);
+            let target = self.tile_occupant(to).filter(|&t| t != id);
+
+            // the return trip ends in the same tick as the next pickup, so an
+            // unobstructed cycle is exactly two swings long
+            if let InserterPhase::Returning(t) = phase {
+                phase = if t > 1 {
+                    InserterPhase::Returning(t - 1)
+                } else {
+                    InserterPhase::WaitingForPickup
+                };
+            }
+
+            phase = match phase {
+                InserterPhase::WaitingForPickup => {
+                    let item = match (source, target) {
+                        (Some(s), Some(t)) => self
+                            .offered_items(s)
+                            .into_iter()
+                            .find(|&i| filter.is_none_or(|f| f == i) && self.can_accept(t, i)),
+                        _ => None,
+                    };
+                    hand = match (item, source) {
+                        (Some(item), Some(s)) => self.take_items(s, capacity, |i| i == item),
+                        _ => None,
+                    };
+                    if hand.is_some() {
+                        InserterPhase::Swinging(swing)
+                    } else {
+                        InserterPhase::WaitingForPickup
+                    }
+                }
+                InserterPhase::Swinging(t) if t > 1 => InserterPhase::Swinging(t - 1),
+                InserterPhase::Swinging(_) | InserterPhase::WaitingForDrop => {
+                    if let (Some(stack), Some(t)) = (hand, target) {
+                        let given = self.give_items(t, stack);
+                        hand = (given < stack.count).then_some(ItemStack {
+                            item: stack.item,
+                            count: stack.count - given,
+                        });
+                    }
+                    if hand.is_none() {
+                        InserterPhase::Returning(swing)
+                    } else {
+                        InserterPhase::WaitingForDrop
+                    }
+                }
+                InserterPhase::Returning(t) => InserterPhase::Returning(t),
+            };
+
+            if let Some(ins) = self.inserters.get_mut(&id) {
+                ins.phase = phase;
+                ins.hand = hand;
+            }
+        }
+    }
+
+    /// Distinct item ids building `id` could hand out, in pickup priority order.
+    fn offered_items(&self, id: InstanceId) -> Vec<ItemId> {
+        let mut out: Vec<ItemId> = Vec::new();
+        let mut push = |item: ItemId| {
+            if !out.contains(&item) {
+                out.push(item);
+            }
+        };
+        if let Some(belt) = self.belts.get(&id) {
+            belt.items().for_each(|i| push(i.item));
+        } else if let Some(machine) = self.machines.get(&id) {
+            machine.output.stacks().for_each(|s| push(s.item));
+        } else if let Some(inv) = self.inventories.get(&id) {
+            inv.stacks().for_each(|s| push(s.item));
+        }
+        out
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub type ItemId = u32;
+
+/// Built-in item definitions, embedded like the building specs.
+const BUILTIN_ITEMS: &str = include_str!("../data/items.ron");
+
+/// Static description of an item type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ItemDef {
+    pub item_id: ItemId,
+    pub name: String,
+    /// Maximum number of this item in a single inventory slot
+    pub stack_size: u32,
+    /// RGBA color for item icons and ore overlays
+    #[serde(default = "default_item_color")]
+    pub color: (f32, f32, f32, f32),
+    /// Fluids travel through pipes and fluid boxes instead of as items
+    #[serde(default)]
+    pub fluid: bool,
+}
+
+fn default_item_color() -> (f32, f32, f32, f32) {
+    (0.95, 0.85, 0.3, 1.0)
+}
+
+#[derive(Debug)]
+pub enum ItemError {
+    /// The definitions text could not be parsed
+    Parse(String),
+    /// Two definitions share the same `item_id`
+    DuplicateId(ItemId),
+    /// A definition has a stack size of zero
+    ZeroStackSize(ItemId),
+}
+
+impl fmt::Display for ItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemError::Parse(msg) => write!(f, "failed to parse item defs: {}", msg),
+            ItemError::DuplicateId(id) => write!(f, "duplicate item id {}", id),
+            ItemError::ZeroStackSize(id) => write!(f, "item {} has a stack size of 0", id),
+        }
+    }
+}
+
+impl std::error::Error for ItemError {}
+
+/// Owns every known `ItemDef`, keyed by `item_id`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ItemRegistry {
+    items: BTreeMap<ItemId, ItemDef>,
+}
+
+impl ItemRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry populated from the definitions shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_ron_str(BUILTIN_ITEMS).expect("built-in item defs must be valid")
+    }
+
+    /// Parse a RON list of item definitions.
+    pub fn from_ron_str(text: &str) -> Result<Self, ItemError> {
+        let list: Vec<ItemDef> =
+            ron::from_str(text).map_err(|e| ItemError::Parse(e.to_string()))?;
+        let mut reg = Self::new();
+        for def in list {
+            let id = def.item_id;
+            if def.stack_size == 0 {
+                return Err(ItemError::ZeroStackSize(id));
+            }
+            if reg.insert(def).is_some() {
+                return Err(ItemError::DuplicateId(id));
+            }
+        }
+        Ok(reg)
+    }
+
+    /// Add or replace an item. Returns the previous def with the same id, if any.
+    pub fn insert(&mut self, def: ItemDef) -> Option<ItemDef> {
+        self.items.insert(def.item_id, def)
+    }
+
+    pub fn get(&self, item_id: ItemId) -> Option<&ItemDef> {
+        self.items.get(&item_id)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&ItemDef> {
+        self.items.values().find(|d| d.name == name)
+    }
+
+    /// Stack size of `item_id`, or 0 for unknown items (which then never fit).
+    pub fn stack_size(&self, item_id: ItemId) -> u32 {
+        self.get(item_id).map_or(0, |d| d.stack_size)
+    }
+
+    pub fn is_fluid(&self, item_id: ItemId) -> bool {
+        self.get(item_id).is_some_and(|d| d.fluid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDef> {
+        self.items.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+/// Fixed number of slots, each holding at most one stack.
+/// A slot may carry a filter restricting which item it accepts.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    filters: Vec<Option<ItemId>>,
+}
+
+impl Inventory {
+    pub fn new(slot_count: usize) -> Self {
+        Self {
+            slots: vec![None; slot_count],
+            filters: vec![None; slot_count],
+        }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn slot(&self, index: usize) -> Option<ItemStack> {
+        self.slots.get(index).copied().flatten()
+    }
+
+    /// Non-empty stacks in slot order.
+    pub fn stacks(&self) -> impl Iterator<Item = ItemStack> + '_ {
+        self.slots.iter().filter_map(|s| *s)
+    }
+
+    pub fn filter(&self, index: usize) -> Option<ItemId> {
+        self.filters.get(index).copied().flatten()
+    }
+
+    /// Restrict slot `index` to `item` (or clear with `None`).
+    /// Existing contents are left in place even if they no longer match.
+    pub fn set_filter(&mut self, index: usize, item: Option<ItemId>) {
+        if let Some(f) = self.filters.get_mut(index) {
+            *f = item;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|s| s.is_none())
+    }
+
+    /// Total number of `item` across all slots.
+    pub fn count(&self, item: ItemId) -> u32 {
+        self.stacks()
+            .filter(|s| s.item == item)
+            .map(|s| s.count)
+            .sum()
+    }
+
+    fn accepts(&self, index: usize, item: ItemId) -> bool {
+        self.filters[index].is_none_or(|f| f == item)
+    }
+
+    /// How many of `item` could be inserted right now.
+    pub fn space_for(&self, items: &ItemRegistry, item: ItemId) -> u32 {
+        let stack_size = items.stack_size(item);
+        (0..self.slots.len())
+            .filter(|&i| self.accepts(i, item))
+            .map(|i| match self.slots[i] {
+                None => stack_size,
+                Some(s) if s.item == item => stack_size.saturating_sub(s.count),
+                Some(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Insert up to `count` of `item`, topping up existing stacks before using
+    /// empty slots. Returns the number that did not fit.
+    pub fn insert(&mut self, items: &ItemRegistry, item: ItemId, count: u32) -> u32 {
+        let stack_size = items.stack_size(item);
+        let mut left = count;
+        for i in 0..self.slots.len() {
+            if left == 0 {
+                break;
+            }
+            if let Some(s) = self.slots[i].as_mut() {
+                if s.item == item && self.filters[i].is_none_or(|f| f == item) {
+                    let moved = left.min(stack_size.saturating_sub(s.count));
+                    s.count += moved;
+                    left -= moved;
+                }
+            }
+        }
+        for i in 0..self.slots.len() {
+            if left == 0 || stack_size == 0 {
+                break;
+            }
+            if self.slots[i].is_none() && self.accepts(i, item) {
+                let moved = left.min(stack_size);
+                self.slots[i] = Some(ItemStack { item, count: moved });
+                left -= moved;
+            }
+        }
+        left
+    }
+
+    /// Remove up to `count` of `item`, taking from the last slots first.
+    /// Returns the number actually removed.
+    pub fn extract(&mut self, item: ItemId, count: u32) -> u32 {
+        let mut taken = 0;
+        for slot in self.slots.iter_mut().rev() {
+            if taken == count {
+                break;
+            }
+            if let Some(s) = slot.as_mut() {
+                if s.item == item {
+                    let moved = (count - taken).min(s.count);
+                    s.count -= moved;
+                    taken += moved;
+                    if s.count == 0 {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+        taken
+    }
+
+    /// Remove up to `max` of the item in the first stack matching `filter`
+    /// (any item when `None`).
+    pub fn extract_first(&mut self, filter: Option<ItemId>, max: u32) -> Option<ItemStack> {
+        let item = self
+            .stacks()
+            .find(|s| filter.is_none_or(|f| f == s.item))?
+            .item;
+        let count = self.extract(item, max);
+        (count > 0).then_some(ItemStack { item, count })
+    }
+}
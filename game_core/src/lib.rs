@@ -4,36 +4,96 @@
 
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 pub type EntityId = u32;
 
+mod belt;
+pub use belt::*;
+
+mod blueprint;
+pub use blueprint::*;
+
+mod chunk;
+pub use chunk::*;
+
+mod collision;
+pub use collision::*;
+
+mod combat;
+pub use combat::*;
+
+mod drill;
+pub use drill::*;
+
+mod fluid;
+pub use fluid::*;
+
 mod grid;
 pub use grid::*;
 
-#[derive(Clone, Debug)]
+mod inserter;
+pub use inserter::*;
+
+mod item;
+pub use item::*;
+
+mod machine;
+pub use machine::*;
+
+mod power;
+pub use power::*;
+
+mod recipe;
+pub use recipe::*;
+
+mod research;
+pub use research::*;
+
+mod save;
+pub use save::*;
+
+mod spatial;
+pub use spatial::*;
+
+mod terrain;
+pub use terrain::*;
+
+mod spec;
+pub use spec::*;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Transform {
     pub x: f32,
     pub y: f32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Velocity {
     pub vx: f32,
     pub vy: f32,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum EntityType {
     Player,
     Enemy,
+    Projectile,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Entity {
     pub id: EntityId,
     pub ty: EntityType,
     pub transform: Transform,
     pub velocity: Velocity,
     pub radius: f32,
+    /// Carried items; only some entity types have one
+    pub inventory: Option<Inventory>,
+    /// Entities without health cannot be damaged
+    pub health: Option<Health>,
+    /// Set on entities of type `Projectile`
+    pub projectile: Option<Projectile>,
 }
 
 impl fmt::Display for Entity {
@@ -51,19 +111,42 @@ impl fmt::Display for Entity {
     }
 }
 
+/// Number of inventory slots the player spawns with.
+pub const PLAYER_INVENTORY_SLOTS: usize = 40;
+
 /// Minimal world container with deterministic update (physics integration).
+#[derive(Serialize, Deserialize)]
 pub struct World {
-    pub entities: Vec<Entity>, // intentionally public for iterating/drawing
+    /// Read through `entities`; edits go through `entities_mut` so the
+    /// spatial index knows it is stale
+    entities: Vec<Entity>,
+    /// Buildings and their per-instance factory state
+    pub grid: TileGrid,
+    /// Which entity types collide with each other and with buildings
+    pub collision: CollisionRules,
     next_id: EntityId,
+    /// Proximity index over `entities`; not saved, rebuilt on load
+    #[serde(skip)]
+    index: spatial::SpatialIndex,
 }
 
 impl World {
-    /// Create an empty world.
+    /// Create an empty world whose building grid grows as needed.
     pub fn new() -> Self {
-        Self {
+        Self::with_grid(TileGrid::unbounded())
+    }
+
+    /// Create an empty world around an existing building grid.
+    pub fn with_grid(grid: TileGrid) -> Self {
+        let mut world = Self {
             entities: Vec::new(),
+            grid,
+            collision: CollisionRules::default(),
             next_id: 1,
-        }
+            index: spatial::SpatialIndex::default(),
+        };
+        world.reindex();
+        world
     }
 
     fn alloc_id(&mut self) -> EntityId {
@@ -80,8 +163,11 @@ impl World {
             transform: Transform { x, y },
             velocity: Velocity { vx: 0.0, vy: 0.0 },
             radius: 16.0,
+            inventory: Some(Inventory::new(PLAYER_INVENTORY_SLOTS)),
+            health: Some(Health::new(PLAYER_HEALTH)),
+            projectile: None,
         };
-        self.entities.push(e);
+        self.push_entity(e);
     }
 
     /// Spawn a simple enemy.
@@ -92,47 +178,68 @@ impl World {
             transform: Transform { x, y },
             velocity: Velocity { vx: 0.0, vy: 0.0 },
             radius: 12.0,
+            inventory: None,
+            health: Some(Health::new(ENEMY_HEALTH)),
+            projectile: None,
         };
+        self.push_entity(e);
+    }
+
+    fn push_entity(&mut self, e: Entity) {
+        if self.index_is_current() {
+            self.index.push(self.entities.len(), &e);
+        }
         self.entities.push(e);
     }
 
-    /// Simple physics integration: position += velocity * dt.
+    /// Simple physics integration: position += velocity * dt, stopped by
+    /// buildings and other entities (see `collision`).
     /// Also perform basic world bounds clamping (optional).
     pub fn update_physics(&mut self, dt: f32) {
+        for slot in 0..self.entities.len() {
+            self.move_entity(slot, dt);
+        }
+        // clamp last so separated entities cannot be pushed outside
+        self.resolve_entity_overlaps();
+        let mut clamped = false;
         for e in &mut self.entities {
-            e.transform.x += e.velocity.vx * dt;
-            e.transform.y += e.velocity.vy * dt;
-
             // keep things inside a simple visible world rectangle (0..2000)
             if e.transform.x < -1000.0 {
                 e.transform.x = -1000.0;
                 e.velocity.vx = 0.0;
+                clamped = true;
             }
             if e.transform.x > 1000.0 {
                 e.transform.x = 1000.0;
                 e.velocity.vx = 0.0;
+                clamped = true;
             }
             if e.transform.y < -1000.0 {
                 e.transform.y = -1000.0;
                 e.velocity.vy = 0.0;
+                clamped = true;
             }
             if e.transform.y > 1000.0 {
                 e.transform.y = 1000.0;
                 e.velocity.vy = 0.0;
+                clamped = true;
             }
         }
+        if clamped {
+            self.reindex();
+        }
     }
 
     /// Helper: find mutable reference to the player entity (first occurrence).
+    /// Marks the spatial index stale, like `entities_mut`.
     pub fn find_player_mut(&mut self) -> Option<&mut Entity> {
-        self.entities
-            .iter_mut()
-            .find(|e| e.ty == EntityType::Player)
+        let slot = self.player_slot()?;
+        self.entities_mut().get_mut(slot)
     }
 
     /// Helper: find immutable reference to the player entity (first occurrence).
     pub fn find_player(&self) -> Option<&Entity> {
-        self.entities.iter().find(|e| e.ty == EntityType::Player)
+        self.entities.get(self.player_slot()?)
     }
 }
 
@@ -0,0 +1,382 @@
+//! Recipe-driven crafting machines (furnaces, assemblers).
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fluid::FluidBox;
+use crate::grid::{InstanceId, TileGrid};
+use crate::item::{Inventory, ItemId, ItemRegistry};
+use crate::recipe::{CraftCategory, Recipe, RecipeId, RecipeRegistry};
+
+/// Slots in each machine's input and output buffers.
+pub const MACHINE_BUFFER_SLOTS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MachineStatus {
+    /// No recipe chosen
+    Idle,
+    /// Recipe known but ingredients missing
+    WaitingForInput,
+    Crafting,
+    /// Craft finished but the output buffer has no room
+    OutputBlocked,
+}
+
+/// Something a machine did during `TileGrid::tick_machines`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MachineEvent {
+    /// Ingredients consumed and a craft of `recipe` begun
+    Started { id: InstanceId, recipe: RecipeId },
+    /// Products of `recipe` moved into the output buffer
+    Finished { id: InstanceId, recipe: RecipeId },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MachineError {
+    NotAMachine,
+    UnknownRecipe(RecipeId),
+    /// The recipe belongs to a category this machine cannot craft
+    WrongCategory(RecipeId),
+    /// The recipe needs a technology that is not researched yet
+    Locked(RecipeId),
+    /// A finished craft is waiting for room in the output buffer
+    OutputBlocked,
+    /// The recipe's fluid differs from the one the machine's pipe network
+    /// already carries
+    MixedFluids(RecipeId),
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineError::NotAMachine => write!(f, "building is not a crafting machine"),
+            MachineError::UnknownRecipe(id) => write!(f, "unknown recipe {}", id),
+            MachineError::WrongCategory(id) => {
+                write!(f, "recipe {} cannot be crafted by this machine", id)
+            }
+            MachineError::Locked(id) => write!(f, "recipe {} is not researched yet", id),
+            MachineError::OutputBlocked => {
+                write!(f, "finished products are waiting for room in the output")
+            }
+            MachineError::MixedFluids(id) => {
+                write!(
+                    f,
+                    "recipe {} needs a fluid its pipe network cannot carry",
+                    id
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MachineError {}
+
+/// Per-instance crafting state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Machine {
+    pub category: CraftCategory,
+    /// Pick whichever recipe of `category` has its inputs available
+    pub auto_select: bool,
+    recipe: Option<RecipeId>,
+    status: MachineStatus,
+    /// Ticks spent on the current craft
+    progress: u32,
+    /// Ticks the current craft takes
+    duration: u32,
+    pub input: Inventory,
+    pub output: Inventory,
+}
+
+impl Machine {
+    pub fn new(category: CraftCategory, auto_select: bool) -> Self {
+        Self {
+            category,
+            auto_select,
+            recipe: None,
+            status: if auto_select {
+                MachineStatus::WaitingForInput
+            } else {
+                MachineStatus::Idle
+            },
+            progress: 0,
+            duration: 0,
+            input: Inventory::new(MACHINE_BUFFER_SLOTS),
+            output: Inventory::new(MACHINE_BUFFER_SLOTS),
+        }
+    }
+
+    pub fn recipe(&self) -> Option<RecipeId> {
+        self.recipe
+    }
+
+    pub fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    /// Fraction of the current craft completed, 0.0..=1.0.
+    pub fn progress(&self) -> f32 {
+        match self.status {
+            MachineStatus::Crafting | MachineStatus::OutputBlocked if self.duration > 0 => {
+                self.progress as f32 / self.duration as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Whether `item` belongs in this machine's input buffer.
+    pub(crate) fn wants(&self, recipes: &RecipeRegistry, item: ItemId) -> bool {
+        let uses = |r: &Recipe| r.inputs.iter().any(|s| s.item == item);
+        if self.auto_select {
+            recipes.in_category(self.category).any(uses)
+        } else {
+            self.recipe.and_then(|id| recipes.get(id)).is_some_and(uses)
+        }
+    }
+
+    /// Whether every ingredient of `recipe` is present, fluids in `fluid`
+    /// and everything else in the input buffer.
+    fn has_inputs(&self, items: &ItemRegistry, recipe: &Recipe, fluid: Option<&FluidBox>) -> bool {
+        recipe.inputs.iter().all(|s| {
+            if items.is_fluid(s.item) {
+                fluid.is_some_and(|f| f.fluid() == Some(s.item) && f.amount() >= s.count)
+            } else {
+                self.input.count(s.item) >= s.count
+            }
+        })
+    }
+
+    /// Recipe to start next, if its ingredients are all present. Machines
+    /// that pick their own recipe skip the `locked` ones.
+    fn next_recipe<'a>(
+        &self,
+        items: &ItemRegistry,
+        recipes: &'a RecipeRegistry,
+        locked: &BTreeSet<RecipeId>,
+        fluid: Option<&FluidBox>,
+    ) -> Option<&'a Recipe> {
+        if self.auto_select {
+            recipes
+                .in_category(self.category)
+                .filter(|r| !locked.contains(&r.recipe_id))
+                .find(|r| self.has_inputs(items, r, fluid))
+        } else {
+            self.recipe
+                .and_then(|id| recipes.get(id))
+                .filter(|r| self.has_inputs(items, r, fluid))
+        }
+    }
+
+    /// Move finished products into the output buffer, all or nothing.
+    fn deliver(&mut self, items: &ItemRegistry, recipe: &Recipe) -> bool {
+        let fits = recipe
+            .outputs
+            .iter()
+            .all(|s| self.output.space_for(items, s.item) >= s.count);
+        if fits {
+            for s in &recipe.outputs {
+                self.output.insert(items, s.item, s.count);
+            }
+        }
+        fits
+    }
+
+    fn tick(
+        &mut self,
+        id: InstanceId,
+        items: &ItemRegistry,
+        recipes: &RecipeRegistry,
+        locked: &BTreeSet<RecipeId>,
+        mut fluid: Option<&mut FluidBox>,
+        events: &mut Vec<MachineEvent>,
+    ) {
+        if let MachineStatus::Crafting | MachineStatus::OutputBlocked = self.status {
+            self.progress = (self.progress + 1).min(self.duration);
+            if self.progress < self.duration {
+                return;
+            }
+            let Some(recipe) = self.recipe.and_then(|id| recipes.get(id)) else {
+                self.status = MachineStatus::Idle;
+                return;
+            };
+            if !self.deliver(items, recipe) {
+                self.status = MachineStatus::OutputBlocked;
+                return;
+            }
+            events.push(MachineEvent::Finished {
+                id,
+                recipe: recipe.recipe_id,
+            });
+            self.progress = 0;
+            self.status = MachineStatus::WaitingForInput;
+        }
+
+        match self.next_recipe(items, recipes, locked, fluid.as_deref()) {
+            Some(recipe) => {
+                for s in &recipe.inputs {
+                    match fluid.as_deref_mut() {
+                        Some(f) if items.is_fluid(s.item) => {
+                            f.extract(s.count);
+                        }
+                        _ => {
+                            self.input.extract(s.item, s.count);
+                        }
+                    }
+                }
+                self.recipe = Some(recipe.recipe_id);
+                self.duration = recipe.craft_ticks.max(1);
+                self.progress = 0;
+                self.status = MachineStatus::Crafting;
+                events.push(MachineEvent::Started {
+                    id,
+                    recipe: recipe.recipe_id,
+                });
+            }
+            None if self.recipe.is_none() && !self.auto_select => {
+                self.status = MachineStatus::Idle;
+            }
+            None => self.status = MachineStatus::WaitingForInput,
+        }
+    }
+}
+
+impl TileGrid {
+    pub fn recipes(&self) -> &RecipeRegistry {
+        &self.recipes
+    }
+
+    pub fn recipes_mut(&mut self) -> &mut RecipeRegistry {
+        &mut self.recipes
+    }
+
+    pub fn machine(&self, id: InstanceId) -> Option<&Machine> {
+        self.machines.get(&id)
+    }
+
+    pub fn machine_mut(&mut self, id: InstanceId) -> Option<&mut Machine> {
+        self.machines.get_mut(&id)
+    }
+
+    /// Every crafting machine, in id order.
+    pub fn machines(&self) -> impl Iterator<Item = (InstanceId, &Machine)> {
+        self.machines.iter().map(|(&id, m)| (id, m))
+    }
+
+    /// Choose the recipe for a machine (or clear it with `None`).
+    ///
+    /// A craft in progress is cancelled and its ingredients go back into the
+    /// input buffer; anything that no longer fits there is lost, as is fluid
+    /// the new recipe does not use. Refused while a finished craft waits for
+    /// output room, or when the recipe's fluid would mix with another in the
+    /// connected pipes.
+    pub fn set_recipe(
+        &mut self,
+        id: InstanceId,
+        recipe_id: Option<RecipeId>,
+    ) -> Result<(), MachineError> {
+        let machine = self.machines.get(&id).ok_or(MachineError::NotAMachine)?;
+        let (category, blocked) = (
+            machine.category,
+            machine.status == MachineStatus::OutputBlocked,
+        );
+        if blocked && recipe_id == machine.recipe {
+            return Ok(());
+        }
+        let recipe = match recipe_id {
+            Some(rid) => Some(
+                self.recipes
+                    .get(rid)
+                    .ok_or(MachineError::UnknownRecipe(rid))?,
+            ),
+            None => None,
+        };
+        if let Some(r) = recipe.filter(|r| r.category != category) {
+            return Err(MachineError::WrongCategory(r.recipe_id));
+        }
+        // the products are already made; switching would throw them away
+        if blocked {
+            return Err(MachineError::OutputBlocked);
+        }
+        let new_fluid = recipe
+            .and_then(|r| r.inputs.iter().find(|s| self.items.is_fluid(s.item)))
+            .map(|s| s.item);
+        if let (Some(r), Some(f)) = (recipe, new_fluid) {
+            if self.fluid_boxes.contains_key(&id) && !self.fluid_fits(id, f) {
+                return Err(MachineError::MixedFluids(r.recipe_id));
+            }
+        }
+        let machine = self
+            .machines
+            .get_mut(&id)
+            .ok_or(MachineError::NotAMachine)?;
+        let mut fluid = self.fluid_boxes.get_mut(&id);
+        // the fluid box only takes the fluid the new recipe uses
+        if let Some(f) = fluid.as_deref_mut() {
+            if new_fluid.is_some() && f.fluid().is_some_and(|held| Some(held) != new_fluid) {
+                f.extract(f.amount());
+            }
+            f.filter = new_fluid;
+        }
+        if machine.status == MachineStatus::Crafting {
+            if let Some(old) = machine.recipe.and_then(|r| self.recipes.get(r)) {
+                for s in &old.inputs {
+                    match fluid.as_deref_mut() {
+                        // refused unless the box still takes this fluid
+                        Some(f) if self.items.is_fluid(s.item) => {
+                            f.insert(s.item, s.count);
+                        }
+                        _ => {
+                            machine.input.insert(&self.items, s.item, s.count);
+                        }
+                    }
+                }
+            }
+        }
+        machine.recipe = recipe_id;
+        machine.progress = 0;
+        machine.duration = 0;
+        machine.status = if recipe_id.is_some() || machine.auto_select {
+            MachineStatus::WaitingForInput
+        } else {
+            MachineStatus::Idle
+        };
+        Ok(())
+    }
+
+    /// Offer `item` to machine `id`'s input buffer.
+    /// `None` if `id` is not a machine.
+    pub(crate) fn offer_machine_item(&mut self, id: InstanceId, item: ItemId) -> Option<bool> {
+        let machine = self.machines.get_mut(&id)?;
+        Some(machine.wants(&self.recipes, item) && machine.input.insert(&self.items, item, 1) == 0)
+    }
+
+    /// Advance every machine by one tick. Underpowered machines skip ticks.
+    /// Returns the crafts that started and finished, in instance order.
+    pub fn tick_machines(&mut self) -> Vec<MachineEvent> {
+        let mut events = Vec::new();
+        let locked: BTreeSet<RecipeId> = self
+            .recipes
+            .iter()
+            .map(|r| r.recipe_id)
+            .filter(|&r| !self.is_recipe_unlocked(r))
+            .collect();
+        let ids: Vec<InstanceId> = self.machines.keys().copied().collect();
+        for id in ids {
+            if !self.draw_power(id) {
+                continue;
+            }
+            if let Some(machine) = self.machines.get_mut(&id) {
+                machine.tick(
+                    id,
+                    &self.items,
+                    &self.recipes,
+                    &locked,
+                    self.fluid_boxes.get_mut(&id),
+                    &mut events,
+                );
+            }
+        }
+        events
+    }
+}
@@ -0,0 +1,352 @@
+//! Electric networks. Poles link to each other within wire reach and supply
+//! generators and consumers whose footprint lies in their supply area.
+//! Membership is updated incrementally as buildings are placed and removed;
+//! only production/demand balancing runs every tick.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, TileGrid, TilePos};
+
+pub type NetworkId = u32;
+
+/// Full power satisfaction, in permille.
+pub const FULL_SATISFACTION: u32 = 1000;
+
+/// How a building takes part in an electric network. Power is in kW.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PowerRole {
+    #[default]
+    None,
+    Generator {
+        output: u32,
+    },
+    /// Connects to other poles within `wire_reach` tiles and powers buildings
+    /// touching the square of `supply_radius` tiles around it.
+    Pole {
+        wire_reach: u32,
+        supply_radius: u32,
+    },
+    Consumer {
+        demand: u32,
+    },
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PowerNetwork {
+    pub poles: BTreeSet<InstanceId>,
+    pub generators: BTreeSet<InstanceId>,
+    pub consumers: BTreeSet<InstanceId>,
+    /// Sum of generator output as of the last `tick_power`
+    pub production: u32,
+    /// Sum of consumer demand as of the last `tick_power`
+    pub demand: u32,
+    /// Fraction of demand met, in permille
+    pub satisfaction: u32,
+}
+
+/// All electric networks on a grid plus per-building membership.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PowerGrid {
+    networks: BTreeMap<NetworkId, PowerNetwork>,
+    roles: BTreeMap<InstanceId, PowerRole>,
+    /// Network of each pole, generator and consumer that is connected
+    member_of: BTreeMap<InstanceId, NetworkId>,
+    /// Consumers accumulate satisfaction and act once per full unit, so a
+    /// half-powered machine advances every other tick
+    credit: BTreeMap<InstanceId, u32>,
+    next_network: NetworkId,
+}
+
+impl PowerGrid {
+    pub fn network(&self, id: NetworkId) -> Option<&PowerNetwork> {
+        self.networks.get(&id)
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = (NetworkId, &PowerNetwork)> {
+        self.networks.iter().map(|(id, n)| (*id, n))
+    }
+
+    pub fn network_of(&self, id: InstanceId) -> Option<NetworkId> {
+        self.member_of.get(&id).copied()
+    }
+
+    fn new_network(&mut self) -> NetworkId {
+        self.next_network += 1;
+        self.networks
+            .insert(self.next_network, PowerNetwork::default());
+        self.next_network
+    }
+
+    fn join(&mut self, id: InstanceId, net: NetworkId) {
+        let Some(network) = self.networks.get_mut(&net) else {
+            return;
+        };
+        match self.roles.get(&id) {
+            Some(PowerRole::Pole { .. }) => network.poles.insert(id),
+            Some(PowerRole::Generator { .. }) => network.generators.insert(id),
+            Some(PowerRole::Consumer { .. }) => network.consumers.insert(id),
+            _ => return,
+        };
+        self.member_of.insert(id, net);
+    }
+
+    fn leave(&mut self, id: InstanceId) {
+        if let Some(net) = self.member_of.remove(&id) {
+            if let Some(network) = self.networks.get_mut(&net) {
+                network.poles.remove(&id);
+                network.generators.remove(&id);
+                network.consumers.remove(&id);
+            }
+        }
+    }
+
+    /// Move every member of `from` into `into` and drop `from`.
+    fn merge(&mut self, from: NetworkId, into: NetworkId) {
+        let Some(old) = self.networks.remove(&from) else {
+            return;
+        };
+        for id in old
+            .poles
+            .iter()
+            .chain(&old.generators)
+            .chain(&old.consumers)
+        {
+            self.join(*id, into);
+        }
+    }
+}
+
+fn dist2(a: TilePos, b: TilePos) -> i64 {
+    let dx = (a.x - b.x) as i64;
+    let dy = (a.y - b.y) as i64;
+    dx * dx + dy * dy
+}
+
+impl TileGrid {
+    pub fn power(&self) -> &PowerGrid {
+        &self.power
+    }
+
+    /// Satisfaction in permille of the network powering consumer `id`.
+    /// `None` if `id` is not a consumer; 0 if it is not connected.
+    pub fn power_satisfaction(&self, id: InstanceId) -> Option<u32> {
+        match self.power.roles.get(&id) {
+            Some(PowerRole::Consumer { .. }) => Some(
+                self.power
+                    .network_of(id)
+                    .and_then(|n| self.power.networks.get(&n))
+                    .map_or(0, |n| n.satisfaction),
+            ),
+            _ => None,
+        }
+    }
+
+    fn pole_links(&self, a: InstanceId, b: InstanceId) -> bool {
+        let (
+            Some(PowerRole::Pole { wire_reach: ra, .. }),
+            Some(PowerRole::Pole { wire_reach: rb, .. }),
+        ) = (self.power.roles.get(&a), self.power.roles.get(&b))
+        else {
+            return false;
+        };
+        let (Some(ia), Some(ib)) = (self.instances.get(&a), self.instances.get(&b)) else {
+            return false;
+        };
+        let reach = (*ra).min(*rb) as i64;
+        dist2(ia.origin, ib.origin) <= reach * reach
+    }
+
+    /// Whether pole `pole` supplies any tile of building `id`.
+    fn pole_covers(&self, pole: InstanceId, id: InstanceId) -> bool {
+        let Some(PowerRole::Pole { supply_radius, .. }) = self.power.roles.get(&pole) else {
+            return false;
+        };
+        let Some(center) = self.instances.get(&pole).map(|i| i.origin) else {
+            return false;
+        };
+        let r = *supply_radius as i32;
+        self.footprint(id).is_some_and(|tiles| {
+            tiles
+                .iter()
+                .any(|t| (t.x - center.x).abs() <= r && (t.y - center.y).abs() <= r)
+        })
+    }
+
+    fn poles(&self) -> Vec<InstanceId> {
+        self.power
+            .roles
+            .iter()
+            .filter(|(_, r)| matches!(r, PowerRole::Pole { .. }))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Lowest network id among poles covering building `id`.
+    fn covering_network(&self, id: InstanceId) -> Option<NetworkId> {
+        self.poles()
+            .into_iter()
+            .filter(|&p| self.pole_covers(p, id))
+            .filter_map(|p| self.power.network_of(p))
+            .min()
+    }
+
+    /// Hook for `TileGrid::place`: register `id` and connect it.
+    pub(crate) fn power_on_place(&mut self, id: InstanceId, role: PowerRole) {
+        match role {
+            PowerRole::None => {}
+            PowerRole::Pole { .. } => {
+                self.power.roles.insert(id, role);
+                let mut linked: Vec<NetworkId> = self
+                    .poles()
+                    .into_iter()
+                    .filter(|&p| p != id && self.pole_links(id, p))
+                    .filter_map(|p| self.power.network_of(p))
+                    .collect();
+                linked.sort_unstable();
+                linked.dedup();
+                let net = match linked.first() {
+                    Some(&n) => n,
+                    None => self.power.new_network(),
+                };
+                for &other in &linked[linked.len().min(1)..] {
+                    self.power.merge(other, net);
+                }
+                self.power.join(id, net);
+                // pick up buildings in range that had no power yet
+                let unconnected: Vec<InstanceId> = self
+                    .power
+                    .roles
+                    .iter()
+                    .filter(|(i, r)| {
+                        !matches!(r, PowerRole::Pole { .. })
+                            && !self.power.member_of.contains_key(i)
+                    })
+                    .map(|(i, _)| *i)
+                    .collect();
+                for other in unconnected {
+                    if self.pole_covers(id, other) {
+                        self.power.join(other, net);
+                    }
+                }
+            }
+            PowerRole::Generator { .. } | PowerRole::Consumer { .. } => {
+                self.power.roles.insert(id, role);
+                if let Some(net) = self.covering_network(id) {
+                    self.power.join(id, net);
+                }
+            }
+        }
+    }
+
+    /// Hook for `TileGrid::remove`, called while the instance is still
+    /// registered. Removing a pole re-splits only the network it was in.
+    pub(crate) fn power_on_remove(&mut self, id: InstanceId) {
+        let Some(role) = self.power.roles.get(&id).copied() else {
+            return;
+        };
+        let net = self.power.network_of(id);
+        self.power.leave(id);
+        self.power.credit.remove(&id);
+        self.power.roles.remove(&id);
+        if let (PowerRole::Pole { .. }, Some(net)) = (role, net) {
+            self.split_network(net);
+        }
+    }
+
+    /// Rebuild network `net` after one of its poles left: the remaining
+    /// poles form one new network per connected group, and its generators and
+    /// consumers reconnect to whatever still covers them.
+    fn split_network(&mut self, net: NetworkId) {
+        let Some(old) = self.power.networks.remove(&net) else {
+            return;
+        };
+        for id in old
+            .poles
+            .iter()
+            .chain(&old.generators)
+            .chain(&old.consumers)
+        {
+            self.power.member_of.remove(id);
+        }
+        let mut pending: BTreeSet<InstanceId> = old.poles.clone();
+        while let Some(&start) = pending.iter().next() {
+            pending.remove(&start);
+            let fresh = self.power.new_network();
+            let mut stack = vec![start];
+            while let Some(p) = stack.pop() {
+                self.power.join(p, fresh);
+                let next: Vec<InstanceId> = pending
+                    .iter()
+                    .copied()
+                    .filter(|&q| self.pole_links(p, q))
+                    .collect();
+                for q in next {
+                    pending.remove(&q);
+                    stack.push(q);
+                }
+            }
+        }
+        for id in old.generators.iter().chain(&old.consumers) {
+            if let Some(n) = self.covering_network(*id) {
+                self.power.join(*id, n);
+            }
+        }
+    }
+
+    /// Balance production against demand in every network. Returns the
+    /// networks that just started demanding more than they produce.
+    pub fn tick_power(&mut self) -> Vec<NetworkId> {
+        let roles = &self.power.roles;
+        let mut short = Vec::new();
+        for (&id, network) in self.power.networks.iter_mut() {
+            let was_short = network.demand > network.production;
+            let production: u32 = network
+                .generators
+                .iter()
+                .map(|g| match roles.get(g) {
+                    Some(PowerRole::Generator { output }) => *output,
+                    _ => 0,
+                })
+                .sum();
+            let demand: u32 = network
+                .consumers
+                .iter()
+                .map(|c| match roles.get(c) {
+                    Some(PowerRole::Consumer { demand }) => *demand,
+                    _ => 0,
+                })
+                .sum();
+            network.production = production;
+            network.demand = demand;
+            network.satisfaction = if demand == 0 {
+                FULL_SATISFACTION
+            } else {
+                ((production as u64 * FULL_SATISFACTION as u64) / demand as u64)
+                    .min(FULL_SATISFACTION as u64) as u32
+            };
+            if demand > production && !was_short {
+                short.push(id);
+            }
+        }
+        short
+    }
+
+    /// Whether building `id` gets to act this tick. Buildings that are not
+    /// consumers always do; consumers act at a rate equal to their network's
+    /// satisfaction.
+    pub(crate) fn draw_power(&mut self, id: InstanceId) -> bool {
+        let Some(satisfaction) = self.power_satisfaction(id) else {
+            return true;
+        };
+        let credit = self.power.credit.entry(id).or_insert(0);
+        *credit += satisfaction;
+        if *credit >= FULL_SATISFACTION {
+            *credit -= FULL_SATISFACTION;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::item::{Inventory, ItemStack};
+
+pub type RecipeId = u32;
+
+/// Built-in recipe definitions, embedded like the building specs.
+const BUILTIN_RECIPES: &str = include_str!("../data/recipes.ron");
+
+/// Which kind of machine can craft a recipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CraftCategory {
+    Smelting,
+    Assembling,
+    /// Recipes with fluid ingredients
+    Chemistry,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Recipe {
+    pub recipe_id: RecipeId,
+    pub name: String,
+    pub category: CraftCategory,
+    pub inputs: Vec<ItemStack>,
+    pub outputs: Vec<ItemStack>,
+    /// Ticks a machine spends on one craft
+    pub craft_ticks: u32,
+}
+
+impl Recipe {
+    /// Whether `inv` holds every ingredient for one craft.
+    pub fn inputs_available(&self, inv: &Inventory) -> bool {
+        self.inputs.iter().all(|s| inv.count(s.item) >= s.count)
+    }
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    /// The definitions text could not be parsed
+    Parse(String),
+    /// Two definitions share the same `recipe_id`
+    DuplicateId(RecipeId),
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipeError::Parse(msg) => write!(f, "failed to parse recipes: {}", msg),
+            RecipeError::DuplicateId(id) => write!(f, "duplicate recipe id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
+
+/// Owns every known `Recipe`, keyed by `recipe_id`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecipeRegistry {
+    recipes: BTreeMap<RecipeId, Recipe>,
+}
+
+impl RecipeRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry populated from the definitions shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_ron_str(BUILTIN_RECIPES).expect("built-in recipes must be valid")
+    }
+
+    /// Parse a RON list of recipes.
+    pub fn from_ron_str(text: &str) -> Result<Self, RecipeError> {
+        let list: Vec<Recipe> =
+            ron::from_str(text).map_err(|e| RecipeError::Parse(e.to_string()))?;
+        let mut reg = Self::new();
+        for recipe in list {
+            let id = recipe.recipe_id;
+            if reg.insert(recipe).is_some() {
+                return Err(RecipeError::DuplicateId(id));
+            }
+        }
+        Ok(reg)
+    }
+
+    /// Add or replace a recipe. Returns the previous recipe with the same id, if any.
+    pub fn insert(&mut self, recipe: Recipe) -> Option<Recipe> {
+        self.recipes.insert(recipe.recipe_id, recipe)
+    }
+
+    pub fn get(&self, recipe_id: RecipeId) -> Option<&Recipe> {
+        self.recipes.get(&recipe_id)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.values().find(|r| r.name == name)
+    }
+
+    /// Recipes craftable in `category`, ordered by id.
+    pub fn in_category(&self, category: CraftCategory) -> impl Iterator<Item = &Recipe> {
+        self.recipes
+            .values()
+            .filter(move |r| r.category == category)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Recipe> {
+        self.recipes.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+}
@@ -0,0 +1,449 @@
+//! Research: a technology tree that unlocks building specs and recipes, and
+//! the labs that work through it by consuming science items.
+//!
+//! A spec or recipe named in some technology's `unlocks` stays locked until
+//! one of those technologies is researched; everything else is available
+//! from the start. Each technology needs `units` units of research. A lab
+//! starts a unit by consuming one `cost` worth of science items and finishes
+//! it `unit_ticks` later. Units from every lab add up, and progress on a
+//! technology is kept when research switches to another.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{InstanceId, TileGrid};
+use crate::item::{Inventory, ItemId, ItemStack};
+use crate::recipe::RecipeId;
+use crate::spec::SpecId;
+
+pub type TechId = u32;
+
+/// Built-in technology tree, embedded like the building specs.
+const BUILTIN_TECHS: &str = include_str!("../data/technologies.ron");
+
+/// Input slots of each lab.
+pub const LAB_SLOTS: usize = 4;
+
+/// Something researching a technology makes available.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Unlock {
+    Building(SpecId),
+    Recipe(RecipeId),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Technology {
+    pub tech_id: TechId,
+    pub name: String,
+    /// Technologies that must be researched before this one can start
+    #[serde(default)]
+    pub prerequisites: Vec<TechId>,
+    /// Science items a lab consumes for each unit
+    pub cost: Vec<ItemStack>,
+    /// Units needed to finish the technology
+    pub units: u32,
+    /// Ticks a lab spends on one unit
+    pub unit_ticks: u32,
+    pub unlocks: Vec<Unlock>,
+}
+
+#[derive(Debug)]
+pub enum TechError {
+    /// The definitions text could not be parsed
+    Parse(String),
+    /// Two definitions share the same `tech_id`
+    DuplicateId(TechId),
+    /// `tech` lists a prerequisite that is not defined
+    UnknownPrerequisite { tech: TechId, prerequisite: TechId },
+    /// `tech` depends on itself through its prerequisites
+    Cycle(TechId),
+}
+
+impl fmt::Display for TechError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TechError::Parse(msg) => write!(f, "failed to parse technologies: {}", msg),
+            TechError::DuplicateId(id) => write!(f, "duplicate technology id {}", id),
+            TechError::UnknownPrerequisite { tech, prerequisite } => write!(
+                f,
+                "technology {} requires unknown technology {}",
+                tech, prerequisite
+            ),
+            TechError::Cycle(id) => write!(f, "technology {} depends on itself", id),
+        }
+    }
+}
+
+impl std::error::Error for TechError {}
+
+/// Owns every `Technology`, keyed by `tech_id`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TechTree {
+    techs: BTreeMap<TechId, Technology>,
+}
+
+impl TechTree {
+    /// Create an empty tree, which locks nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tree populated from the definitions shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_ron_str(BUILTIN_TECHS).expect("built-in technologies must be valid")
+    }
+
+    /// Parse a RON list of technologies. Every prerequisite must be defined
+    /// and no technology may depend on itself.
+    pub fn from_ron_str(text: &str) -> Result<Self, TechError> {
+        let list: Vec<Technology> =
+            ron::from_str(text).map_err(|e| TechError::Parse(e.to_string()))?;
+        let mut tree = Self::new();
+        for tech in list {
+            let id = tech.tech_id;
+            if tree.insert(tech).is_some() {
+                return Err(TechError::DuplicateId(id));
+            }
+        }
+        tree.validate()?;
+        Ok(tree)
+    }
+
+    fn validate(&self) -> Result<(), TechError> {
+        for tech in self.techs.values() {
+            if let Some(&p) = tech
+                .prerequisites
+                .iter()
+                .find(|p| !self.techs.contains_key(p))
+            {
+                return Err(TechError::UnknownPrerequisite {
+                    tech: tech.tech_id,
+                    prerequisite: p,
+                });
+            }
+        }
+        // depth-first walk; a technology met again while still on the
+        // stack closes a cycle
+        let mut done = BTreeSet::new();
+        for &start in self.techs.keys() {
+            let mut stack = vec![(start, 0)];
+            let mut on_stack = BTreeSet::from([start]);
+            while let Some((id, next)) = stack.pop() {
+                let prereqs = &self.techs[&id].prerequisites;
+                if next == prereqs.len() || done.contains(&id) {
+                    on_stack.remove(&id);
+                    done.insert(id);
+                    continue;
+                }
+                stack.push((id, next + 1));
+                let p = prereqs[next];
+                if on_stack.contains(&p) {
+                    return Err(TechError::Cycle(p));
+                }
+                if !done.contains(&p) {
+                    on_stack.insert(p);
+                    stack.push((p, 0));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add or replace a technology. Returns the previous one with the same
+    /// id, if any.
+    pub fn insert(&mut self, tech: Technology) -> Option<Technology> {
+        self.techs.insert(tech.tech_id, tech)
+    }
+
+    pub fn get(&self, tech_id: TechId) -> Option<&Technology> {
+        self.techs.get(&tech_id)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Technology> {
+        self.techs.values().find(|t| t.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Technology> {
+        self.techs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.techs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.techs.is_empty()
+    }
+
+    /// Technologies that unlock `unlock`, ordered by id.
+    pub fn unlocking(&self, unlock: Unlock) -> impl Iterator<Item = &Technology> {
+        self.techs
+            .values()
+            .filter(move |t| t.unlocks.contains(&unlock))
+    }
+
+    /// Whether `item` is part of any technology's cost.
+    pub fn is_science(&self, item: ItemId) -> bool {
+        self.techs
+            .values()
+            .any(|t| t.cost.iter().any(|s| s.item == item))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResearchError {
+    UnknownTech(TechId),
+    AlreadyResearched(TechId),
+    /// `prerequisite` must be researched before `tech`
+    MissingPrerequisite {
+        tech: TechId,
+        prerequisite: TechId,
+    },
+}
+
+impl fmt::Display for ResearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResearchError::UnknownTech(id) => write!(f, "unknown technology {}", id),
+            ResearchError::AlreadyResearched(id) => {
+                write!(f, "technology {} is already researched", id)
+            }
+            ResearchError::MissingPrerequisite { tech, prerequisite } => write!(
+                f,
+                "technology {} needs technology {} first",
+                tech, prerequisite
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResearchError {}
+
+/// Research progress, saved with the grid.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Research {
+    researched: BTreeSet<TechId>,
+    /// Technology labs start new units for
+    current: Option<TechId>,
+    /// Units finished toward technologies not yet researched
+    units: BTreeMap<TechId, u32>,
+}
+
+impl Research {
+    pub fn is_researched(&self, tech: TechId) -> bool {
+        self.researched.contains(&tech)
+    }
+
+    /// Researched technologies, ordered by id.
+    pub fn researched(&self) -> impl Iterator<Item = TechId> + '_ {
+        self.researched.iter().copied()
+    }
+
+    pub fn current(&self) -> Option<TechId> {
+        self.current
+    }
+
+    /// Units finished toward `tech`; 0 once it is researched.
+    pub fn units_done(&self, tech: TechId) -> u32 {
+        self.units.get(&tech).copied().unwrap_or(0)
+    }
+}
+
+/// Per-instance research state for `Lab` specs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Lab {
+    /// Technology the unit in progress counts toward
+    unit: Option<TechId>,
+    /// Ticks spent on the unit in progress
+    progress: u32,
+    /// Science items waiting to be consumed
+    pub input: Inventory,
+}
+
+/// Something a lab did during `TileGrid::tick_labs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabEvent {
+    /// Science consumed and a unit of `tech` begun
+    Started { id: InstanceId, tech: TechId },
+    /// The last unit of `tech` finished
+    Researched { tech: TechId },
+}
+
+impl Default for Lab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lab {
+    pub fn new() -> Self {
+        Self {
+            unit: None,
+            progress: 0,
+            input: Inventory::new(LAB_SLOTS),
+        }
+    }
+
+    /// Whether a unit of research is in progress.
+    pub fn is_working(&self) -> bool {
+        self.unit.is_some()
+    }
+
+    /// Technology the unit in progress counts toward.
+    pub fn unit(&self) -> Option<TechId> {
+        self.unit
+    }
+}
+
+impl TileGrid {
+    pub fn techs(&self) -> &TechTree {
+        &self.techs
+    }
+
+    pub fn techs_mut(&mut self) -> &mut TechTree {
+        &mut self.techs
+    }
+
+    pub fn research(&self) -> &Research {
+        &self.research
+    }
+
+    /// Whether `spec_id` may be built: no technology unlocks it, or one that
+    /// does is researched.
+    pub fn is_spec_unlocked(&self, spec_id: SpecId) -> bool {
+        self.is_unlocked(Unlock::Building(spec_id))
+    }
+
+    /// Whether `recipe_id` may be chosen, by the same rule as specs.
+    pub fn is_recipe_unlocked(&self, recipe_id: RecipeId) -> bool {
+        self.is_unlocked(Unlock::Recipe(recipe_id))
+    }
+
+    fn is_unlocked(&self, unlock: Unlock) -> bool {
+        let mut techs = self.techs.unlocking(unlock).peekable();
+        techs.peek().is_none() || techs.any(|t| self.research.is_researched(t.tech_id))
+    }
+
+    /// Whether `tech` could be researched now.
+    pub fn can_research(&self, tech: TechId) -> Result<(), ResearchError> {
+        let t = self
+            .techs
+            .get(tech)
+            .ok_or(ResearchError::UnknownTech(tech))?;
+        if self.research.is_researched(tech) {
+            return Err(ResearchError::AlreadyResearched(tech));
+        }
+        match t
+            .prerequisites
+            .iter()
+            .find(|&&p| !self.research.is_researched(p))
+        {
+            Some(&prerequisite) => Err(ResearchError::MissingPrerequisite { tech, prerequisite }),
+            None => Ok(()),
+        }
+    }
+
+    /// Point labs at `tech`, or stop starting new units with `None`. Units
+    /// already under way still finish and count toward their technology.
+    pub fn set_research(&mut self, tech: Option<TechId>) -> Result<(), ResearchError> {
+        if let Some(t) = tech {
+            self.can_research(t)?;
+        }
+        self.research.current = tech;
+        Ok(())
+    }
+
+    /// Mark `tech` researched at once, without labs or prerequisites, e.g.
+    /// for scenarios that start further along.
+    pub fn finish_research(&mut self, tech: TechId) -> Result<(), ResearchError> {
+        if self.techs.get(tech).is_none() {
+            return Err(ResearchError::UnknownTech(tech));
+        }
+        self.complete(tech);
+        Ok(())
+    }
+
+    fn complete(&mut self, tech: TechId) {
+        self.research.researched.insert(tech);
+        self.research.units.remove(&tech);
+        if self.research.current == Some(tech) {
+            self.research.current = None;
+        }
+    }
+
+    pub fn lab(&self, id: InstanceId) -> Option<&Lab> {
+        self.labs.get(&id)
+    }
+
+    /// Every lab, in id order.
+    pub fn labs(&self) -> impl Iterator<Item = (InstanceId, &Lab)> {
+        self.labs.iter().map(|(&id, l)| (id, l))
+    }
+
+    /// Offer `item` to lab `id`, which takes science items only.
+    /// `None` if `id` is not a lab.
+    pub(crate) fn offer_lab_item(&mut self, id: InstanceId, item: ItemId) -> Option<bool> {
+        let lab = self.labs.get_mut(&id)?;
+        Some(self.techs.is_science(item) && lab.input.insert(&self.items, item, 1) == 0)
+    }
+
+    /// Whether lab `id` would take one `item` right now.
+    pub(crate) fn lab_accepts(&self, lab: &Lab, item: ItemId) -> bool {
+        self.techs.is_science(item) && lab.input.space_for(&self.items, item) > 0
+    }
+
+    /// Advance every lab by one tick, in instance id order. Underpowered
+    /// labs skip ticks.
+    pub fn tick_labs(&mut self) -> Vec<LabEvent> {
+        let mut events = Vec::new();
+        let ids: Vec<InstanceId> = self.labs.keys().copied().collect();
+        for id in ids {
+            if !self.draw_power(id) {
+                continue;
+            }
+            let Some(lab) = self.labs.get_mut(&id) else {
+                continue;
+            };
+            if lab.unit.is_none() {
+                let Some(tech) = self.research.current.and_then(|t| self.techs.get(t)) else {
+                    continue;
+                };
+                if !tech.cost.iter().all(|s| lab.input.count(s.item) >= s.count) {
+                    continue;
+                }
+                for s in &tech.cost {
+                    lab.input.extract(s.item, s.count);
+                }
+                lab.unit = Some(tech.tech_id);
+                lab.progress = 0;
+                events.push(LabEvent::Started {
+                    id,
+                    tech: tech.tech_id,
+                });
+            }
+            let Some(tech) = lab.unit.and_then(|t| self.techs.get(t)) else {
+                lab.unit = None;
+                continue;
+            };
+            lab.progress += 1;
+            if lab.progress < tech.unit_ticks.max(1) {
+                continue;
+            }
+            let (tech_id, units) = (tech.tech_id, tech.units);
+            lab.unit = None;
+            lab.progress = 0;
+            if self.research.is_researched(tech_id) {
+                continue;
+            }
+            let done = self.research.units.entry(tech_id).or_insert(0);
+            *done += 1;
+            if *done >= units {
+                self.complete(tech_id);
+                events.push(LabEvent::Researched { tech: tech_id });
+            }
+        }
+        events
+    }
+}
@@ -0,0 +1,162 @@
+//! Save games: the complete `World` in a versioned binary or text format.
+//!
+//! Binary saves are `SAVE_MAGIC`, the format version as a little-endian
+//! `u32`, then the bincode-encoded world. Text saves are RON with a
+//! `version` field beside the world. Tile occupancy is not stored; loading
+//! rebuilds it from the saved instances. Specs, items, recipes and
+//! technologies are not stored either; a loaded world always uses the
+//! built-in data. Entity and instance id counters are stored, so ids handed
+//! out after loading never collide with saved ones.
+
+use std::fmt;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::World;
+
+/// Version written by `save_binary` and `save_text`.
+pub const SAVE_VERSION: u32 = 7;
+
+/// First bytes of every binary save.
+pub const SAVE_MAGIC: [u8; 4] = *b"FGSV";
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// The world could not be serialized
+    Encode(String),
+    /// The data could not be decoded
+    Decode(String),
+    /// Binary data that does not start with `SAVE_MAGIC`
+    NotASave,
+    /// Written by a format version this build cannot read
+    UnsupportedVersion(u32),
+    /// Decoded, but the contents are inconsistent (e.g. overlapping buildings)
+    Corrupt(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Encode(msg) => write!(f, "failed to encode save: {}", msg),
+            SaveError::Decode(msg) => write!(f, "failed to decode save: {}", msg),
+            SaveError::NotASave => write!(f, "data is not a save file"),
+            SaveError::UnsupportedVersion(v) => write!(f, "unsupported save version {}", v),
+            SaveError::Corrupt(msg) => write!(f, "corrupt save: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+#[derive(Serialize)]
+struct TextSave<'a> {
+    version: u32,
+    world: &'a World,
+}
+
+/// Body of a text save whose version was already checked.
+#[derive(Deserialize)]
+struct TextLoad {
+    world: World,
+}
+
+/// Just the version of a text save; every other field is ignored.
+#[derive(Deserialize)]
+struct TextHeader {
+    version: u32,
+}
+
+impl World {
+    /// Encode the whole world in the compact binary format.
+    pub fn save_binary(&self) -> Result<Vec<u8>, SaveError> {
+        let body = bincode::serialize(self).map_err(|e| SaveError::Encode(e.to_string()))?;
+        let mut out = Vec::with_capacity(8 + body.len());
+        out.extend_from_slice(&SAVE_MAGIC);
+        out.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+        out.extend_from_slice(&body);
+        Ok(out)
+    }
+
+    pub fn load_binary(bytes: &[u8]) -> Result<World, SaveError> {
+        if bytes.len() < 8 || bytes[..4] != SAVE_MAGIC {
+            return Err(SaveError::NotASave);
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        let world: World =
+            bincode::deserialize(&bytes[8..]).map_err(|e| SaveError::Decode(e.to_string()))?;
+        world.finish_load()
+    }
+
+    /// Encode the whole world as human-readable RON.
+    pub fn save_text(&self) -> Result<String, SaveError> {
+        let save = TextSave {
+            version: SAVE_VERSION,
+            world: self,
+        };
+        ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+            .map_err(|e| SaveError::Encode(e.to_string()))
+    }
+
+    pub fn load_text(text: &str) -> Result<World, SaveError> {
+        let header: TextHeader =
+            ron::from_str(text).map_err(|e| SaveError::Decode(e.to_string()))?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(header.version));
+        }
+        let save: TextLoad = ron::from_str(text).map_err(|e| SaveError::Decode(e.to_string()))?;
+        save.world.finish_load()
+    }
+
+    /// Load either format, telling them apart by `SAVE_MAGIC`.
+    pub fn load(bytes: &[u8]) -> Result<World, SaveError> {
+        if bytes.starts_with(&SAVE_MAGIC) {
+            return Self::load_binary(bytes);
+        }
+        let text = std::str::from_utf8(bytes).map_err(|_| SaveError::NotASave)?;
+        Self::load_text(text)
+    }
+
+    /// 64-bit FNV-1a hash of the serialized world. Equal worlds always give
+    /// equal checksums, so replays can compare them across runs.
+    ///
+    /// # Panics
+    /// If the world cannot be serialized, which would make every checksum
+    /// meaningless.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv1a(0xcbf2_9ce4_8422_2325);
+        bincode::serialize_into(&mut hasher, self).expect("world failed to serialize");
+        hasher.0
+    }
+
+    fn finish_load(mut self) -> Result<World, SaveError> {
+        self.grid.load_builtin_data();
+        self.grid.rebuild_occupancy().map_err(|id| {
+            SaveError::Corrupt(format!(
+                "building {} overlaps another, leaves the grid or has an unknown spec",
+                id
+            ))
+        })?;
+        self.reindex();
+        Ok(self)
+    }
+}
+
+/// FNV-1a over everything written to it.
+struct Fnv1a(u64);
+
+impl io::Write for Fnv1a {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
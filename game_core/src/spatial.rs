@@ -0,0 +1,214 @@
+//! Spatial hash over entity positions, so proximity queries only look at
+//! nearby entities instead of the whole `World::entities` list.
+//!
+//! The index stores positions into `entities` bucketed by `SPATIAL_CELL_SIZE`
+//! cells. `update_physics` and the spawn helpers keep it current. Handing out
+//! mutable access through `World::entities_mut` or `World::find_player_mut`
+//! marks it stale; queries then fall back to a full scan until the next
+//! `World::reindex`.
+
+use std::collections::HashMap;
+
+use crate::{Entity, EntityType, World};
+
+/// Side length of one index cell in world units.
+pub const SPATIAL_CELL_SIZE: f32 = 64.0;
+
+type Cell = (i32, i32);
+
+fn cell_of(x: f32, y: f32) -> Cell {
+    (
+        (x / SPATIAL_CELL_SIZE).floor() as i32,
+        (y / SPATIAL_CELL_SIZE).floor() as i32,
+    )
+}
+
+fn dist2(e: &Entity, (x, y): (f32, f32)) -> f32 {
+    let (dx, dy) = (e.transform.x - x, e.transform.y - y);
+    dx * dx + dy * dy
+}
+
+/// The closer of `best` and `e` to `to`, the lower id on a tie.
+fn closer<'a>(best: Option<&'a Entity>, e: &'a Entity, to: (f32, f32)) -> Option<&'a Entity> {
+    match best {
+        Some(b) if (dist2(b, to), b.id) <= (dist2(e, to), e.id) => Some(b),
+        _ => Some(e),
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SpatialIndex {
+    /// Positions in `World::entities`, by cell
+    cells: HashMap<Cell, Vec<usize>>,
+    /// Smallest and largest occupied cell, bounding nearest-neighbour searches
+    extent: Option<(Cell, Cell)>,
+    /// Position of the first player entity
+    player: Option<usize>,
+    /// Cleared whenever `entities` may have changed behind the index's back
+    current: bool,
+}
+
+impl SpatialIndex {
+    pub(crate) fn rebuild(&mut self, entities: &[Entity]) {
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+        self.extent = None;
+        self.player = None;
+        for (slot, e) in entities.iter().enumerate() {
+            self.push(slot, e);
+        }
+        self.cells.retain(|_, bucket| !bucket.is_empty());
+        self.current = true;
+    }
+
+    /// Add `e`, which sits at position `slot` right after the last indexed one.
+    pub(crate) fn push(&mut self, slot: usize, e: &Entity) {
+        let cell = cell_of(e.transform.x, e.transform.y);
+        self.cells.entry(cell).or_default().push(slot);
+        self.extent = Some(match self.extent {
+            None => (cell, cell),
+            Some((lo, hi)) => (
+                (lo.0.min(cell.0), lo.1.min(cell.1)),
+                (hi.0.max(cell.0), hi.1.max(cell.1)),
+            ),
+        });
+        if e.ty == EntityType::Player && self.player.is_none() {
+            self.player = Some(slot);
+        }
+    }
+}
+
+impl World {
+    /// Bring the spatial index up to date after editing `entities` directly.
+    pub fn reindex(&mut self) {
+        self.index.rebuild(&self.entities);
+    }
+
+    pub(crate) fn index_is_current(&self) -> bool {
+        self.index.current
+    }
+
+    /// Every entity, in spawn order except where removals reordered them.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Mutable access to the entity list. The index counts as stale until
+    /// the next `reindex`, which `update_physics` does every tick.
+    pub fn entities_mut(&mut self) -> &mut Vec<Entity> {
+        self.index.current = false;
+        &mut self.entities
+    }
+
+    /// Entity positions from the index, in ascending order, for every cell
+    /// overlapping the given box.
+    pub(crate) fn slots_in_box(&self, min: (f32, f32), max: (f32, f32)) -> Vec<usize> {
+        if !self.index_is_current() {
+            return (0..self.entities.len()).collect();
+        }
+        let (lo, hi) = (cell_of(min.0, min.1), cell_of(max.0, max.1));
+        let mut slots = Vec::new();
+        if let Some((elo, ehi)) = self.index.extent {
+            for cy in lo.1.max(elo.1)..=hi.1.min(ehi.1) {
+                for cx in lo.0.max(elo.0)..=hi.0.min(ehi.0) {
+                    if let Some(bucket) = self.index.cells.get(&(cx, cy)) {
+                        slots.extend_from_slice(bucket);
+                    }
+                }
+            }
+        }
+        slots.sort_unstable();
+        slots
+    }
+
+    /// Entities whose centre lies within `radius` of (x, y), in
+    /// `entities` order.
+    pub fn entities_within(&self, x: f32, y: f32, radius: f32) -> Vec<&Entity> {
+        let r2 = radius * radius;
+        self.slots_in_box((x - radius, y - radius), (x + radius, y + radius))
+            .into_iter()
+            .map(|i| &self.entities[i])
+            .filter(|e| {
+                let (dx, dy) = (e.transform.x - x, e.transform.y - y);
+                dx * dx + dy * dy <= r2
+            })
+            .collect()
+    }
+
+    /// Entities whose centre lies inside the rectangle between the two
+    /// corners (inclusive), in `entities` order.
+    pub fn entities_in_rect(&self, min: (f32, f32), max: (f32, f32)) -> Vec<&Entity> {
+        self.slots_in_box(min, max)
+            .into_iter()
+            .map(|i| &self.entities[i])
+            .filter(|e| {
+                let (ex, ey) = (e.transform.x, e.transform.y);
+                ex >= min.0 && ex <= max.0 && ey >= min.1 && ey <= max.1
+            })
+            .collect()
+    }
+
+    /// The entity of type `ty` closest to (x, y). Ties go to the lower id.
+    pub fn nearest_of_type(&self, x: f32, y: f32, ty: EntityType) -> Option<&Entity> {
+        let mut best: Option<&Entity> = None;
+        if !self.index_is_current() {
+            for e in self.entities.iter().filter(|e| e.ty == ty) {
+                best = closer(best, e, (x, y));
+            }
+            return best;
+        }
+
+        let (lo, hi) = self.index.extent?;
+        let (cx, cy) = cell_of(x, y);
+        // rings closer than the occupied extent are empty, rings past it too
+        let first_ring = [lo.0 - cx, cx - hi.0, lo.1 - cy, cy - hi.1, 0]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+        let last_ring = [cx - lo.0, hi.0 - cx, cy - lo.1, hi.1 - cy]
+            .into_iter()
+            .max()
+            .unwrap_or(0)
+            .max(0);
+        for ring in first_ring..=last_ring {
+            // everything in this ring is at least (ring - 1) cells away
+            if let Some(b) = best {
+                let reach = (ring - 1).max(0) as f32 * SPATIAL_CELL_SIZE;
+                if dist2(b, (x, y)) <= reach * reach {
+                    break;
+                }
+            }
+            for gy in (cy - ring).max(lo.1)..=(cy + ring).min(hi.1) {
+                // full rows at the top and bottom edge, only the two side
+                // cells in between
+                let row: Vec<i32> = if (gy - cy).abs() == ring {
+                    ((cx - ring).max(lo.0)..=(cx + ring).min(hi.0)).collect()
+                } else {
+                    vec![cx - ring, cx + ring]
+                };
+                for gx in row {
+                    let Some(bucket) = self.index.cells.get(&(gx, gy)) else {
+                        continue;
+                    };
+                    for e in bucket.iter().map(|&i| &self.entities[i]) {
+                        if e.ty == ty {
+                            best = closer(best, e, (x, y));
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Position of the player in `entities`, from the index when it is current.
+    pub(crate) fn player_slot(&self) -> Option<usize> {
+        if self.index_is_current() {
+            return self.index.player;
+        }
+        self.entities
+            .iter()
+            .position(|e| e.ty == EntityType::Player)
+    }
+}
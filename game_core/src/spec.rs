@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Size2;
+use crate::power::PowerRole;
+use crate::recipe::CraftCategory;
+
+pub type SpecId = u32;
+
+/// Built-in building definitions, embedded so the wasm build needs no file access.
+const BUILTIN_SPECS: &str = include_str!("../data/buildings.ron");
+
+/// Broad grouping used by the UI to organise the build palette.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BuildingCategory {
+    Logistics,
+    Production,
+    Mining,
+    Power,
+    Storage,
+    #[default]
+    Other,
+}
+
+/// What a building does once placed. Subsystems attach per-instance state based on this.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BuildingKind {
+    /// Occupies tiles, no behaviour of its own
+    #[default]
+    Structure,
+    /// Moves items toward the tile its rotation faces.
+    /// `speed` is in belt steps per tick (see `BELT_TILE_LEN`).
+    Conveyor { speed: u32 },
+    /// Crafts recipes of `category`. With `auto_select` the machine picks any
+    /// recipe whose inputs it holds; otherwise the player chooses one.
+    Crafter {
+        category: CraftCategory,
+        auto_select: bool,
+    },
+    /// Extracts one unit of ore from under its footprint every `interval`
+    /// ticks and pushes it out of its front face.
+    Drill { interval: u32 },
+    /// Moves up to `capacity` items from the tile behind to the tile in
+    /// front; each half swing takes `swing_ticks`.
+    Inserter { swing_ticks: u32, capacity: u32 },
+    /// Fills its fluid box with `rate` units of water per tick while the
+    /// tile behind it is water.
+    Pump { rate: u32 },
+    /// Consumes science items to research the current technology.
+    Lab,
+}
+
+/// Static description of a building type. Instances refer to it by `spec_id`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BuildingSpec {
+    pub spec_id: SpecId,
+    pub name: String,
+    pub size: Size2,
+    /// RGBA fill color used when no sprite is available
+    #[serde(default = "default_color")]
+    pub color: (f32, f32, f32, f32),
+    /// Optional sprite key for the renderer to resolve
+    #[serde(default)]
+    pub sprite: Option<String>,
+    #[serde(default)]
+    pub category: BuildingCategory,
+    #[serde(default)]
+    pub kind: BuildingKind,
+    /// Slots of general storage attached to each placed instance (0 for none)
+    #[serde(default)]
+    pub inventory_slots: u32,
+    /// Part played in electric networks, independent of `kind`
+    #[serde(default)]
+    pub power: PowerRole,
+    /// Units of fluid each instance can hold (0 for no fluid box)
+    #[serde(default)]
+    pub fluid_capacity: u32,
+}
+
+fn default_color() -> (f32, f32, f32, f32) {
+    (0.7, 0.7, 0.7, 0.9)
+}
+
+#[derive(Debug)]
+pub enum SpecError {
+    /// The definitions text could not be parsed
+    Parse(String),
+    /// Two definitions share the same `spec_id`
+    DuplicateId(SpecId),
+    /// Placed buildings still use the spec being replaced
+    InUse(SpecId),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Parse(msg) => write!(f, "failed to parse building specs: {}", msg),
+            SpecError::DuplicateId(id) => write!(f, "duplicate building spec id {}", id),
+            SpecError::InUse(id) => write!(f, "building spec {} is used by placed buildings", id),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// Owns every known `BuildingSpec`, keyed by `spec_id`.
+/// Iteration is ordered by id so UI listings are stable.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SpecRegistry {
+    specs: BTreeMap<SpecId, BuildingSpec>,
+}
+
+impl SpecRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry populated from the definitions shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_ron_str(BUILTIN_SPECS).expect("built-in building specs must be valid")
+    }
+
+    /// Parse a RON list of building definitions.
+    pub fn from_ron_str(text: &str) -> Result<Self, SpecError> {
+        let list: Vec<BuildingSpec> =
+            ron::from_str(text).map_err(|e| SpecError::Parse(e.to_string()))?;
+        let mut reg = Self::new();
+        for spec in list {
+            let id = spec.spec_id;
+            if reg.insert(spec).is_some() {
+                return Err(SpecError::DuplicateId(id));
+            }
+        }
+        Ok(reg)
+    }
+
+    /// Add or replace a spec. Returns the previous spec with the same id, if any.
+    pub fn insert(&mut self, spec: BuildingSpec) -> Option<BuildingSpec> {
+        self.specs.insert(spec.spec_id, spec)
+    }
+
+    pub fn get(&self, spec_id: SpecId) -> Option<&BuildingSpec> {
+        self.specs.get(&spec_id)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&BuildingSpec> {
+        self.specs.values().find(|s| s.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BuildingSpec> {
+        self.specs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.specs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+}
@@ -0,0 +1,75 @@
+//! Ground layer underneath buildings: terrain kinds and ore deposits.
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{PlacementError, TileGrid, TilePos, TileRect};
+use crate::item::ItemId;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerrainKind {
+    #[default]
+    Ground,
+    Water,
+    Cliff,
+}
+
+impl TerrainKind {
+    /// Whether ordinary buildings may stand on this terrain.
+    pub fn is_buildable(self) -> bool {
+        matches!(self, TerrainKind::Ground)
+    }
+}
+
+/// A finite amount of a raw resource lying in one tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerrainTile {
+    pub kind: TerrainKind,
+    pub deposit: Option<Deposit>,
+}
+
+impl TileGrid {
+    /// Terrain at `pos`. Tiles never set are plain ground.
+    pub fn terrain(&self, pos: TilePos) -> TerrainTile {
+        self.terrain.get(&pos).copied().unwrap_or_default()
+    }
+
+    /// Replace the terrain at `pos`. Setting plain ground clears the entry.
+    /// Tiles outside the grid or under a building cannot change.
+    pub fn set_terrain(&mut self, pos: TilePos, tile: TerrainTile) -> Result<(), PlacementError> {
+        if !self.in_bounds(pos) {
+            return Err(PlacementError::OutOfBounds);
+        }
+        if self.tile_occupant(pos).is_some() {
+            return Err(PlacementError::Occupied);
+        }
+        if tile == TerrainTile::default() {
+            self.terrain.remove(&pos);
+        } else {
+            self.terrain.insert(pos, tile);
+        }
+        Ok(())
+    }
+
+    pub fn deposit(&self, pos: TilePos) -> Option<Deposit> {
+        self.terrain(pos).deposit
+    }
+
+    /// Every tile that is not plain ground, in position order.
+    pub fn terrain_tiles(&self) -> impl Iterator<Item = (TilePos, TerrainTile)> + '_ {
+        self.terrain.iter().map(|(p, t)| (*p, *t))
+    }
+
+    /// Tiles of `rect` that are not plain ground, in position order.
+    pub fn terrain_in(&self, rect: TileRect) -> impl Iterator<Item = (TilePos, TerrainTile)> + '_ {
+        (rect.min.x..=rect.max.x).flat_map(move |x| {
+            let (top, bottom) = (TilePos { x, y: rect.min.y }, TilePos { x, y: rect.max.y });
+            self.terrain.range(top..=bottom).map(|(p, t)| (*p, *t))
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+/// Assembler at (1,1) with an inserter and a belt running east from it.
+fn source() -> TileGrid {
+    let mut g = TileGrid::new(16, 16);
+    let asm = g.place(spec("assembler"), at(1, 1), Rotation::R0).unwrap();
+    g.set_recipe(asm, Some(recipe("iron-gear"))).unwrap();
+    let ins = g.place(spec("inserter"), at(4, 2), Rotation::R0).unwrap();
+    g.inserter_mut(ins).unwrap().filter = Some(item("coal"));
+    g.place(spec("conveyor"), at(5, 2), Rotation::R90).unwrap();
+    // partly outside the capture below
+    g.place(spec("chest"), at(6, 2), Rotation::R0).unwrap();
+    g
+}
+
+fn capture(g: &TileGrid) -> Blueprint {
+    g.capture_blueprint(at(5, 3), at(1, 1))
+}
+
+#[test]
+fn capture_keeps_layout_and_settings() {
+    let bp = capture(&source());
+    assert_eq!(bp.size, Size2 { w: 5, h: 3 });
+    assert_eq!(bp.entries.len(), 3);
+
+    let asm = bp
+        .entries
+        .iter()
+        .find(|e| e.spec_id == spec("assembler"))
+        .unwrap();
+    assert_eq!(asm.offset, at(0, 0));
+    assert_eq!(asm.recipe, Some(recipe("iron-gear")));
+    let belt = bp
+        .entries
+        .iter()
+        .find(|e| e.spec_id == spec("conveyor"))
+        .unwrap();
+    assert_eq!((belt.offset, belt.rotation), (at(4, 1), Rotation::R90));
+}
+
+#[test]
+fn paste_recreates_buildings_and_settings() {
+    let bp = capture(&source());
+    let mut g = TileGrid::new(16, 16);
+    let ids = g.paste_blueprint(&bp, at(8, 8)).unwrap();
+    assert_eq!(ids.len(), 3);
+
+    let asm = g.tile_occupant(at(8, 8)).unwrap();
+    assert_eq!(g.machine(asm).unwrap().recipe(), Some(recipe("iron-gear")));
+    let ins = g.tile_occupant(at(11, 9)).unwrap();
+    assert_eq!(g.inserter(ins).unwrap().filter, Some(item("coal")));
+    let belt = g.tile_occupant(at(12, 9)).unwrap();
+    assert_eq!(g.instances[&belt].rotation, Rotation::R90);
+}
+
+#[test]
+fn rotating_turns_offsets_and_buildings() {
+    let g = source();
+    let bp = capture(&g).rotated(Rotation::R90, g.specs());
+    assert_eq!(bp.size, Size2 { w: 3, h: 5 });
+
+    let mut target = TileGrid::new(16, 16);
+    target.paste_blueprint(&bp, at(0, 0)).unwrap();
+    // the east-facing row now runs south along x = 1
+    let asm = target.tile_occupant(at(0, 0)).unwrap();
+    assert_eq!(target.footprint(asm).unwrap().len(), 9);
+    let ins = target.tile_occupant(at(1, 3)).unwrap();
+    assert_eq!(target.instances[&ins].rotation, Rotation::R90);
+    let belt = target.tile_occupant(at(1, 4)).unwrap();
+    assert_eq!(target.instances[&belt].rotation, Rotation::R180);
+
+    // four quarter turns give the original back
+    let full = capture(&g)
+        .rotated(Rotation::R270, g.specs())
+        .rotated(Rotation::R90, g.specs());
+    assert_eq!(full, capture(&g));
+}
+
+#[test]
+fn conflicts_are_reported_by_tile_and_nothing_is_placed() {
+    let bp = capture(&source());
+    let mut g = TileGrid::new(16, 16);
+    g.place(spec("chest"), at(3, 4), Rotation::R0).unwrap();
+    water(&mut g, at(4, 4));
+
+    // the last belt tile hangs over the right edge
+    let conflicts = g.blueprint_conflicts(&bp, at(12, 3));
+    assert_eq!(conflicts, vec![at(16, 4)]);
+
+    let conflicts = g.blueprint_conflicts(&bp, at(2, 3));
+    assert_eq!(conflicts, vec![at(3, 4), at(4, 4)]);
+    assert!(matches!(
+        g.paste_blueprint(&bp, at(2, 3)),
+        Err(BlueprintError::Conflicts(t)) if t == conflicts
+    ));
+    assert_eq!(g.instances.len(), 1);
+}
+
+#[test]
+fn text_round_trip() {
+    let bp = capture(&source());
+    let text = bp.to_text().unwrap();
+    assert!(text.starts_with("FBP1:"));
+    assert!(text.chars().all(|c| c.is_ascii_graphic()));
+    assert_eq!(Blueprint::from_text(&format!("  {}\n", text)).unwrap(), bp);
+
+    assert!(matches!(
+        Blueprint::from_text("hello"),
+        Err(BlueprintError::NotABlueprint)
+    ));
+    assert!(matches!(
+        Blueprint::from_text(&text.replacen("FBP1", "FBP7", 1)),
+        Err(BlueprintError::UnsupportedVersion(7))
+    ));
+    assert!(matches!(
+        Blueprint::from_text("FBP1:!!!"),
+        Err(BlueprintError::Decode(_))
+    ));
+}
+
+#[test]
+fn unknown_spec_is_rejected() {
+    let mut bp = capture(&source());
+    bp.entries[0].spec_id = 999;
+    let mut g = TileGrid::new(16, 16);
+    assert!(matches!(
+        g.paste_blueprint(&bp, at(0, 0)),
+        Err(BlueprintError::Placement(PlacementError::UnknownSpec(999)))
+    ));
+    assert!(g.instances.is_empty());
+}
@@ -0,0 +1,149 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+/// A world with a wall of chests filling tile column 5 (x 160..192) from
+/// tile row 0 to 9.
+fn walled() -> World {
+    let mut world = World::new();
+    for y in 0..10 {
+        world
+            .grid
+            .place(spec("chest"), TilePos { x: 5, y }, Rotation::R0)
+            .unwrap();
+    }
+    world
+}
+
+fn run(world: &mut World, ticks: u32) {
+    for _ in 0..ticks {
+        world.update_physics(1.0 / 60.0);
+    }
+}
+
+fn distance(a: &Entity, b: &Entity) -> f32 {
+    (a.transform.x - b.transform.x).hypot(a.transform.y - b.transform.y)
+}
+
+#[test]
+fn overlapping_entities_are_pushed_apart() {
+    let mut world = World::new();
+    world.spawn_player(100.0, 100.0);
+    world.spawn_enemy(110.0, 100.0);
+    world.spawn_enemy(100.0, 100.0);
+    world.update_physics(0.0);
+
+    let e = world.entities();
+    assert!(distance(&e[0], &e[1]) >= e[0].radius + e[1].radius - 0.5);
+    assert!(distance(&e[0], &e[2]) >= e[0].radius + e[2].radius - 0.5);
+    assert!(distance(&e[1], &e[2]) >= e[1].radius + e[2].radius - 0.5);
+}
+
+#[test]
+fn rules_choose_which_types_collide() {
+    let mut world = World::new();
+    world
+        .collision
+        .set_entities_collide(EntityType::Enemy, EntityType::Enemy, false);
+    world.spawn_enemy(100.0, 100.0);
+    world.spawn_enemy(105.0, 100.0);
+    world.spawn_player(300.0, 100.0);
+    world.spawn_enemy(305.0, 100.0);
+    world.update_physics(0.0);
+
+    let e = world.entities();
+    assert_eq!(e[0].transform.x, 100.0);
+    assert_eq!(e[1].transform.x, 105.0);
+    assert!(distance(&e[2], &e[3]) > 27.0);
+}
+
+#[test]
+fn walls_stop_movement() {
+    let mut world = walled();
+    world.spawn_player(100.0, 16.0);
+    world.entities_mut()[0].velocity.vx = 300.0;
+    run(&mut world, 60);
+
+    let p = &world.entities()[0];
+    assert!((p.transform.x - (160.0 - p.radius)).abs() < 0.01);
+    assert_eq!(p.velocity.vx, 0.0);
+    assert!(!world.circle_hits_buildings(p.transform.x, p.transform.y, p.radius - 0.1));
+}
+
+#[test]
+fn diagonal_motion_slides_along_walls() {
+    let mut world = walled();
+    world.spawn_player(130.0, 50.0);
+    world.entities_mut()[0].velocity = Velocity {
+        vx: 200.0,
+        vy: 90.0,
+    };
+    run(&mut world, 60);
+
+    let p = &world.entities()[0];
+    assert!((p.transform.x - (160.0 - p.radius)).abs() < 0.01);
+    assert!((p.transform.y - 140.0).abs() < 0.01);
+    assert_eq!(p.velocity.vy, 90.0);
+}
+
+#[test]
+fn fast_entities_do_not_tunnel() {
+    let mut world = walled();
+    world.spawn_enemy(100.0, 80.0);
+    // far more than a tile per tick
+    world.entities_mut()[0].velocity.vx = 6000.0;
+    run(&mut world, 3);
+    assert!(world.entities()[0].transform.x < 160.0);
+}
+
+#[test]
+fn unblocked_types_walk_through_buildings() {
+    let mut world = walled();
+    world
+        .collision
+        .set_blocked_by_buildings(EntityType::Enemy, false);
+    world.spawn_enemy(100.0, 80.0);
+    world.entities_mut()[0].velocity.vx = 300.0;
+    run(&mut world, 60);
+    assert!(world.entities()[0].transform.x > 350.0);
+}
+
+#[test]
+fn crowds_settle_the_same_way_every_time() {
+    let build = || {
+        let mut world = walled();
+        for i in 0..40 {
+            world.spawn_enemy(120.0 + (i % 5) as f32, 60.0 + (i / 5) as f32);
+        }
+        for e in world.entities_mut() {
+            e.velocity.vx = 150.0;
+        }
+        run(&mut world, 30);
+        world
+    };
+    let (a, b) = (build(), build());
+    assert_eq!(a.checksum(), b.checksum());
+    for e in a.entities() {
+        assert!(!a.circle_hits_buildings(e.transform.x, e.transform.y, e.radius - 0.5));
+    }
+}
+
+#[test]
+fn separated_entities_stay_inside_the_world() {
+    let mut world = World::new();
+    world.spawn_player(1000.0, 0.0);
+    world.spawn_enemy(995.0, 0.0);
+    world.update_physics(0.0);
+
+    for e in world.entities() {
+        assert!(e.transform.x <= 1000.0, "{} at {}", e.id, e.transform.x);
+    }
+    // the index follows the clamped positions
+    let edge: Vec<EntityType> = world
+        .entities_in_rect((999.0, -1.0), (1001.0, 1.0))
+        .iter()
+        .map(|e| e.ty)
+        .collect();
+    assert_eq!(edge, vec![EntityType::Player]);
+}
@@ -0,0 +1,118 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+/// Physics then combat for `ticks` 60 Hz ticks, collecting the events.
+fn run(world: &mut World, ticks: u32) -> Vec<CombatEvent> {
+    let mut events = Vec::new();
+    for _ in 0..ticks {
+        world.update_physics(1.0 / 60.0);
+        events.extend(world.tick_combat(1.0 / 60.0));
+    }
+    events
+}
+
+fn projectiles(world: &World) -> usize {
+    world
+        .entities()
+        .iter()
+        .filter(|e| e.ty == EntityType::Projectile)
+        .count()
+}
+
+#[test]
+fn projectile_damages_the_enemy_it_hits_and_disappears() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.spawn_enemy(200.0, 0.0);
+    let shot = world.fire_projectile(1, 200.0, 0.0).unwrap();
+    assert_eq!(projectiles(&world), 1);
+
+    let events = run(&mut world, 30);
+    assert_eq!(
+        events,
+        vec![CombatEvent::Damaged {
+            target: 2,
+            damage: PROJECTILE_DAMAGE,
+            x: 200.0,
+            y: 0.0,
+        }]
+    );
+    assert_eq!(projectiles(&world), 0);
+    assert!(world.entities().iter().all(|e| e.id != shot));
+    let enemy = world.entities().iter().find(|e| e.id == 2).unwrap();
+    assert_eq!(
+        enemy.health.unwrap().current,
+        ENEMY_HEALTH - PROJECTILE_DAMAGE
+    );
+}
+
+#[test]
+fn enemy_dies_after_enough_hits() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.spawn_enemy(0.0, 200.0);
+    world.fire_projectile(1, 0.0, 200.0);
+    run(&mut world, 5);
+    world.fire_projectile(1, 0.0, 200.0);
+    let events = run(&mut world, 30);
+
+    assert!(events.contains(&CombatEvent::Died {
+        id: 2,
+        ty: EntityType::Enemy,
+        x: 0.0,
+        y: 200.0,
+    }));
+    assert_eq!(world.entities().len(), 1);
+    assert!(world.entities_within(0.0, 200.0, 50.0).is_empty());
+}
+
+#[test]
+fn projectiles_expire_and_stop_at_buildings() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.fire_projectile(1, -100.0, 0.0);
+    run(&mut world, 59);
+    assert_eq!(projectiles(&world), 1);
+    run(&mut world, 2);
+    assert_eq!(projectiles(&world), 0);
+
+    world
+        .grid
+        .place(spec("chest"), at(3, -1), Rotation::R0)
+        .unwrap();
+    world.spawn_enemy(200.0, -16.0);
+    world.fire_projectile(1, 200.0, -16.0);
+    let events = run(&mut world, 30);
+    assert!(events.is_empty());
+    assert_eq!(projectiles(&world), 0);
+}
+
+#[test]
+fn shooters_are_never_hit_by_their_own_projectiles() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.fire_projectile(1, 100.0, 0.0);
+    // run the player straight into its own shot
+    world.entities_mut()[0].velocity.vx = 2000.0;
+    let events = run(&mut world, 10);
+    assert!(events.is_empty());
+    assert_eq!(
+        world.find_player().unwrap().health,
+        Some(Health::new(PLAYER_HEALTH))
+    );
+}
+
+#[test]
+fn touching_enemies_hurt_the_player() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.spawn_enemy(28.0, 0.0);
+    world.spawn_enemy(-28.0, 0.0);
+    let events = run(&mut world, 60);
+
+    assert_eq!(events.len(), 60);
+    let health = world.find_player().unwrap().health.unwrap();
+    assert!((health.current - (PLAYER_HEALTH - 2.0 * ENEMY_CONTACT_DPS)).abs() < 0.01);
+}
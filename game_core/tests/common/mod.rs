@@ -0,0 +1,79 @@
+//! Fixtures shared by the integration tests. Built-in specs, items, recipes
+//! and technologies are looked up by name, so renumbering the data files
+//! cannot silently change what a test builds.
+#![allow(dead_code)]
+
+use std::sync::OnceLock;
+
+use game_core::*;
+
+pub fn at(x: i32, y: i32) -> TilePos {
+    TilePos { x, y }
+}
+
+pub fn spec(name: &str) -> SpecId {
+    static SPECS: OnceLock<SpecRegistry> = OnceLock::new();
+    SPECS
+        .get_or_init(SpecRegistry::builtin)
+        .by_name(name)
+        .unwrap_or_else(|| panic!("no building spec named {}", name))
+        .spec_id
+}
+
+pub fn item(name: &str) -> ItemId {
+    static ITEMS: OnceLock<ItemRegistry> = OnceLock::new();
+    ITEMS
+        .get_or_init(ItemRegistry::builtin)
+        .by_name(name)
+        .unwrap_or_else(|| panic!("no item named {}", name))
+        .item_id
+}
+
+pub fn recipe(name: &str) -> RecipeId {
+    static RECIPES: OnceLock<RecipeRegistry> = OnceLock::new();
+    RECIPES
+        .get_or_init(RecipeRegistry::builtin)
+        .by_name(name)
+        .unwrap_or_else(|| panic!("no recipe named {}", name))
+        .recipe_id
+}
+
+pub fn tech(name: &str) -> TechId {
+    static TECHS: OnceLock<TechTree> = OnceLock::new();
+    TECHS
+        .get_or_init(TechTree::builtin)
+        .by_name(name)
+        .unwrap_or_else(|| panic!("no technology named {}", name))
+        .tech_id
+}
+
+/// A pole at `pole` with a generator just east of it, powering everything
+/// within two tiles of the pole.
+pub fn power(g: &mut TileGrid, pole: TilePos) {
+    g.place(spec("pole"), pole, Rotation::R0).unwrap();
+    g.place(spec("generator"), at(pole.x + 1, pole.y), Rotation::R0)
+        .unwrap();
+}
+
+/// `amount` units of `item` lying in the ground at `pos`.
+pub fn deposit(g: &mut TileGrid, pos: TilePos, item: ItemId, amount: u32) {
+    g.set_terrain(
+        pos,
+        TerrainTile {
+            kind: TerrainKind::Ground,
+            deposit: Some(Deposit { item, amount }),
+        },
+    )
+    .unwrap();
+}
+
+pub fn water(g: &mut TileGrid, pos: TilePos) {
+    g.set_terrain(
+        pos,
+        TerrainTile {
+            kind: TerrainKind::Water,
+            deposit: None,
+        },
+    )
+    .unwrap();
+}
@@ -0,0 +1,93 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+fn tick(g: &mut TileGrid, n: u32) {
+    for _ in 0..n {
+        g.tick_power();
+        g.tick_drills();
+    }
+}
+
+#[test]
+fn output_tile_follows_rotation() {
+    let mut g = TileGrid::new(10, 10);
+    let d = g.place(spec("drill"), at(2, 2), Rotation::R0).unwrap();
+    assert_eq!(g.output_tile(d), Some(at(4, 2)));
+    g.remove(d);
+    let d = g.place(spec("drill"), at(2, 2), Rotation::R270).unwrap();
+    assert_eq!(g.output_tile(d), Some(at(2, 1)));
+}
+
+#[test]
+fn drill_mines_into_chest_every_interval() {
+    let mut g = TileGrid::new(10, 10);
+    deposit(&mut g, at(0, 0), item("iron-ore"), 10);
+    let d = g.place(spec("drill"), at(0, 0), Rotation::R0).unwrap();
+    let chest = g.place(spec("chest"), at(2, 0), Rotation::R0).unwrap();
+    power(&mut g, at(0, 3));
+    tick(&mut g, 59);
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 0);
+    tick(&mut g, 1);
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 1);
+    tick(&mut g, 120);
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 3);
+    assert_eq!(g.deposit(at(0, 0)).unwrap().amount, 7);
+    assert_eq!(g.drill(d).unwrap().status(), DrillStatus::Working);
+}
+
+#[test]
+fn drill_feeds_belt() {
+    let mut g = TileGrid::new(10, 10);
+    deposit(&mut g, at(1, 1), item("iron-ore"), 10);
+    g.place(spec("drill"), at(0, 0), Rotation::R0).unwrap();
+    let belt = g.place(spec("conveyor"), at(2, 0), Rotation::R0).unwrap();
+    power(&mut g, at(0, 3));
+    tick(&mut g, 60);
+    assert_eq!(g.belt(belt).unwrap().len(), 1);
+}
+
+#[test]
+fn drill_without_room_is_blocked() {
+    let mut g = TileGrid::new(10, 10);
+    deposit(&mut g, at(0, 0), item("iron-ore"), 10);
+    let d = g.place(spec("drill"), at(0, 0), Rotation::R0).unwrap();
+    power(&mut g, at(0, 3));
+    tick(&mut g, 60);
+    let drill = g.drill(d).unwrap();
+    assert_eq!(drill.status(), DrillStatus::OutputBlocked);
+    assert_eq!(drill.held(), Some(item("iron-ore")));
+    // nothing more is mined while blocked
+    tick(&mut g, 600);
+    assert_eq!(g.deposit(at(0, 0)).unwrap().amount, 9);
+}
+
+#[test]
+fn depleted_deposits_stop_the_drill() {
+    let mut g = TileGrid::new(10, 10);
+    deposit(&mut g, at(0, 0), item("iron-ore"), 1);
+    deposit(&mut g, at(1, 1), item("iron-ore"), 1);
+    let d = g.place(spec("drill"), at(0, 0), Rotation::R0).unwrap();
+    let chest = g.place(spec("chest"), at(2, 0), Rotation::R0).unwrap();
+    power(&mut g, at(0, 3));
+    tick(&mut g, 120);
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 2);
+    assert_eq!(g.deposit(at(0, 0)), None);
+    assert_eq!(g.deposit(at(1, 1)), None);
+    tick(&mut g, 60);
+    assert_eq!(g.drill(d).unwrap().status(), DrillStatus::Depleted);
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 2);
+}
+
+#[test]
+fn unpowered_drill_does_not_mine() {
+    let mut g = TileGrid::new(10, 10);
+    deposit(&mut g, at(0, 0), item("iron-ore"), 10);
+    let d = g.place(spec("drill"), at(0, 0), Rotation::R0).unwrap();
+    let chest = g.place(spec("chest"), at(2, 0), Rotation::R0).unwrap();
+    tick(&mut g, 120);
+    assert_eq!(g.power_satisfaction(d), Some(0));
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 0);
+    assert_eq!(g.deposit(at(0, 0)).unwrap().amount, 10);
+}
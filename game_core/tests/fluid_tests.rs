@@ -0,0 +1,186 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+fn place(g: &mut TileGrid, spec: SpecId, x: i32, y: i32) -> InstanceId {
+    g.place(spec, at(x, y), Rotation::R0).unwrap()
+}
+
+fn fill(g: &mut TileGrid, id: InstanceId, fluid: ItemId, amount: u32) {
+    g.fluid_box_mut(id).unwrap().insert(fluid, amount);
+}
+
+fn amount(g: &TileGrid, id: InstanceId) -> u32 {
+    g.fluid_box(id).unwrap().amount()
+}
+
+#[test]
+fn pump_draws_from_water_behind_it() {
+    let mut g = TileGrid::new(10, 10);
+    water(&mut g, at(0, 0));
+    let pump = place(&mut g, spec("pump"), 1, 0);
+    let dry = place(&mut g, spec("pump"), 5, 5);
+    g.tick_fluids();
+    let b = g.fluid_box(pump).unwrap();
+    assert_eq!((b.fluid(), b.amount()), (Some(item("water")), 20));
+    assert_eq!(amount(&g, dry), 0);
+}
+
+#[test]
+fn adjacent_pipes_link_into_networks() {
+    let mut g = TileGrid::new(10, 10);
+    let a = place(&mut g, spec("pipe"), 0, 0);
+    let b = place(&mut g, spec("pipe"), 1, 0);
+    let c = place(&mut g, spec("pipe"), 3, 0);
+    assert_eq!(g.fluid_links().collect::<Vec<_>>(), vec![(a, b)]);
+    assert_eq!(
+        g.fluid_network(a).into_iter().collect::<Vec<_>>(),
+        vec![a, b]
+    );
+
+    let bridge = place(&mut g, spec("pipe"), 2, 0);
+    assert!(g.fluid_network(a).contains(&c));
+    g.remove(bridge);
+    assert!(!g.fluid_network(a).contains(&c));
+    assert_eq!(g.fluid_links().count(), 1);
+}
+
+#[test]
+fn flow_conserves_fluid_and_levels_out() {
+    let mut g = TileGrid::new(10, 10);
+    let pipes: Vec<InstanceId> = (0..5).map(|x| place(&mut g, spec("pipe"), x, 0)).collect();
+    fill(&mut g, pipes[0], item("water"), 100);
+    for _ in 0..500 {
+        g.tick_fluids();
+        let total: u32 = pipes.iter().map(|&p| amount(&g, p)).sum();
+        assert_eq!(total, 100);
+    }
+    // integer flow leaves a small dead band between neighbours
+    let levels: Vec<u32> = pipes.iter().map(|&p| amount(&g, p)).collect();
+    assert!(
+        levels.windows(2).all(|w| w[0].abs_diff(w[1]) <= 5),
+        "levels did not settle: {:?}",
+        levels
+    );
+    assert!(pipes
+        .iter()
+        .all(|&p| g.fluid_box(p).unwrap().fluid() == Some(item("water"))));
+}
+
+#[test]
+fn flow_does_not_depend_on_placement_order() {
+    let run = |xs: &[i32]| {
+        let mut g = TileGrid::new(10, 10);
+        for &x in xs {
+            place(&mut g, spec("pipe"), x, 0);
+        }
+        let first = g.tile_occupant(at(0, 0)).unwrap();
+        fill(&mut g, first, item("water"), 90);
+        for _ in 0..7 {
+            g.tick_fluids();
+        }
+        (0..4)
+            .map(|x| amount(&g, g.tile_occupant(at(x, 0)).unwrap()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(&[0, 1, 2, 3]), run(&[3, 1, 0, 2]));
+}
+
+#[test]
+fn joining_different_fluids_is_rejected() {
+    let mut g = TileGrid::new(10, 10);
+    let w = place(&mut g, spec("pipe"), 0, 0);
+    let o = place(&mut g, spec("pipe"), 2, 0);
+    fill(&mut g, w, item("water"), 50);
+    fill(&mut g, o, item("crude-oil"), 50);
+
+    assert!(!g.can_place(spec("pipe"), at(1, 0), Rotation::R0));
+    assert!(matches!(
+        g.place(spec("pipe"), at(1, 0), Rotation::R0),
+        Err(PlacementError::MixedFluids(a, b)) if (a, b) == (item("water"), item("crude-oil"))
+    ));
+    // a pump commits its network to water even before pumping
+    assert!(matches!(
+        g.place(spec("pump"), at(3, 0), Rotation::R0),
+        Err(PlacementError::MixedFluids(a, b)) if (a, b) == (item("water"), item("crude-oil"))
+    ));
+    // an empty network accepts either
+    place(&mut g, spec("pipe"), 5, 5);
+    place(&mut g, spec("pipe"), 6, 5);
+}
+
+#[test]
+fn chemical_plant_uses_pumped_water() {
+    let mut g = TileGrid::new(12, 12);
+    water(&mut g, at(0, 1));
+    place(&mut g, spec("pump"), 1, 1);
+    place(&mut g, spec("pipe"), 2, 1);
+    let plant = place(&mut g, spec("chemical-plant"), 3, 0);
+    power(&mut g, at(6, 0));
+
+    g.set_recipe(plant, Some(recipe("concrete"))).unwrap();
+    assert_eq!(g.fluid_box(plant).unwrap().filter, Some(item("water")));
+    for _ in 0..5 {
+        assert_eq!(g.offer_item(plant, item("stone")), Some(true));
+    }
+    for _ in 0..100 {
+        g.tick_power();
+        g.tick_fluids();
+        g.tick_machines();
+    }
+    assert_eq!(g.machine(plant).unwrap().output.count(item("concrete")), 10);
+}
+
+#[test]
+fn recipe_change_cannot_mix_fluids() {
+    let mut g = TileGrid::new(12, 12);
+    let plant = place(&mut g, spec("chemical-plant"), 0, 0);
+    let pipe = place(&mut g, spec("pipe"), 3, 0);
+    g.set_recipe(plant, Some(recipe("concrete"))).unwrap();
+    fill(&mut g, pipe, item("water"), 50);
+
+    assert_eq!(
+        g.set_recipe(plant, Some(recipe("plastic-bar"))),
+        Err(MachineError::MixedFluids(recipe("plastic-bar")))
+    );
+    assert_eq!(g.machine(plant).unwrap().recipe(), Some(recipe("concrete")));
+    assert_eq!(g.fluid_box(plant).unwrap().filter, Some(item("water")));
+
+    // once the pipe is drained the network takes oil
+    g.fluid_box_mut(pipe).unwrap().extract(50);
+    g.set_recipe(plant, Some(recipe("plastic-bar"))).unwrap();
+    assert_eq!(g.fluid_box(plant).unwrap().filter, Some(item("crude-oil")));
+}
+
+#[test]
+fn switching_between_oil_and_water_recipes_drops_the_old_fluid() {
+    let mut g = TileGrid::new(12, 12);
+    let plant = place(&mut g, spec("chemical-plant"), 0, 0);
+    let pipe = place(&mut g, spec("pipe"), 3, 0);
+    g.set_recipe(plant, Some(recipe("plastic-bar"))).unwrap();
+    fill(&mut g, plant, item("crude-oil"), 50);
+
+    g.set_recipe(plant, Some(recipe("concrete"))).unwrap();
+    let b = g.fluid_box(plant).unwrap();
+    assert_eq!(
+        (b.filter, b.fluid(), b.amount()),
+        (Some(item("water")), None, 0)
+    );
+    assert!(b.accepts(item("water")));
+    for _ in 0..10 {
+        g.tick_fluids();
+    }
+    assert_eq!(g.fluid_box(pipe).unwrap().fluid(), None);
+
+    // and back: water in the box goes too
+    fill(&mut g, plant, item("water"), 30);
+    g.set_recipe(plant, Some(recipe("plastic-bar"))).unwrap();
+    let b = g.fluid_box(plant).unwrap();
+    assert_eq!(
+        (b.filter, b.fluid(), b.amount()),
+        (Some(item("crude-oil")), None, 0)
+    );
+    fill(&mut g, plant, item("crude-oil"), 20);
+    assert_eq!(amount(&g, plant), 20);
+}
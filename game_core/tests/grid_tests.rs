@@ -1,15 +1,26 @@
 use game_core::*;
 
+fn spec(spec_id: SpecId, w: u32, h: u32) -> BuildingSpec {
+    BuildingSpec {
+        spec_id,
+        name: format!("test{}", spec_id),
+        size: Size2 { w, h },
+        color: (1.0, 1.0, 1.0, 1.0),
+        sprite: None,
+        category: BuildingCategory::Other,
+        kind: BuildingKind::Structure,
+        inventory_slots: 0,
+        power: PowerRole::None,
+        fluid_capacity: 0,
+    }
+}
+
 #[test]
 fn single_tile_place_and_remove() {
     let mut g = TileGrid::new(10, 10);
-    let spec = BuildingSpec {
-        spec_id: 1,
-        size: Size2 { w: 1, h: 1 },
-    };
     let origin = TilePos { x: 2, y: 3 };
-    assert!(g.can_place(&spec, origin, Rotation::R0));
-    let id = g.place(&spec, origin, Rotation::R0).expect("place failed");
+    assert!(g.can_place(1, origin, Rotation::R0));
+    let id = g.place(1, origin, Rotation::R0).expect("place failed");
     assert_eq!(g.tile_occupant(origin), Some(id));
     let removed = g.remove(id).expect("remove failed");
     assert_eq!(removed.id, id);
@@ -19,35 +30,156 @@ fn single_tile_place_and_remove() {
 #[test]
 fn multi_tile_place_and_overlap() {
     let mut g = TileGrid::new(10, 10);
-    let spec = BuildingSpec {
-        spec_id: 3,
-        size: Size2 { w: 3, h: 3 },
-    };
     let origin = TilePos { x: 5, y: 5 };
-    assert!(g.can_place(&spec, origin, Rotation::R0));
-    let id = g.place(&spec, origin, Rotation::R0).expect("place failed");
+    assert!(g.can_place(3, origin, Rotation::R0));
+    let id = g.place(3, origin, Rotation::R0).expect("place failed");
     // overlapping placement should fail
     let origin2 = TilePos { x: 6, y: 6 };
-    assert!(!g.can_place(&spec, origin2, Rotation::R0));
-    match g.place(&spec, origin2, Rotation::R0) {
+    assert!(!g.can_place(3, origin2, Rotation::R0));
+    match g.place(3, origin2, Rotation::R0) {
         Err(PlacementError::Occupied) => {}
         other => panic!("expected Occupied, got {:?}", other),
     }
     // remove and then placing should succeed
     g.remove(id);
-    assert!(g.can_place(&spec, origin2, Rotation::R0));
+    assert!(g.can_place(3, origin2, Rotation::R0));
 }
 
 #[test]
 fn rotation_bounds() {
-    let g = TileGrid::new(5, 5);
-    let spec = BuildingSpec {
-        spec_id: 3,
-        size: Size2 { w: 3, h: 2 },
-    };
+    let mut specs = SpecRegistry::new();
+    specs.insert(spec(3, 3, 2));
+    let g = TileGrid::with_specs(5, 5, specs);
     // placing at (3,0) with R0 should be out of bounds (width 5, 3+3>5)
     let oob = TilePos { x: 3, y: 0 };
-    assert!(!g.can_place(&spec, oob, Rotation::R0));
+    assert!(!g.can_place(3, oob, Rotation::R0));
     // rotated 90 swaps sizes; placing at (3,0) should fit (2x3 footprint)
-    assert!(g.can_place(&spec, oob, Rotation::R90));
+    assert!(g.can_place(3, oob, Rotation::R90));
+}
+
+#[test]
+fn remove_clears_custom_footprint() {
+    let mut specs = SpecRegistry::new();
+    specs.insert(spec(7, 4, 2));
+    let mut g = TileGrid::with_specs(10, 10, specs);
+    let id = g
+        .place(7, TilePos { x: 1, y: 1 }, Rotation::R90)
+        .expect("place failed");
+    // R90 footprint is 2 wide, 4 tall
+    assert_eq!(g.tile_occupant(TilePos { x: 2, y: 4 }), Some(id));
+    g.remove(id);
+    for y in 0..10 {
+        for x in 0..10 {
+            assert_eq!(g.tile_occupant(TilePos { x, y }), None);
+        }
+    }
+}
+
+#[test]
+fn unknown_spec_is_rejected() {
+    let mut g = TileGrid::new(10, 10);
+    let origin = TilePos { x: 0, y: 0 };
+    assert!(!g.can_place(99, origin, Rotation::R0));
+    match g.place(99, origin, Rotation::R0) {
+        Err(PlacementError::UnknownSpec(99)) => {}
+        other => panic!("expected UnknownSpec, got {:?}", other),
+    }
+}
+
+#[test]
+fn unbounded_grid_accepts_negative_and_far_positions() {
+    let mut g = TileGrid::unbounded();
+    assert_eq!(g.bounds(), None);
+    let far = TilePos { x: -5000, y: 70000 };
+    let id = g.place(3, far, Rotation::R0).expect("place failed");
+    assert_eq!(g.tile_occupant(TilePos { x: -4998, y: 70002 }), Some(id));
+    assert_eq!(g.tile_occupant(TilePos { x: -4997, y: 70002 }), None);
+    assert!(!g.can_place(1, TilePos { x: -4999, y: 70001 }, Rotation::R0));
+    assert_eq!(
+        g.occupied_area(),
+        Some(TileRect::spanning(far, TilePos { x: -4998, y: 70002 }))
+    );
+}
+
+#[test]
+fn buildings_straddle_chunk_corners() {
+    let mut specs = SpecRegistry::new();
+    specs.insert(spec(7, 4, 2));
+    let mut g = TileGrid::with_bounds(None, specs);
+    // covers x -2..2, y -1..1: four chunks around the origin
+    let origin = TilePos { x: -2, y: -1 };
+    let id = g.place(7, origin, Rotation::R0).expect("place failed");
+    assert_eq!(g.chunk_count(), 4);
+    for (x, y) in [(-2, -1), (1, -1), (-2, 0), (1, 0)] {
+        assert_eq!(g.tile_occupant(TilePos { x, y }), Some(id));
+    }
+    match g.place(7, TilePos { x: 1, y: 0 }, Rotation::R0) {
+        Err(PlacementError::Occupied) => {}
+        other => panic!("expected Occupied, got {:?}", other),
+    }
+
+    // chunks are freed once nothing covers them
+    g.remove(id);
+    assert_eq!(g.chunk_count(), 0);
+    assert!(g.can_place(7, origin, Rotation::R0));
+}
+
+#[test]
+fn chunks_are_allocated_on_demand() {
+    let mut g = TileGrid::unbounded();
+    assert_eq!(g.chunk_count(), 0);
+    let a = g.place(1, TilePos { x: 0, y: 0 }, Rotation::R0).unwrap();
+    g.place(1, TilePos { x: 31, y: 31 }, Rotation::R0).unwrap();
+    assert_eq!(g.chunk_count(), 1);
+    g.place(
+        1,
+        TilePos {
+            x: CHUNK_SIZE * 10,
+            y: -1,
+        },
+        Rotation::R0,
+    )
+    .unwrap();
+    assert_eq!(g.chunk_count(), 2);
+    g.remove(a);
+    assert_eq!(g.chunk_count(), 2);
+}
+
+#[test]
+fn instances_in_rect_come_from_overlapping_chunks() {
+    let mut g = TileGrid::unbounded();
+    let near = g.place(1, TilePos { x: 2, y: 2 }, Rotation::R0).unwrap();
+    let edge = g.place(2, TilePos { x: 30, y: 30 }, Rotation::R0).unwrap();
+    let far = g
+        .place(1, TilePos { x: 5000, y: -5000 }, Rotation::R0)
+        .unwrap();
+
+    let view = TileRect::spanning(TilePos { x: 0, y: 0 }, TilePos { x: 30, y: 30 });
+    assert_eq!(g.instances_in(view), vec![near, edge]);
+    // a view spanning many empty chunks still finds the far building
+    let wide = TileRect::spanning(TilePos { x: -6000, y: -6000 }, TilePos { x: 6000, y: 6000 });
+    assert_eq!(g.instances_in(wide), vec![near, edge, far]);
+    let empty = TileRect::spanning(TilePos { x: 100, y: 100 }, TilePos { x: 200, y: 200 });
+    assert!(g.instances_in(empty).is_empty());
+}
+
+#[test]
+fn uncollected_building_changes_are_capped() {
+    let mut g = TileGrid::new(10, 10);
+    let origin = TilePos { x: 2, y: 3 };
+    let first = g.place(1, origin, Rotation::R0).unwrap();
+    g.remove(first);
+    for _ in 0..MAX_BUILDING_CHANGES {
+        let id = g.place(1, origin, Rotation::R0).unwrap();
+        g.remove(id);
+    }
+    let changes = g.take_building_changes();
+    assert_eq!(changes.len(), MAX_BUILDING_CHANGES);
+    assert!(!changes.iter().any(|c| matches!(c,
+        BuildingChange::Placed { id, .. } | BuildingChange::Removed { id, .. } if *id == first)));
+    assert!(matches!(
+        changes.last(),
+        Some(BuildingChange::Removed { spec_id: 1, origin: o, .. }) if *o == origin
+    ));
+    assert!(g.take_building_changes().is_empty());
 }
@@ -0,0 +1,135 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+fn tick(g: &mut TileGrid, n: u32) {
+    for _ in 0..n {
+        g.tick_power();
+        g.tick_inserters();
+    }
+}
+
+/// chest (0,0) -> inserter (1,0) facing east -> chest (2,0)
+fn chest_pair() -> (TileGrid, InstanceId, InstanceId, InstanceId) {
+    let mut g = TileGrid::new(10, 10);
+    let src = g.place(spec("chest"), at(0, 0), Rotation::R0).unwrap();
+    let ins = g.place(spec("inserter"), at(1, 0), Rotation::R0).unwrap();
+    let dst = g.place(spec("chest"), at(2, 0), Rotation::R0).unwrap();
+    power(&mut g, at(1, 2));
+    (g, src, ins, dst)
+}
+
+fn stock(g: &mut TileGrid, id: InstanceId, item: ItemId, count: u32) {
+    let items = g.items().clone();
+    g.inventory_mut(id).unwrap().insert(&items, item, count);
+}
+
+#[test]
+fn exact_throughput_between_chests() {
+    let (mut g, src, _, dst) = chest_pair();
+    stock(&mut g, src, item("iron-ore"), 10);
+    // pickup on tick 1, drops on ticks 21, 61, 101, ...
+    tick(&mut g, 20);
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-ore")), 0);
+    tick(&mut g, 1);
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-ore")), 1);
+    tick(&mut g, 79);
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-ore")), 2);
+    tick(&mut g, 1);
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-ore")), 3);
+    assert_eq!(g.inventory(src).unwrap().count(item("iron-ore")), 7);
+}
+
+#[test]
+fn filter_limits_pickup() {
+    let (mut g, src, ins, dst) = chest_pair();
+    stock(&mut g, src, item("iron-ore"), 5);
+    stock(&mut g, src, item("copper-ore"), 5);
+    g.inserter_mut(ins).unwrap().filter = Some(item("copper-ore"));
+    tick(&mut g, 400);
+    let out = g.inventory(dst).unwrap();
+    assert_eq!(out.count(item("copper-ore")), 5);
+    assert_eq!(out.count(item("iron-ore")), 0);
+}
+
+#[test]
+fn no_pickup_while_target_is_full() {
+    let (mut g, src, ins, dst) = chest_pair();
+    stock(&mut g, src, item("iron-ore"), 1);
+    stock(&mut g, dst, item("coal"), 16 * 50);
+    tick(&mut g, 100);
+    assert_eq!(
+        g.inserter(ins).unwrap().phase(),
+        InserterPhase::WaitingForPickup
+    );
+    assert_eq!(g.inventory(src).unwrap().count(item("iron-ore")), 1);
+}
+
+#[test]
+fn hand_waits_at_drop_until_room() {
+    let (mut g, src, ins, dst) = chest_pair();
+    stock(&mut g, src, item("iron-ore"), 1);
+    tick(&mut g, 1);
+    // target fills up while the arm is mid-swing
+    stock(&mut g, dst, item("coal"), 16 * 50);
+    tick(&mut g, 30);
+    let arm = g.inserter(ins).unwrap();
+    assert_eq!(arm.phase(), InserterPhase::WaitingForDrop);
+    assert_eq!(
+        arm.hand(),
+        Some(ItemStack {
+            item: item("iron-ore"),
+            count: 1
+        })
+    );
+    g.inventory_mut(dst).unwrap().extract(item("coal"), 50);
+    tick(&mut g, 1);
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-ore")), 1);
+    assert!(g.inserter(ins).unwrap().hand().is_none());
+}
+
+#[test]
+fn skips_items_the_target_cannot_use() {
+    let mut g = TileGrid::new(10, 10);
+    let src = g.place(spec("chest"), at(0, 0), Rotation::R0).unwrap();
+    g.place(spec("inserter"), at(1, 0), Rotation::R0).unwrap();
+    let furnace = g.place(spec("furnace"), at(2, 0), Rotation::R0).unwrap();
+    power(&mut g, at(1, 2));
+    stock(&mut g, src, item("coal"), 3);
+    stock(&mut g, src, item("iron-ore"), 1);
+    tick(&mut g, 21);
+    assert_eq!(g.machine(furnace).unwrap().input.count(item("iron-ore")), 1);
+    assert_eq!(g.inventory(src).unwrap().count(item("coal")), 3);
+}
+
+#[test]
+fn unloads_multi_tile_machine_output() {
+    let mut g = TileGrid::new(10, 10);
+    // furnace covers (0,0)-(1,1); the inserter at (2,1) reaches its lower-right tile
+    let furnace = g.place(spec("furnace"), at(0, 0), Rotation::R0).unwrap();
+    g.place(spec("inserter"), at(2, 1), Rotation::R0).unwrap();
+    let dst = g.place(spec("chest"), at(3, 1), Rotation::R0).unwrap();
+    power(&mut g, at(1, 2));
+    let items = g.items().clone();
+    g.machine_mut(furnace)
+        .unwrap()
+        .output
+        .insert(&items, item("iron-plate"), 2);
+    tick(&mut g, 61);
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-plate")), 2);
+    assert!(g.machine(furnace).unwrap().output.is_empty());
+}
+
+#[test]
+fn moves_items_from_belt_to_chest() {
+    let mut g = TileGrid::new(10, 10);
+    let belt = g.place(spec("conveyor"), at(0, 1), Rotation::R270).unwrap();
+    g.place(spec("inserter"), at(1, 1), Rotation::R0).unwrap();
+    let dst = g.place(spec("chest"), at(2, 1), Rotation::R0).unwrap();
+    power(&mut g, at(1, 2));
+    g.belt_insert(belt, item("iron-ore"));
+    tick(&mut g, 21);
+    assert!(g.belt(belt).unwrap().is_empty());
+    assert_eq!(g.inventory(dst).unwrap().count(item("iron-ore")), 1);
+}
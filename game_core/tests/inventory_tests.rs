@@ -0,0 +1,139 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+#[test]
+fn builtin_items_load() {
+    let items = ItemRegistry::builtin();
+    let ore = items.by_name("iron-ore").expect("iron-ore missing");
+    assert_eq!(ore.item_id, item("iron-ore"));
+    assert_eq!(items.stack_size(item("iron-ore")), 50);
+    assert_eq!(items.stack_size(999), 0);
+}
+
+#[test]
+fn zero_stack_size_is_rejected() {
+    let text = r#"[(item_id: 1, name: "x", stack_size: 0)]"#;
+    assert!(matches!(
+        ItemRegistry::from_ron_str(text),
+        Err(ItemError::ZeroStackSize(1))
+    ));
+}
+
+#[test]
+fn insert_fills_stacks_then_slots() {
+    let items = ItemRegistry::builtin();
+    let mut inv = Inventory::new(3);
+    assert_eq!(inv.insert(&items, item("iron-ore"), 30), 0);
+    assert_eq!(inv.insert(&items, item("iron-ore"), 30), 0);
+    assert_eq!(
+        inv.slot(0),
+        Some(ItemStack {
+            item: item("iron-ore"),
+            count: 50
+        })
+    );
+    assert_eq!(
+        inv.slot(1),
+        Some(ItemStack {
+            item: item("iron-ore"),
+            count: 10
+        })
+    );
+    assert_eq!(inv.count(item("iron-ore")), 60);
+}
+
+#[test]
+fn insert_reports_leftover_when_full() {
+    let items = ItemRegistry::builtin();
+    let mut inv = Inventory::new(2);
+    assert_eq!(inv.space_for(&items, item("iron-ore")), 100);
+    assert_eq!(inv.insert(&items, item("iron-ore"), 120), 20);
+    assert_eq!(inv.space_for(&items, item("iron-ore")), 0);
+    assert_eq!(inv.insert(&items, item("copper-ore"), 1), 1);
+}
+
+#[test]
+fn unknown_items_never_fit() {
+    let items = ItemRegistry::builtin();
+    let mut inv = Inventory::new(2);
+    assert_eq!(inv.insert(&items, 999, 5), 5);
+    assert!(inv.is_empty());
+}
+
+#[test]
+fn filters_restrict_slots() {
+    let items = ItemRegistry::builtin();
+    let mut inv = Inventory::new(2);
+    inv.set_filter(0, Some(item("copper-ore")));
+    assert_eq!(inv.space_for(&items, item("iron-ore")), 50);
+    assert_eq!(inv.insert(&items, item("iron-ore"), 60), 10);
+    assert_eq!(inv.slot(0), None);
+    assert_eq!(inv.insert(&items, item("copper-ore"), 5), 0);
+    assert_eq!(
+        inv.slot(0),
+        Some(ItemStack {
+            item: item("copper-ore"),
+            count: 5
+        })
+    );
+}
+
+#[test]
+fn extract_removes_and_clears_slots() {
+    let items = ItemRegistry::builtin();
+    let mut inv = Inventory::new(3);
+    inv.insert(&items, item("iron-ore"), 70);
+    inv.insert(&items, item("copper-ore"), 5);
+    assert_eq!(inv.extract(item("iron-ore"), 25), 25);
+    assert_eq!(inv.count(item("iron-ore")), 45);
+    assert_eq!(inv.extract(item("copper-ore"), 10), 5);
+    assert_eq!(inv.count(item("copper-ore")), 0);
+    assert_eq!(
+        inv.extract_first(None, 100),
+        Some(ItemStack {
+            item: item("iron-ore"),
+            count: 45
+        })
+    );
+    assert!(inv.is_empty());
+    assert_eq!(inv.extract_first(None, 1), None);
+}
+
+#[test]
+fn chest_instances_get_inventories() {
+    let mut g = TileGrid::new(10, 10);
+    let chest = g.place(spec("chest"), at(0, 0), Rotation::R0).unwrap();
+    assert_eq!(g.inventory(chest).unwrap().slot_count(), 16);
+    assert_eq!(g.offer_item(chest, item("iron-ore")), Some(true));
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 1);
+    g.remove(chest);
+    assert!(g.inventory(chest).is_none());
+}
+
+#[test]
+fn belt_delivers_into_chest() {
+    let mut g = TileGrid::new(10, 10);
+    let belt = g.place(1, at(0, 0), Rotation::R0).unwrap();
+    let chest = g.place(spec("chest"), at(1, 0), Rotation::R0).unwrap();
+    g.belt_insert(belt, item("iron-ore"));
+    for _ in 0..40 {
+        g.tick_belts(|_, _| panic!("chest should take the item itself"));
+    }
+    assert_eq!(g.inventory(chest).unwrap().count(item("iron-ore")), 1);
+    assert!(g.belt(belt).unwrap().is_empty());
+}
+
+#[test]
+fn player_spawns_with_inventory() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.spawn_enemy(10.0, 0.0);
+    let player = world.find_player().unwrap();
+    assert_eq!(
+        player.inventory.as_ref().map(|i| i.slot_count()),
+        Some(PLAYER_INVENTORY_SLOTS)
+    );
+    assert!(world.entities()[1].inventory.is_none());
+}
@@ -0,0 +1,199 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+fn tick(g: &mut TileGrid, n: u32) {
+    for _ in 0..n {
+        g.tick_power();
+        g.tick_machines();
+    }
+}
+
+/// Assembler at the origin, powered by a pole and generator to its east.
+fn powered_assembler(g: &mut TileGrid) -> InstanceId {
+    let a = g.place(spec("assembler"), at(0, 0), Rotation::R0).unwrap();
+    power(g, at(3, 0));
+    a
+}
+
+#[test]
+fn builtin_recipes_load() {
+    let recipes = RecipeRegistry::builtin();
+    let gear = recipes.by_name("iron-gear").unwrap();
+    assert_eq!(gear.recipe_id, recipe("iron-gear"));
+    assert_eq!(
+        gear.inputs,
+        vec![ItemStack {
+            item: item("iron-plate"),
+            count: 2
+        }]
+    );
+    assert_eq!(recipes.in_category(CraftCategory::Smelting).count(), 2);
+}
+
+#[test]
+fn furnace_auto_selects_smelting_recipe() {
+    let mut g = TileGrid::new(10, 10);
+    let f = g.place(spec("furnace"), at(0, 0), Rotation::R0).unwrap();
+    assert_eq!(
+        g.machine(f).unwrap().status(),
+        MachineStatus::WaitingForInput
+    );
+    power(&mut g, at(3, 0));
+
+    assert_eq!(g.offer_item(f, item("copper-ore")), Some(true));
+    tick(&mut g, 1);
+    let m = g.machine(f).unwrap();
+    assert_eq!(m.status(), MachineStatus::Crafting);
+    assert_eq!(m.recipe(), Some(recipe("copper-plate")));
+
+    tick(&mut g, 192);
+    let m = g.machine(f).unwrap();
+    assert_eq!(m.output.count(item("copper-plate")), 1);
+    assert_eq!(m.status(), MachineStatus::WaitingForInput);
+}
+
+#[test]
+fn furnace_rejects_non_ingredients() {
+    let mut g = TileGrid::new(10, 10);
+    let f = g.place(spec("furnace"), at(0, 0), Rotation::R0).unwrap();
+    assert_eq!(g.offer_item(f, item("coal")), Some(false));
+    assert!(g.machine(f).unwrap().input.is_empty());
+}
+
+#[test]
+fn progress_is_reported() {
+    let mut g = TileGrid::new(10, 10);
+    let f = g.place(spec("furnace"), at(0, 0), Rotation::R0).unwrap();
+    power(&mut g, at(3, 0));
+    g.offer_item(f, item("iron-ore"));
+    tick(&mut g, 1 + 96);
+    let m = g.machine(f).unwrap();
+    assert_eq!(m.recipe(), Some(recipe("iron-plate")));
+    assert!((m.progress() - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn assembler_needs_player_recipe() {
+    let mut g = TileGrid::new(10, 10);
+    let a = powered_assembler(&mut g);
+    assert_eq!(g.machine(a).unwrap().status(), MachineStatus::Idle);
+    // nothing wanted without a recipe
+    assert_eq!(g.offer_item(a, item("iron-plate")), Some(false));
+
+    assert_eq!(
+        g.set_recipe(a, Some(recipe("iron-plate"))),
+        Err(MachineError::WrongCategory(recipe("iron-plate")))
+    );
+    assert_eq!(
+        g.set_recipe(a, Some(99)),
+        Err(MachineError::UnknownRecipe(99))
+    );
+    g.set_recipe(a, Some(recipe("iron-gear"))).unwrap();
+
+    g.offer_item(a, item("iron-plate"));
+    tick(&mut g, 5);
+    assert_eq!(
+        g.machine(a).unwrap().status(),
+        MachineStatus::WaitingForInput
+    );
+    g.offer_item(a, item("iron-plate"));
+    tick(&mut g, 1 + 30);
+    assert_eq!(g.machine(a).unwrap().output.count(item("iron-gear")), 1);
+}
+
+#[test]
+fn full_output_blocks_machine() {
+    let mut g = TileGrid::new(10, 10);
+    let a = powered_assembler(&mut g);
+    g.set_recipe(a, Some(recipe("iron-gear"))).unwrap();
+    let items = g.items().clone();
+    {
+        let m = g.machine_mut(a).unwrap();
+        // fill every output slot with something else
+        for _ in 0..MACHINE_BUFFER_SLOTS {
+            m.output.insert(&items, item("coal"), 50);
+        }
+        m.input.insert(&items, item("iron-plate"), 2);
+    }
+    tick(&mut g, 1 + 30);
+    let m = g.machine(a).unwrap();
+    assert_eq!(m.status(), MachineStatus::OutputBlocked);
+    assert_eq!(m.progress(), 1.0);
+
+    g.machine_mut(a).unwrap().output.extract(item("coal"), 50);
+    tick(&mut g, 1);
+    let m = g.machine(a).unwrap();
+    assert_eq!(m.output.count(item("iron-gear")), 1);
+    assert_ne!(m.status(), MachineStatus::OutputBlocked);
+}
+
+#[test]
+fn blocked_machine_keeps_its_recipe() {
+    let mut g = TileGrid::new(10, 10);
+    let a = powered_assembler(&mut g);
+    g.set_recipe(a, Some(recipe("iron-gear"))).unwrap();
+    let items = g.items().clone();
+    {
+        let m = g.machine_mut(a).unwrap();
+        for _ in 0..MACHINE_BUFFER_SLOTS {
+            m.output.insert(&items, item("coal"), 50);
+        }
+        m.input.insert(&items, item("iron-plate"), 2);
+    }
+    tick(&mut g, 1 + 30);
+    assert_eq!(g.machine(a).unwrap().status(), MachineStatus::OutputBlocked);
+
+    assert_eq!(g.set_recipe(a, None), Err(MachineError::OutputBlocked));
+    // choosing the same recipe again changes nothing
+    assert_eq!(g.set_recipe(a, Some(recipe("iron-gear"))), Ok(()));
+    assert_eq!(g.machine(a).unwrap().status(), MachineStatus::OutputBlocked);
+
+    // once delivered, the recipe may change
+    g.machine_mut(a).unwrap().output.extract(item("coal"), 50);
+    tick(&mut g, 1);
+    assert_eq!(g.machine(a).unwrap().output.count(item("iron-gear")), 1);
+    assert_eq!(g.set_recipe(a, None), Ok(()));
+}
+
+#[test]
+fn changing_recipe_refunds_ingredients() {
+    let mut g = TileGrid::new(10, 10);
+    let a = powered_assembler(&mut g);
+    g.set_recipe(a, Some(recipe("iron-gear"))).unwrap();
+    g.offer_item(a, item("iron-plate"));
+    g.offer_item(a, item("iron-plate"));
+    tick(&mut g, 2);
+    assert_eq!(g.machine(a).unwrap().status(), MachineStatus::Crafting);
+    g.set_recipe(a, None).unwrap();
+    let m = g.machine(a).unwrap();
+    assert_eq!(m.status(), MachineStatus::Idle);
+    assert_eq!(m.input.count(item("iron-plate")), 2);
+}
+
+#[test]
+fn set_recipe_on_non_machine_fails() {
+    let mut g = TileGrid::new(10, 10);
+    let c = g.place(spec("chest"), at(0, 0), Rotation::R0).unwrap();
+    assert_eq!(
+        g.set_recipe(c, Some(recipe("iron-gear"))),
+        Err(MachineError::NotAMachine)
+    );
+}
+
+#[test]
+fn belt_feeds_furnace() {
+    let mut g = TileGrid::new(10, 10);
+    let belt = g.place(spec("conveyor"), at(0, 0), Rotation::R0).unwrap();
+    let f = g.place(spec("furnace"), at(1, 0), Rotation::R0).unwrap();
+    power(&mut g, at(3, 0));
+    g.belt_insert(belt, item("iron-ore"));
+    for _ in 0..40 {
+        g.tick_power();
+        g.tick_belts(|_, _| false);
+        g.tick_machines();
+    }
+    assert!(g.belt(belt).unwrap().is_empty());
+    assert_eq!(g.machine(f).unwrap().status(), MachineStatus::Crafting);
+}
@@ -0,0 +1,127 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+fn place(g: &mut TileGrid, spec: SpecId, x: i32, y: i32) -> InstanceId {
+    g.place(spec, at(x, y), Rotation::R0).unwrap()
+}
+
+fn gear_assembler(g: &mut TileGrid, x: i32, y: i32) -> InstanceId {
+    let a = place(g, spec("assembler"), x, y);
+    g.set_recipe(a, Some(recipe("iron-gear"))).unwrap();
+    let items = g.items().clone();
+    g.machine_mut(a)
+        .unwrap()
+        .input
+        .insert(&items, item("iron-plate"), 20);
+    a
+}
+
+fn tick(g: &mut TileGrid, n: u32) {
+    for _ in 0..n {
+        g.tick_power();
+        g.tick_machines();
+    }
+}
+
+#[test]
+fn unconnected_consumer_does_not_run() {
+    let mut g = TileGrid::new(20, 20);
+    let a = gear_assembler(&mut g, 0, 0);
+    // generator with no pole in between
+    place(&mut g, spec("generator"), 3, 0);
+    tick(&mut g, 100);
+    assert_eq!(g.power_satisfaction(a), Some(0));
+    assert_eq!(g.machine(a).unwrap().output.count(item("iron-gear")), 0);
+}
+
+#[test]
+fn non_consumers_report_no_satisfaction() {
+    let mut g = TileGrid::new(20, 20);
+    let p = place(&mut g, spec("pole"), 0, 0);
+    assert_eq!(g.power_satisfaction(p), None);
+}
+
+#[test]
+fn pole_connects_generator_and_consumer() {
+    let mut g = TileGrid::new(20, 20);
+    let a = gear_assembler(&mut g, 0, 0);
+    let gen = place(&mut g, spec("generator"), 4, 0);
+    let p = place(&mut g, spec("pole"), 3, 0);
+    let net = g.power().network_of(p).unwrap();
+    assert_eq!(g.power().network_of(a), Some(net));
+    assert_eq!(g.power().network_of(gen), Some(net));
+
+    tick(&mut g, 1 + 30);
+    assert_eq!(g.power_satisfaction(a), Some(FULL_SATISFACTION));
+    assert_eq!(g.machine(a).unwrap().output.count(item("iron-gear")), 1);
+}
+
+#[test]
+fn shortage_slows_consumers_proportionally() {
+    let mut g = TileGrid::new(20, 20);
+    // one 300 kW generator for four 150 kW assemblers
+    let machines = [
+        gear_assembler(&mut g, 0, 0),
+        gear_assembler(&mut g, 0, 3),
+        gear_assembler(&mut g, 4, 0),
+        gear_assembler(&mut g, 4, 3),
+    ];
+    place(&mut g, spec("pole"), 3, 3);
+    place(&mut g, spec("generator"), 7, 6);
+    place(&mut g, spec("pole"), 7, 5);
+
+    tick(&mut g, 2 * (1 + 30));
+    let net = g.power().network_of(machines[0]).unwrap();
+    let network = g.power().network(net).unwrap();
+    assert_eq!((network.production, network.demand), (300, 600));
+    for a in machines {
+        assert_eq!(g.power_satisfaction(a), Some(500));
+        assert_eq!(g.machine(a).unwrap().output.count(item("iron-gear")), 1);
+    }
+}
+
+#[test]
+fn poles_in_reach_merge_networks() {
+    let mut g = TileGrid::new(30, 30);
+    let left = place(&mut g, spec("pole"), 0, 0);
+    let right = place(&mut g, spec("pole"), 14, 0);
+    assert_ne!(g.power().network_of(left), g.power().network_of(right));
+    assert_eq!(g.power().networks().count(), 2);
+
+    let middle = place(&mut g, spec("pole"), 7, 0);
+    let net = g.power().network_of(middle);
+    assert_eq!(g.power().network_of(left), net);
+    assert_eq!(g.power().network_of(right), net);
+    assert_eq!(g.power().networks().count(), 1);
+}
+
+#[test]
+fn removing_link_pole_splits_network() {
+    let mut g = TileGrid::new(30, 30);
+    let a = gear_assembler(&mut g, 0, 0);
+    place(&mut g, spec("pole"), 3, 0);
+    let middle = place(&mut g, spec("pole"), 10, 0);
+    place(&mut g, spec("pole"), 17, 0);
+    let gen = place(&mut g, spec("generator"), 18, 0);
+    tick(&mut g, 1);
+    assert_eq!(g.power_satisfaction(a), Some(FULL_SATISFACTION));
+
+    g.remove(middle);
+    assert_eq!(g.power().networks().count(), 2);
+    assert_ne!(g.power().network_of(a), g.power().network_of(gen));
+    tick(&mut g, 1);
+    assert_eq!(g.power_satisfaction(a), Some(0));
+}
+
+#[test]
+fn removing_pole_disconnects_covered_buildings() {
+    let mut g = TileGrid::new(20, 20);
+    let a = gear_assembler(&mut g, 0, 0);
+    let p = place(&mut g, spec("pole"), 3, 0);
+    assert!(g.power().network_of(a).is_some());
+    g.remove(p);
+    assert_eq!(g.power().network_of(a), None);
+    assert_eq!(g.power().networks().count(), 0);
+}
@@ -0,0 +1,188 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+/// Cheap technology added by the tests: 2 units of 5 ticks each
+const QUICK: TechId = 100;
+
+fn quick_tech() -> Technology {
+    Technology {
+        tech_id: QUICK,
+        name: "quick".to_string(),
+        prerequisites: Vec::new(),
+        cost: vec![ItemStack {
+            item: item("automation-science"),
+            count: 1,
+        }],
+        units: 2,
+        unit_ticks: 5,
+        unlocks: vec![Unlock::Recipe(recipe("iron-gear"))],
+    }
+}
+
+/// A powered lab researching `QUICK`.
+fn powered_lab() -> (TileGrid, InstanceId) {
+    let mut g = TileGrid::new(20, 20);
+    g.techs_mut().insert(quick_tech());
+    let lab = g.place(spec("lab"), at(0, 0), Rotation::R0).unwrap();
+    power(&mut g, at(3, 0));
+    g.set_research(Some(QUICK)).unwrap();
+    (g, lab)
+}
+
+fn tick(g: &mut TileGrid, n: u32) -> Vec<TechId> {
+    let mut finished = Vec::new();
+    for _ in 0..n {
+        g.tick_power();
+        finished.extend(g.tick_labs().into_iter().filter_map(|e| match e {
+            LabEvent::Researched { tech } => Some(tech),
+            LabEvent::Started { .. } => None,
+        }));
+    }
+    finished
+}
+
+#[test]
+fn builtin_tree_locks_only_what_it_unlocks() {
+    let g = TileGrid::new(10, 10);
+    assert_eq!(g.techs().len(), 4);
+    assert_eq!(
+        g.techs().by_name("oil-processing").map(|t| t.tech_id),
+        Some(tech("oil-processing"))
+    );
+    assert!(!g.is_spec_unlocked(spec("pipe")));
+    assert!(!g.is_spec_unlocked(spec("chemical-plant")));
+    assert!(!g.is_recipe_unlocked(recipe("plastic-bar")));
+    assert!(g.is_spec_unlocked(spec("lab")));
+    assert!(g.is_recipe_unlocked(recipe("iron-gear")));
+    assert!(g.techs().is_science(item("automation-science")));
+    assert!(!g.techs().is_science(item("iron-plate")));
+}
+
+#[test]
+fn finishing_research_unlocks_its_specs() {
+    let mut g = TileGrid::new(10, 10);
+    g.finish_research(tech("fluid-handling")).unwrap();
+    assert!(g.research().is_researched(tech("fluid-handling")));
+    assert!(g.is_spec_unlocked(spec("pipe")));
+    assert!(!g.is_spec_unlocked(spec("chemical-plant")));
+    assert_eq!(g.finish_research(99), Err(ResearchError::UnknownTech(99)));
+}
+
+#[test]
+fn prerequisites_gate_research() {
+    let mut g = TileGrid::new(10, 10);
+    assert_eq!(
+        g.set_research(Some(tech("oil-processing"))),
+        Err(ResearchError::MissingPrerequisite {
+            tech: tech("oil-processing"),
+            prerequisite: tech("fluid-handling"),
+        })
+    );
+    g.finish_research(tech("fluid-handling")).unwrap();
+    g.finish_research(tech("logistic-science")).unwrap();
+    assert_eq!(g.set_research(Some(tech("oil-processing"))), Ok(()));
+    assert_eq!(g.research().current(), Some(tech("oil-processing")));
+    assert_eq!(
+        g.set_research(Some(tech("fluid-handling"))),
+        Err(ResearchError::AlreadyResearched(tech("fluid-handling")))
+    );
+}
+
+#[test]
+fn lab_consumes_science_and_finishes_a_technology() {
+    let (mut g, lab) = powered_lab();
+    assert!(!g.is_recipe_unlocked(recipe("iron-gear")));
+    assert_eq!(g.offer_item(lab, item("automation-science")), Some(true));
+    assert_eq!(g.offer_item(lab, item("automation-science")), Some(true));
+    assert_eq!(g.offer_item(lab, item("iron-plate")), Some(false));
+
+    // the first tick only connects power
+    assert!(tick(&mut g, 6).is_empty());
+    assert_eq!(g.research().units_done(QUICK), 1);
+    assert_eq!(
+        g.lab(lab).unwrap().input.count(item("automation-science")),
+        0
+    );
+    assert!(g.lab(lab).unwrap().is_working());
+
+    assert_eq!(tick(&mut g, 5), vec![QUICK]);
+    assert!(g.research().is_researched(QUICK));
+    assert_eq!(g.research().current(), None);
+    assert!(g.is_recipe_unlocked(recipe("iron-gear")));
+    assert!(!g.lab(lab).unwrap().is_working());
+}
+
+#[test]
+fn lab_waits_without_science_or_power() {
+    let (mut g, lab) = powered_lab();
+    tick(&mut g, 20);
+    assert!(!g.lab(lab).unwrap().is_working());
+
+    let mut g = TileGrid::new(10, 10);
+    g.techs_mut().insert(quick_tech());
+    let lab = g.place(spec("lab"), at(0, 0), Rotation::R0).unwrap();
+    g.set_research(Some(QUICK)).unwrap();
+    g.offer_item(lab, item("automation-science"));
+    tick(&mut g, 20);
+    assert_eq!(g.power_satisfaction(lab), Some(0));
+    assert_eq!(
+        g.lab(lab).unwrap().input.count(item("automation-science")),
+        1
+    );
+    assert_eq!(g.research().units_done(QUICK), 0);
+}
+
+#[test]
+fn research_survives_saving() {
+    // saves use the built-in tree, so this runs a built-in technology
+    let mut g = TileGrid::new(20, 20);
+    let lab = g.place(spec("lab"), at(0, 0), Rotation::R0).unwrap();
+    power(&mut g, at(3, 0));
+    g.set_research(Some(tech("fluid-handling"))).unwrap();
+    g.offer_item(lab, item("automation-science"));
+    g.offer_item(lab, item("automation-science"));
+    tick(&mut g, 1 + 600 + 10);
+    let mut world = World::with_grid(g);
+    let mut loaded = World::load(&world.save_binary().unwrap()).unwrap();
+    assert_eq!(loaded.grid.research().units_done(tech("fluid-handling")), 1);
+    assert!(loaded.grid.lab(lab).unwrap().is_working());
+
+    tick(&mut world.grid, 600);
+    tick(&mut loaded.grid, 600);
+    assert_eq!(loaded.grid.research().units_done(tech("fluid-handling")), 2);
+    assert_eq!(loaded.save_binary().unwrap(), world.save_binary().unwrap());
+}
+
+#[test]
+fn invalid_trees_are_rejected() {
+    let unknown = r#"[(tech_id: 1, name: "a", prerequisites: [7], cost: [], units: 1,
+        unit_ticks: 1, unlocks: [])]"#;
+    assert!(matches!(
+        TechTree::from_ron_str(unknown),
+        Err(TechError::UnknownPrerequisite {
+            tech: 1,
+            prerequisite: 7
+        })
+    ));
+
+    let cycle = r#"[
+        (tech_id: 1, name: "a", prerequisites: [3], cost: [], units: 1, unit_ticks: 1, unlocks: []),
+        (tech_id: 2, name: "b", prerequisites: [1], cost: [], units: 1, unit_ticks: 1, unlocks: []),
+        (tech_id: 3, name: "c", prerequisites: [2], cost: [], units: 1, unit_ticks: 1, unlocks: []),
+    ]"#;
+    assert!(matches!(
+        TechTree::from_ron_str(cycle),
+        Err(TechError::Cycle(_))
+    ));
+
+    let duplicate = r#"[
+        (tech_id: 1, name: "a", cost: [], units: 1, unit_ticks: 1, unlocks: []),
+        (tech_id: 1, name: "b", cost: [], units: 1, unit_ticks: 1, unlocks: []),
+    ]"#;
+    assert!(matches!(
+        TechTree::from_ron_str(duplicate),
+        Err(TechError::DuplicateId(1))
+    ));
+}
@@ -0,0 +1,193 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+/// A small world with a bit of every subsystem in motion.
+fn busy_world() -> World {
+    let mut world = World::with_grid(TileGrid::new(16, 16));
+    world.spawn_player(40.0, 50.0);
+    world.spawn_enemy(300.0, 20.0);
+    world.find_player_mut().unwrap().velocity.vx = 12.5;
+
+    let g = &mut world.grid;
+    water(g, at(0, 8));
+    let belt = g.place(spec("conveyor"), at(0, 0), Rotation::R0).unwrap();
+    g.place(spec("conveyor"), at(1, 0), Rotation::R0).unwrap();
+    let furnace = g.place(spec("furnace"), at(2, 0), Rotation::R0).unwrap();
+    let chest = g.place(spec("chest"), at(5, 5), Rotation::R90).unwrap();
+    g.place(spec("pump"), at(1, 8), Rotation::R0).unwrap();
+    g.place(spec("pipe"), at(2, 8), Rotation::R0).unwrap();
+    // a removed building leaves a gap in the id sequence
+    let gone = g.place(spec("chest"), at(9, 9), Rotation::R0).unwrap();
+    g.remove(gone);
+
+    g.belt_insert(belt, item("iron-ore"));
+    g.offer_item(furnace, item("iron-ore"));
+    g.inventory_mut(chest)
+        .unwrap()
+        .insert(&ItemRegistry::builtin(), item("coal"), 7);
+    for _ in 0..10 {
+        step(&mut world);
+    }
+    world
+}
+
+fn step(world: &mut World) {
+    world.update_physics(1.0 / 60.0);
+    let g = &mut world.grid;
+    g.tick_power();
+    g.tick_drills();
+    g.tick_belts(|_, _| false);
+    g.tick_inserters();
+    g.tick_fluids();
+    g.tick_machines();
+}
+
+fn assert_same_occupancy(a: &TileGrid, b: &TileGrid) {
+    let bounds = a.bounds().unwrap();
+    assert_eq!(b.bounds(), Some(bounds));
+    for y in bounds.min.y..=bounds.max.y {
+        for x in bounds.min.x..=bounds.max.x {
+            assert_eq!(a.tile_occupant(at(x, y)), b.tile_occupant(at(x, y)));
+        }
+    }
+}
+
+#[test]
+fn binary_round_trip_preserves_everything() {
+    let world = busy_world();
+    let bytes = world.save_binary().unwrap();
+    assert!(bytes.starts_with(&SAVE_MAGIC));
+    let loaded = World::load_binary(&bytes).unwrap();
+
+    assert_same_occupancy(&world.grid, &loaded.grid);
+    assert_eq!(loaded.entities().len(), 2);
+    assert_eq!(loaded.find_player().unwrap().velocity.vx, 12.5);
+    // saving again gives identical bytes
+    assert_eq!(loaded.save_binary().unwrap(), bytes);
+}
+
+#[test]
+fn text_round_trip_preserves_everything() {
+    let world = busy_world();
+    let text = world.save_text().unwrap();
+    assert!(text.contains(&format!("version: {}", SAVE_VERSION)));
+    let loaded = World::load_text(&text).unwrap();
+
+    assert_same_occupancy(&world.grid, &loaded.grid);
+    assert_eq!(loaded.save_binary().unwrap(), world.save_binary().unwrap());
+}
+
+#[test]
+fn loaded_world_keeps_running_identically() {
+    let mut world = busy_world();
+    let mut loaded = World::load(&world.save_binary().unwrap()).unwrap();
+    for _ in 0..300 {
+        step(&mut world);
+        step(&mut loaded);
+    }
+    assert_eq!(loaded.save_binary().unwrap(), world.save_binary().unwrap());
+}
+
+#[test]
+fn id_counters_survive_loading() {
+    let mut world = busy_world();
+    let mut loaded = World::load_text(&world.save_text().unwrap()).unwrap();
+
+    world.spawn_enemy(0.0, 0.0);
+    loaded.spawn_enemy(0.0, 0.0);
+    assert_eq!(
+        loaded.entities().last().unwrap().id,
+        world.entities().last().unwrap().id
+    );
+
+    let a = world
+        .grid
+        .place(spec("chest"), at(12, 12), Rotation::R0)
+        .unwrap();
+    let b = loaded
+        .grid
+        .place(spec("chest"), at(12, 12), Rotation::R0)
+        .unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn rejects_foreign_and_future_data() {
+    assert!(matches!(
+        World::load_binary(b"not a save"),
+        Err(SaveError::NotASave)
+    ));
+
+    let mut bytes = busy_world().save_binary().unwrap();
+    bytes[4..8].copy_from_slice(&99u32.to_le_bytes());
+    assert!(matches!(
+        World::load_binary(&bytes),
+        Err(SaveError::UnsupportedVersion(99))
+    ));
+
+    let text = busy_world().save_text().unwrap().replacen(
+        &format!("version: {}", SAVE_VERSION),
+        "version: 99",
+        1,
+    );
+    assert!(matches!(
+        World::load_text(&text),
+        Err(SaveError::UnsupportedVersion(99))
+    ));
+}
+
+#[test]
+fn overlapping_buildings_are_reported_as_corrupt() {
+    let mut world = World::with_grid(TileGrid::new(8, 8));
+    world
+        .grid
+        .place(spec("chest"), at(1, 1), Rotation::R0)
+        .unwrap();
+    world
+        .grid
+        .place(spec("chest"), at(2, 1), Rotation::R0)
+        .unwrap();
+    let text = world.save_text().unwrap().replacen("x: 2,", "x: 1,", 1);
+    assert!(matches!(
+        World::load_text(&text),
+        Err(SaveError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn unbounded_grid_round_trips_negative_positions() {
+    let mut world = World::new();
+    let id = world
+        .grid
+        .place(spec("furnace"), at(-33, -3), Rotation::R0)
+        .unwrap();
+    let loaded = World::load(&world.save_binary().unwrap()).unwrap();
+    assert_eq!(loaded.grid.bounds(), None);
+    assert_eq!(loaded.grid.tile_occupant(at(-32, -2)), Some(id));
+    assert_eq!(loaded.grid.chunk_count(), 2);
+}
+
+#[test]
+fn loading_uses_the_builtin_data() {
+    let mut world = busy_world();
+    let mut gear = world.grid.recipes().by_name("iron-gear").unwrap().clone();
+    gear.craft_ticks = 1;
+    world.grid.recipes_mut().insert(gear.clone());
+
+    let text = world.save_text().unwrap();
+    assert!(!text.contains("iron-gear"));
+    let loaded = World::load_text(&text).unwrap();
+    let builtin = RecipeRegistry::builtin();
+    assert_eq!(
+        loaded
+            .grid
+            .recipes()
+            .get(gear.recipe_id)
+            .unwrap()
+            .craft_ticks,
+        builtin.get(gear.recipe_id).unwrap().craft_ticks
+    );
+    assert_eq!(loaded.grid.specs().len(), SpecRegistry::builtin().len());
+}
@@ -0,0 +1,154 @@
+use game_core::*;
+
+/// Deterministic scatter of enemies over roughly -800..800 on both axes.
+fn crowd(n: u32) -> World {
+    let mut world = World::new();
+    let mut seed = 12345u32;
+    let mut next = || {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        ((seed >> 8) % 1600) as f32 - 800.0
+    };
+    world.spawn_player(next(), next());
+    for _ in 0..n {
+        let (x, y) = (next(), next());
+        world.spawn_enemy(x, y);
+    }
+    world
+}
+
+fn ids(list: Vec<&Entity>) -> Vec<EntityId> {
+    list.into_iter().map(|e| e.id).collect()
+}
+
+fn brute_within(world: &World, x: f32, y: f32, r: f32) -> Vec<EntityId> {
+    world
+        .entities()
+        .iter()
+        .filter(|e| {
+            let (dx, dy) = (e.transform.x - x, e.transform.y - y);
+            dx * dx + dy * dy <= r * r
+        })
+        .map(|e| e.id)
+        .collect()
+}
+
+#[test]
+fn radius_queries_match_a_full_scan() {
+    let mut world = crowd(2000);
+    world.update_physics(0.0);
+    for (x, y, r) in [
+        (0.0, 0.0, 100.0),
+        (-790.0, 500.0, 64.0),
+        (300.0, -300.0, 250.0),
+    ] {
+        let found = ids(world.entities_within(x, y, r));
+        assert!(!found.is_empty());
+        assert_eq!(found, brute_within(&world, x, y, r));
+    }
+}
+
+#[test]
+fn rect_query_is_inclusive_and_handles_negative_space() {
+    let mut world = World::new();
+    world.spawn_enemy(-10.0, -10.0);
+    world.spawn_enemy(0.0, 0.0);
+    world.spawn_enemy(64.0, 64.0);
+    world.spawn_enemy(65.0, 0.0);
+    let found = ids(world.entities_in_rect((-10.0, -10.0), (64.0, 64.0)));
+    assert_eq!(found, vec![1, 2, 3]);
+}
+
+#[test]
+fn nearest_of_type_skips_other_types_and_breaks_ties_by_id() {
+    let mut world = World::new();
+    world.spawn_enemy(500.0, 0.0);
+    world.spawn_player(1.0, 0.0);
+    world.spawn_enemy(-100.0, 0.0);
+    world.spawn_enemy(100.0, 0.0);
+
+    let near = world.nearest_of_type(0.0, 0.0, EntityType::Enemy).unwrap();
+    assert_eq!(near.id, 3);
+    let player = world.nearest_of_type(900.0, 900.0, EntityType::Player);
+    assert_eq!(player.unwrap().id, 2);
+    // far outside everything still finds the closest
+    let far = world
+        .nearest_of_type(1.0e6, 0.0, EntityType::Enemy)
+        .unwrap();
+    assert_eq!(far.id, 1);
+}
+
+#[test]
+fn nearest_matches_a_full_scan() {
+    let mut world = crowd(500);
+    world.update_physics(0.0);
+    for (x, y) in [(0.0, 0.0), (799.0, -799.0), (-2000.0, 40.0)] {
+        let expected = world
+            .entities()
+            .iter()
+            .filter(|e| e.ty == EntityType::Enemy)
+            .min_by(|a, b| {
+                let d = |e: &Entity| (e.transform.x - x).powi(2) + (e.transform.y - y).powi(2);
+                d(a).total_cmp(&d(b)).then(a.id.cmp(&b.id))
+            })
+            .unwrap()
+            .id;
+        let found = world.nearest_of_type(x, y, EntityType::Enemy).unwrap();
+        assert_eq!(found.id, expected);
+    }
+}
+
+#[test]
+fn index_follows_movement() {
+    let mut world = World::new();
+    world.spawn_enemy(0.0, 0.0);
+    world.entities_mut()[0].velocity.vx = 300.0;
+    world.update_physics(1.0);
+    assert!(world.entities_within(0.0, 0.0, 50.0).is_empty());
+    assert_eq!(ids(world.entities_within(300.0, 0.0, 1.0)), vec![1]);
+}
+
+#[test]
+fn direct_edits_fall_back_until_reindexed() {
+    let mut world = crowd(50);
+    world.entities_mut().retain(|e| e.ty == EntityType::Player);
+    // stale index: answers must still be right
+    assert_eq!(world.entities_within(0.0, 0.0, 5000.0).len(), 1);
+    assert!(world.find_player().is_some());
+    world.reindex();
+    assert_eq!(world.entities_within(0.0, 0.0, 5000.0).len(), 1);
+}
+
+#[test]
+fn edits_that_keep_the_count_are_noticed() {
+    let mut world = World::new();
+    world.spawn_player(0.0, 0.0);
+    world.spawn_enemy(500.0, 500.0);
+
+    // move an entity without changing how many there are
+    world.entities_mut()[1].transform.x = -500.0;
+    assert_eq!(ids(world.entities_within(-500.0, 500.0, 1.0)), vec![2]);
+    assert!(world.entities_within(500.0, 500.0, 1.0).is_empty());
+
+    // swap the player for an enemy: same length, no player left
+    world.entities_mut()[0].ty = EntityType::Enemy;
+    assert!(world.find_player().is_none());
+    world.reindex();
+    assert!(world.find_player().is_none());
+    assert_eq!(
+        world
+            .nearest_of_type(0.0, 0.0, EntityType::Enemy)
+            .map(|e| e.id),
+        Some(1)
+    );
+}
+
+#[test]
+fn loaded_world_is_indexed() {
+    let world = crowd(100);
+    let loaded = World::load(&world.save_binary().unwrap()).unwrap();
+    assert_eq!(
+        ids(loaded.entities_within(0.0, 0.0, 300.0)),
+        brute_within(&world, 0.0, 0.0, 300.0)
+    );
+    assert_eq!(loaded.find_player().unwrap().id, 1);
+}
@@ -0,0 +1,58 @@
+use game_core::*;
+
+#[test]
+fn builtin_specs_load() {
+    let specs = SpecRegistry::builtin();
+    let conveyor = specs.by_name("conveyor").expect("conveyor missing");
+    assert_eq!(conveyor.spec_id, 1);
+    assert_eq!(conveyor.size, Size2 { w: 1, h: 1 });
+    assert_eq!(specs.get(2).unwrap().size, Size2 { w: 2, h: 2 });
+    assert_eq!(specs.get(3).unwrap().category, BuildingCategory::Production);
+}
+
+#[test]
+fn parse_defaults_optional_fields() {
+    let text = r#"[(spec_id: 10, name: "chest", size: (w: 1, h: 2))]"#;
+    let specs = SpecRegistry::from_ron_str(text).expect("parse failed");
+    let chest = specs.get(10).unwrap();
+    assert_eq!(chest.size, Size2 { w: 1, h: 2 });
+    assert_eq!(chest.category, BuildingCategory::Other);
+    assert!(chest.sprite.is_none());
+}
+
+#[test]
+fn duplicate_ids_are_rejected() {
+    let text = r#"[
+        (spec_id: 1, name: "a", size: (w: 1, h: 1)),
+        (spec_id: 1, name: "b", size: (w: 2, h: 2)),
+    ]"#;
+    match SpecRegistry::from_ron_str(text) {
+        Err(SpecError::DuplicateId(1)) => {}
+        other => panic!("expected DuplicateId, got {:?}", other),
+    }
+}
+
+#[test]
+fn malformed_text_is_a_parse_error() {
+    assert!(matches!(
+        SpecRegistry::from_ron_str("[(spec_id: )]"),
+        Err(SpecError::Parse(_))
+    ));
+}
+
+#[test]
+fn specs_in_use_cannot_be_replaced() {
+    let mut g = TileGrid::new(10, 10);
+    let mut conveyor = g.specs().get(1).unwrap().clone();
+    conveyor.size = Size2 { w: 2, h: 2 };
+    let id = g.place(1, TilePos { x: 0, y: 0 }, Rotation::R0).unwrap();
+    assert!(matches!(
+        g.insert_spec(conveyor.clone()),
+        Err(SpecError::InUse(1))
+    ));
+    assert_eq!(g.specs().get(1).unwrap().size, Size2 { w: 1, h: 1 });
+
+    g.remove(id);
+    assert!(g.insert_spec(conveyor).unwrap().is_some());
+    assert_eq!(g.specs().get(1).unwrap().size, Size2 { w: 2, h: 2 });
+}
@@ -0,0 +1,108 @@
+use game_core::*;
+
+mod common;
+use common::*;
+
+fn water() -> TerrainTile {
+    TerrainTile {
+        kind: TerrainKind::Water,
+        deposit: None,
+    }
+}
+
+#[test]
+fn unset_tiles_are_ground() {
+    let g = TileGrid::new(4, 4);
+    assert_eq!(g.terrain(at(1, 1)), TerrainTile::default());
+    assert_eq!(g.terrain_tiles().count(), 0);
+}
+
+#[test]
+fn deposits_are_stored_per_tile() {
+    let mut g = TileGrid::new(4, 4);
+    let pos = at(2, 1);
+    let ore = Deposit {
+        item: 1,
+        amount: 300,
+    };
+    g.set_terrain(
+        pos,
+        TerrainTile {
+            kind: TerrainKind::Ground,
+            deposit: Some(ore),
+        },
+    )
+    .unwrap();
+    assert_eq!(g.deposit(pos), Some(ore));
+    assert_eq!(g.deposit(at(0, 0)), None);
+    // resetting to plain ground drops the entry
+    g.set_terrain(pos, TerrainTile::default()).unwrap();
+    assert_eq!(g.terrain_tiles().count(), 0);
+}
+
+#[test]
+fn buildings_cannot_stand_on_water_or_cliffs() {
+    let mut g = TileGrid::new(10, 10);
+    g.set_terrain(at(1, 1), water()).unwrap();
+    g.set_terrain(
+        at(5, 5),
+        TerrainTile {
+            kind: TerrainKind::Cliff,
+            deposit: None,
+        },
+    )
+    .unwrap();
+    // furnace footprint (0,0)-(1,1) overlaps the water tile
+    assert!(!g.can_place(spec("furnace"), at(0, 0), Rotation::R0));
+    match g.place(spec("furnace"), at(0, 0), Rotation::R0) {
+        Err(PlacementError::BlockedTerrain) => {}
+        other => panic!("expected BlockedTerrain, got {:?}", other),
+    }
+    assert!(!g.can_place(spec("conveyor"), at(5, 5), Rotation::R0));
+    assert!(g.can_place(spec("furnace"), at(2, 2), Rotation::R0));
+}
+
+#[test]
+fn ore_tiles_are_buildable() {
+    let mut g = TileGrid::new(10, 10);
+    let pos = at(3, 3);
+    g.set_terrain(
+        pos,
+        TerrainTile {
+            kind: TerrainKind::Ground,
+            deposit: Some(Deposit {
+                item: 1,
+                amount: 10,
+            }),
+        },
+    )
+    .unwrap();
+    assert!(g.place(spec("conveyor"), pos, Rotation::R0).is_ok());
+}
+
+#[test]
+fn terrain_in_rect_skips_tiles_outside() {
+    let mut g = TileGrid::new(10, 10);
+    for (x, y) in [(1, 1), (1, 5), (3, 2), (8, 2)] {
+        g.set_terrain(TilePos { x, y }, water()).unwrap();
+    }
+    let view = TileRect::spanning(at(0, 0), at(4, 3));
+    let tiles: Vec<TilePos> = g.terrain_in(view).map(|(p, _)| p).collect();
+    assert_eq!(tiles, vec![at(1, 1), at(3, 2)]);
+}
+
+#[test]
+fn terrain_under_buildings_or_off_the_grid_is_refused() {
+    let mut g = TileGrid::new(10, 10);
+    g.place(spec("furnace"), at(0, 0), Rotation::R0).unwrap();
+    assert!(matches!(
+        g.set_terrain(at(1, 1), water()),
+        Err(PlacementError::Occupied)
+    ));
+    assert!(matches!(
+        g.set_terrain(at(10, 3), water()),
+        Err(PlacementError::OutOfBounds)
+    ));
+    assert_eq!(g.terrain_tiles().count(), 0);
+    assert!(g.set_terrain(at(2, 2), water()).is_ok());
+}
@@ -0,0 +1,134 @@
+//! The view onto the world: where it is centred, how far it is zoomed in,
+//! and conversion between screen, world and tile coordinates.
+//!
+//! Screen coordinates are pixels from the top-left of the viewport, world
+//! coordinates are the units entities move in, and both grow to the right
+//! and downward. `zoom` is screen pixels per world unit, and `target` is the
+//! world point shown at the centre of the viewport. Nothing here touches a
+//! platform API, so the platform layer feeds in window size and pointer
+//! movement and reads the conversions back.
+
+use game_core::{tile_at, TilePos, TileRect, TILE_SIZE};
+
+pub const MIN_ZOOM: f32 = 0.2;
+pub const MAX_ZOOM: f32 = 5.0;
+
+/// Zoom factor applied per notch of the mouse wheel.
+pub const WHEEL_ZOOM_STEP: f32 = 1.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// World point at the centre of the viewport
+    pub target: (f32, f32),
+    /// Screen pixels per world unit, within `MIN_ZOOM..=MAX_ZOOM`
+    pub zoom: f32,
+    /// Viewport size in screen pixels
+    pub viewport: (f32, f32),
+    /// Tiles the target is kept over, if any
+    pub bounds: Option<TileRect>,
+}
+
+impl Camera {
+    /// A camera at zoom 1 showing the world from its origin at the top-left
+    /// of a `width` x `height` viewport, so screen and world agree.
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            target: (width / 2.0, height / 2.0),
+            zoom: 1.0,
+            viewport: (width, height),
+            bounds: None,
+        }
+    }
+
+    /// Follow a change of window size, keeping the same world point centred.
+    pub fn set_viewport(&mut self, width: f32, height: f32) {
+        self.viewport = (width, height);
+    }
+
+    /// Keep the target over `bounds`, or anywhere for `None`.
+    pub fn set_bounds(&mut self, bounds: Option<TileRect>) {
+        self.bounds = bounds;
+        self.clamp();
+    }
+
+    pub fn screen_to_world(&self, (sx, sy): (f32, f32)) -> (f32, f32) {
+        let (w, h) = self.viewport;
+        (
+            self.target.0 + (sx - w / 2.0) / self.zoom,
+            self.target.1 + (sy - h / 2.0) / self.zoom,
+        )
+    }
+
+    pub fn world_to_screen(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (w, h) = self.viewport;
+        (
+            (x - self.target.0) * self.zoom + w / 2.0,
+            (y - self.target.1) * self.zoom + h / 2.0,
+        )
+    }
+
+    /// The tile under screen point `screen`.
+    pub fn screen_to_tile(&self, screen: (f32, f32)) -> TilePos {
+        let (x, y) = self.screen_to_world(screen);
+        tile_at(x, y)
+    }
+
+    /// Every tile at least partly inside the viewport.
+    pub fn visible_tiles(&self) -> TileRect {
+        let (w, h) = self.viewport;
+        TileRect {
+            min: self.screen_to_tile((0.0, 0.0)),
+            max: self.screen_to_tile((w, h)),
+        }
+    }
+
+    /// Move the view by a screen-space drag of (dx, dy) pixels, so the world
+    /// follows the pointer.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.target.0 -= dx / self.zoom;
+        self.target.1 -= dy / self.zoom;
+        self.clamp();
+    }
+
+    /// Multiply the zoom by `factor`, keeping the world point under screen
+    /// point `at` where it is.
+    pub fn zoom_at(&mut self, at: (f32, f32), factor: f32) {
+        let before = self.screen_to_world(at);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.screen_to_world(at);
+        self.target.0 += before.0 - after.0;
+        self.target.1 += before.1 - after.1;
+        self.clamp();
+    }
+
+    /// Mouse wheel: `notches` up zooms in, down zooms out, around `at`.
+    pub fn wheel(&mut self, at: (f32, f32), notches: f32) {
+        self.zoom_at(at, WHEEL_ZOOM_STEP.powf(notches));
+    }
+
+    /// Two-finger gesture from touch points `from` to `to`: zoom by how far
+    /// the fingers spread and pan with their midpoint.
+    pub fn pinch(&mut self, from: [(f32, f32); 2], to: [(f32, f32); 2]) {
+        let spread = |[a, b]: [(f32, f32); 2]| (a.0 - b.0).hypot(a.1 - b.1);
+        let mid = |[a, b]: [(f32, f32); 2]| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        let (m0, m1) = (mid(from), mid(to));
+        self.pan(m1.0 - m0.0, m1.1 - m0.1);
+        if spread(from) > 0.0 {
+            self.zoom_at(m1, spread(to) / spread(from));
+        }
+    }
+
+    /// Pull zoom and target back within their limits.
+    pub fn clamp(&mut self) {
+        self.zoom = self.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if let Some(b) = self.bounds {
+            let (left, top) = (b.min.x as f32 * TILE_SIZE, b.min.y as f32 * TILE_SIZE);
+            let (right, bottom) = (
+                (b.max.x + 1) as f32 * TILE_SIZE,
+                (b.max.y + 1) as f32 * TILE_SIZE,
+            );
+            self.target.0 = self.target.0.clamp(left, right);
+            self.target.1 = self.target.1.clamp(top, bottom);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Everything observable that happened during a tick, as one typed stream.
+//!
+//! `update_world` collects `GameEvent`s in the order the tick produced them:
+//! building changes made since the previous tick, then combat, then the
+//! factory. `Simulation` keeps them until the platform layer takes them and
+//! hands them to its `EventSubscriber`s.
+
+use game_core::{
+    BuildingChange, CombatEvent, EntityId, EntityType, InstanceId, ItemId, NetworkId, RecipeId,
+    SpecId, TechId, TilePos,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    BuildingPlaced {
+        id: InstanceId,
+        spec_id: SpecId,
+        origin: TilePos,
+    },
+    BuildingRemoved {
+        id: InstanceId,
+        spec_id: SpecId,
+        origin: TilePos,
+    },
+    MachineStarted {
+        id: InstanceId,
+        recipe: RecipeId,
+    },
+    MachineFinished {
+        id: InstanceId,
+        recipe: RecipeId,
+    },
+    /// `count` units of `item` made by building `by`, mined or crafted
+    ItemProduced {
+        by: InstanceId,
+        item: ItemId,
+        count: u32,
+    },
+    /// Lab `id` consumed science and began a unit of `tech`
+    LabStarted {
+        id: InstanceId,
+        tech: TechId,
+    },
+    /// Labs finished the last unit of `tech`
+    ResearchFinished {
+        tech: TechId,
+    },
+    /// Network `network` started demanding more power than it produces
+    PowerShortage {
+        network: NetworkId,
+        production: u32,
+        demand: u32,
+    },
+    ProjectileFired {
+        projectile: EntityId,
+        by: EntityId,
+    },
+    EntityDamaged {
+        target: EntityId,
+        damage: f32,
+        x: f32,
+        y: f32,
+    },
+    EntityDied {
+        id: EntityId,
+        ty: EntityType,
+        x: f32,
+        y: f32,
+    },
+}
+
+impl From<CombatEvent> for GameEvent {
+    fn from(event: CombatEvent) -> Self {
+        match event {
+            CombatEvent::Damaged {
+                target,
+                damage,
+                x,
+                y,
+            } => GameEvent::EntityDamaged {
+                target,
+                damage,
+                x,
+                y,
+            },
+            CombatEvent::Died { id, ty, x, y } => GameEvent::EntityDied { id, ty, x, y },
+        }
+    }
+}
+
+impl From<BuildingChange> for GameEvent {
+    fn from(change: BuildingChange) -> Self {
+        match change {
+            BuildingChange::Placed {
+                id,
+                spec_id,
+                origin,
+            } => GameEvent::BuildingPlaced {
+                id,
+                spec_id,
+                origin,
+            },
+            BuildingChange::Removed {
+                id,
+                spec_id,
+                origin,
+            } => GameEvent::BuildingRemoved {
+                id,
+                spec_id,
+                origin,
+            },
+        }
+    }
+}
+
+/// Something that reacts to game events: a HUD, sound, a log.
+pub trait EventSubscriber {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// Hand every event, in order, to every subscriber.
+pub fn dispatch(events: &[GameEvent], subscribers: &mut [&mut dyn EventSubscriber]) {
+    for event in events {
+        for subscriber in subscribers.iter_mut() {
+            subscriber.on_event(event);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Flow fields: one search outward from the goals tells every tile in an
+//! area which way to walk, so any number of enemies can steer in O(1) each.
+//!
+//! The integration field holds each tile's walking cost to the nearest goal,
+//! with the same step costs and corner rule as `pathfinding`. The direction
+//! field points each reachable tile at its cheapest neighbour, ties going to
+//! the first in `NEIGHBOURS` order. Both depend only on the grid, the area
+//! and the goals. `FlowField::update` repairs just the tiles whose route
+//! changed when buildings were placed or removed, and gives the same result
+//! as a full `rebuild`.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use game_core::{tile_at, ChunkPos, TileGrid, TilePos, TileRect, CHUNK_SIZE};
+
+use crate::pathfinding::{offset, tile_center, walkable, DIAGONAL_COST, NEIGHBOURS, STRAIGHT_COST};
+
+/// Chunks on each side of the goal's chunk covered by `FlowField::area_around`.
+pub const FLOW_FIELD_CHUNKS: i32 = 1;
+
+const UNREACHED: u32 = u32::MAX;
+const NO_STEP: u8 = u8::MAX;
+
+pub struct FlowField {
+    area: TileRect,
+    goals: Vec<TilePos>,
+    /// Whether each tile of `area` was walkable at the last update, row by row
+    open: Vec<bool>,
+    /// Integration field: cost to the nearest goal, `UNREACHED` if none
+    cost: Vec<u32>,
+    /// Direction field: index into `NEIGHBOURS` of the next tile, `NO_STEP`
+    /// on goals and unreachable tiles
+    next: Vec<u8>,
+    /// Grid layout the fields were computed for, `None` if they are stale
+    revision: Option<u64>,
+}
+
+impl FlowField {
+    /// An empty field over `area`; call `update` before sampling it. Goals
+    /// outside the area are ignored.
+    pub fn new(area: TileRect, goals: &[TilePos]) -> Self {
+        let len = (area.width() * area.height()) as usize;
+        let mut field = Self {
+            area,
+            goals: Vec::new(),
+            open: vec![false; len],
+            cost: vec![UNREACHED; len],
+            next: vec![NO_STEP; len],
+            revision: None,
+        };
+        field.set_goals(goals);
+        field
+    }
+
+    /// The area a field toward `goal` should cover: the chunks around it,
+    /// cut to the grid bounds if there are any. Depends on nothing but the
+    /// goal's chunk, so it only moves when the goal changes chunk.
+    pub fn area_around(grid: &TileGrid, goal: TilePos) -> TileRect {
+        let chunk = ChunkPos::of(goal);
+        let min = ChunkPos {
+            x: chunk.x - FLOW_FIELD_CHUNKS,
+            y: chunk.y - FLOW_FIELD_CHUNKS,
+        }
+        .origin();
+        let span = (2 * FLOW_FIELD_CHUNKS + 1) * CHUNK_SIZE - 1;
+        let window = TileRect {
+            min,
+            max: offset(min, span, span),
+        };
+        let Some(bounds) = grid.bounds() else {
+            return window;
+        };
+        let cut = TileRect {
+            min: TilePos {
+                x: window.min.x.max(bounds.min.x),
+                y: window.min.y.max(bounds.min.y),
+            },
+            max: TilePos {
+                x: window.max.x.min(bounds.max.x),
+                y: window.max.y.min(bounds.max.y),
+            },
+        };
+        if cut.min.x <= cut.max.x && cut.min.y <= cut.max.y {
+            cut
+        } else {
+            window
+        }
+    }
+
+    pub fn area(&self) -> TileRect {
+        self.area
+    }
+
+    pub fn goals(&self) -> &[TilePos] {
+        &self.goals
+    }
+
+    /// Change the goals. The next `update` rebuilds the whole field if they
+    /// differ from the current ones.
+    pub fn set_goals(&mut self, goals: &[TilePos]) {
+        let mut goals: Vec<TilePos> = goals
+            .iter()
+            .copied()
+            .filter(|&g| self.area.contains(g))
+            .collect();
+        goals.sort();
+        goals.dedup();
+        if goals != self.goals {
+            self.goals = goals;
+            self.revision = None;
+        }
+    }
+
+    /// Bring the field up to date with `grid`: nothing if its layout is
+    /// unchanged, a repair of the affected tiles if buildings changed, a full
+    /// rebuild after the goals changed.
+    pub fn update(&mut self, grid: &TileGrid) {
+        match self.revision {
+            Some(rev) if rev == grid.layout_revision() => {}
+            Some(_) => self.repair(grid),
+            None => self.rebuild(grid),
+        }
+    }
+
+    /// Recompute both fields from scratch.
+    pub fn rebuild(&mut self, grid: &TileGrid) {
+        self.open = self.snapshot(grid);
+        self.cost.fill(UNREACHED);
+        let mut queue = BinaryHeap::new();
+        let starts: Vec<usize> = self.goals.iter().filter_map(|&g| self.index(g)).collect();
+        for i in starts {
+            if self.open[i] {
+                self.cost[i] = 0;
+                queue.push(Reverse((0, i)));
+            }
+        }
+        self.propagate(queue);
+        for i in 0..self.next.len() {
+            self.next[i] = self.best_step(i);
+        }
+        self.revision = Some(grid.layout_revision());
+    }
+
+    /// Walking cost from `pos` to the nearest goal, in tenths of a tile.
+    pub fn cost(&self, pos: TilePos) -> Option<u32> {
+        let c = self.cost[self.index(pos)?];
+        (c != UNREACHED).then_some(c)
+    }
+
+    /// The tile to walk to from `pos`. `None` on a goal, on an unreachable
+    /// tile and outside the area.
+    pub fn next_tile(&self, pos: TilePos) -> Option<TilePos> {
+        let k = self.next[self.index(pos)?];
+        let (dx, dy) = *NEIGHBOURS.get(k as usize)?;
+        Some(offset(pos, dx, dy))
+    }
+
+    /// Unit direction for an entity at world point (x, y): toward the centre
+    /// of the next tile. `None` wherever `next_tile` is.
+    pub fn sample(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        let (tx, ty) = tile_center(self.next_tile(tile_at(x, y))?);
+        let (dx, dy) = (tx - x, ty - y);
+        let len = dx.hypot(dy).max(0.001);
+        Some((dx / len, dy / len))
+    }
+
+    fn index(&self, pos: TilePos) -> Option<usize> {
+        if !self.area.contains(pos) {
+            return None;
+        }
+        let (x, y) = (pos.x - self.area.min.x, pos.y - self.area.min.y);
+        Some((y as u32 * self.area.width() + x as u32) as usize)
+    }
+
+    fn pos(&self, i: usize) -> TilePos {
+        let w = self.area.width() as usize;
+        offset(self.area.min, (i % w) as i32, (i / w) as i32)
+    }
+
+    fn snapshot(&self, grid: &TileGrid) -> Vec<bool> {
+        (0..self.open.len())
+            .map(|i| walkable(grid, self.pos(i)))
+            .collect()
+    }
+
+    /// Index and cost of stepping from tile `i` toward `NEIGHBOURS[k]`, if
+    /// that tile is open and the step does not cut a corner.
+    fn step(&self, i: usize, k: usize) -> Option<(usize, u32)> {
+        let (dx, dy) = NEIGHBOURS[k];
+        let from = self.pos(i);
+        let is_open = |p: TilePos| self.index(p).is_some_and(|j| self.open[j]);
+        let j = self.index(offset(from, dx, dy)).filter(|&j| self.open[j])?;
+        if dx != 0 && dy != 0 {
+            if !(is_open(offset(from, dx, 0)) && is_open(offset(from, 0, dy))) {
+                return None;
+            }
+            return Some((j, DIAGONAL_COST));
+        }
+        Some((j, STRAIGHT_COST))
+    }
+
+    /// Every in-area neighbour of tile `i`, walkable or not.
+    fn around(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let pos = self.pos(i);
+        NEIGHBOURS
+            .iter()
+            .filter_map(move |&(dx, dy)| self.index(offset(pos, dx, dy)))
+    }
+
+    /// Dijkstra from the queued tiles, only ever lowering costs. Returns the
+    /// tiles whose cost went down.
+    fn propagate(&mut self, mut queue: BinaryHeap<Reverse<(u32, usize)>>) -> Vec<usize> {
+        let mut lowered = Vec::new();
+        while let Some(Reverse((c, i))) = queue.pop() {
+            if c > self.cost[i] {
+                continue;
+            }
+            for k in 0..NEIGHBOURS.len() {
+                let Some((j, step)) = self.step(i, k) else {
+                    continue;
+                };
+                if c + step < self.cost[j] {
+                    self.cost[j] = c + step;
+                    lowered.push(j);
+                    queue.push(Reverse((c + step, j)));
+                }
+            }
+        }
+        lowered
+    }
+
+    /// The neighbour of `i` lying on a cheapest route to a goal.
+    fn best_step(&self, i: usize) -> u8 {
+        let c = self.cost[i];
+        if c == 0 || c == UNREACHED {
+            return NO_STEP;
+        }
+        let mut best = (UNREACHED, NO_STEP);
+        for k in 0..NEIGHBOURS.len() {
+            if let Some((j, step)) = self.step(i, k) {
+                if self.cost[j] != UNREACHED && self.cost[j] + step < best.0 {
+                    best = (self.cost[j] + step, k as u8);
+                }
+            }
+        }
+        best.1
+    }
+
+    /// Update the fields for the tiles that opened or closed since the last
+    /// update.
+    fn repair(&mut self, grid: &TileGrid) {
+        let fresh = self.snapshot(grid);
+        let changed: Vec<usize> = (0..fresh.len())
+            .filter(|&i| fresh[i] != self.open[i])
+            .collect();
+        self.open = fresh;
+        self.revision = Some(grid.layout_revision());
+        if changed.is_empty() {
+            return;
+        }
+
+        // Tiles whose route crossed a closed tile or a newly cut corner, and
+        // everything routed through them, lose their cost.
+        let mut stale = vec![false; self.cost.len()];
+        let mut work: Vec<usize> = Vec::new();
+        for &c in changed.iter().filter(|&&c| !self.open[c]) {
+            stale[c] = true;
+            work.push(c);
+            for i in self.around(c) {
+                let k = self.next[i];
+                if !stale[i] && k != NO_STEP && self.step(i, k as usize).is_none() {
+                    stale[i] = true;
+                    work.push(i);
+                }
+            }
+        }
+        let mut invalid = Vec::new();
+        while let Some(t) = work.pop() {
+            invalid.push(t);
+            let target = self.pos(t);
+            let upstream: Vec<usize> = self
+                .around(t)
+                .filter(|&i| !stale[i] && self.next_tile(self.pos(i)) == Some(target))
+                .collect();
+            for i in upstream {
+                stale[i] = true;
+                work.push(i);
+            }
+        }
+        for &i in &invalid {
+            self.cost[i] = UNREACHED;
+        }
+
+        // Refill from the intact tiles bordering them and around opened
+        // tiles, which may offer shorter routes.
+        let mut queue = BinaryHeap::new();
+        let mut borders: Vec<usize> = invalid.clone();
+        for &c in changed.iter().filter(|&&c| self.open[c]) {
+            if self.goals.contains(&self.pos(c)) {
+                self.cost[c] = 0;
+                queue.push(Reverse((0, c)));
+            }
+            borders.push(c);
+        }
+        for &b in &borders {
+            for i in self.around(b) {
+                if self.cost[i] != UNREACHED {
+                    queue.push(Reverse((self.cost[i], i)));
+                }
+            }
+        }
+        let lowered = self.propagate(queue);
+
+        // Directions can change on any touched tile and its neighbours.
+        let mut redo = vec![false; self.next.len()];
+        for &t in changed.iter().chain(&invalid).chain(&lowered) {
+            redo[t] = true;
+            for i in self.around(t) {
+                redo[i] = true;
+            }
+        }
+        for i in (0..redo.len()).filter(|&i| redo[i]) {
+            self.next[i] = self.best_step(i);
+        }
+    }
+}
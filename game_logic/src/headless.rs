@@ -0,0 +1,132 @@
+//! Running the simulation with no window, for batch runs and regression
+//! checks on machines without a display.
+//!
+//! A `Scenario` is a starting world plus the input for each tick. It comes
+//! from a save, which runs with no input at all, or from a replay, whose
+//! recorded inputs drive the run until they are used up. `run` steps the
+//! simulation as fast as it will go and tallies what happened.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use game_core::{EntityType, ItemId, RecipeId, SaveError, TechId, World};
+
+use crate::events::{EventSubscriber, GameEvent};
+use crate::replay::{Replay, ReplayError, REPLAY_MAGIC};
+use crate::simulation::{Simulation, TICK_RATE};
+use crate::InputFrame;
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    /// A save file that failed to load
+    Save(SaveError),
+    /// A replay file that failed to load
+    Replay(ReplayError),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Save(e) => write!(f, "{}", e),
+            ScenarioError::Replay(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+pub struct Scenario {
+    pub world: World,
+    pub tick_rate: u32,
+    /// Input for each tick; index 0 is tick 1. Ticks past the end get none.
+    pub inputs: Vec<InputFrame>,
+}
+
+impl Scenario {
+    /// A world left to run on its own.
+    pub fn from_world(world: World) -> Self {
+        Self {
+            world,
+            tick_rate: TICK_RATE,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Read a binary or text save, or a replay, telling them apart by their
+    /// magic bytes.
+    pub fn load(bytes: &[u8]) -> Result<Self, ScenarioError> {
+        if bytes.starts_with(&REPLAY_MAGIC) {
+            let replay = Replay::from_bytes(bytes).map_err(ScenarioError::Replay)?;
+            let world = World::load_binary(&replay.initial_state)
+                .map_err(|e| ScenarioError::Replay(ReplayError::InitialState(e)))?;
+            return Ok(Self {
+                world,
+                tick_rate: replay.tick_rate,
+                inputs: replay.inputs,
+            });
+        }
+        World::load(bytes)
+            .map(Self::from_world)
+            .map_err(ScenarioError::Save)
+    }
+}
+
+/// What happened over a headless run, tallied from its `GameEvent`s.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunStats {
+    pub ticks: u64,
+    /// Units made of each item, mined or crafted
+    pub produced: BTreeMap<ItemId, u64>,
+    /// Crafts finished of each recipe
+    pub crafted: BTreeMap<RecipeId, u64>,
+    pub buildings_placed: u64,
+    pub buildings_removed: u64,
+    pub power_shortages: u64,
+    pub enemies_killed: u64,
+    pub player_deaths: u64,
+    /// Technologies finished, in order
+    pub researched: Vec<TechId>,
+    /// `World::checksum` of the final state
+    pub checksum: u64,
+}
+
+impl EventSubscriber for RunStats {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::ItemProduced { item, count, .. } => {
+                *self.produced.entry(item).or_default() += count as u64;
+            }
+            GameEvent::MachineFinished { recipe, .. } => {
+                *self.crafted.entry(recipe).or_default() += 1;
+            }
+            GameEvent::BuildingPlaced { .. } => self.buildings_placed += 1,
+            GameEvent::BuildingRemoved { .. } => self.buildings_removed += 1,
+            GameEvent::PowerShortage { .. } => self.power_shortages += 1,
+            GameEvent::ResearchFinished { tech } => self.researched.push(tech),
+            GameEvent::EntityDied { ty, .. } => match ty {
+                EntityType::Enemy => self.enemies_killed += 1,
+                EntityType::Player => self.player_deaths += 1,
+                EntityType::Projectile => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Run `ticks` ticks of `scenario` and return the final simulation with
+/// its stats. The result depends only on the scenario and the tick count.
+pub fn run(scenario: Scenario, ticks: u64) -> (Simulation, RunStats) {
+    let mut sim = Simulation::with_tick_rate(scenario.world, scenario.tick_rate);
+    let mut stats = RunStats::default();
+    let idle = InputFrame::default();
+    for i in 0..ticks {
+        let input = scenario.inputs.get(i as usize).unwrap_or(&idle);
+        sim.step(input);
+        for event in sim.take_events() {
+            stats.on_event(&event);
+        }
+    }
+    stats.ticks = sim.tick();
+    stats.checksum = sim.world.checksum();
+    (sim, stats)
+}
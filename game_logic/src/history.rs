@@ -0,0 +1,285 @@
+//! Undoable building edits. Every change to the grid made through `History`
+//! is recorded as a reversible step; undoing a removal restores the building
+//! under its old id with its old contents, so later steps that refer to that
+//! id stay valid.
+
+use std::fmt;
+
+use game_core::{InstanceId, PlacementError, RemovedBuilding, Rotation, SpecId, TileGrid, TilePos};
+
+use crate::placement::try_place_building;
+
+/// Default number of undo steps kept.
+pub const HISTORY_LIMIT: usize = 100;
+
+/// An edit the player asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Build a spec, which must be researched
+    Place {
+        spec_id: SpecId,
+        origin: TilePos,
+        rotation: Rotation,
+    },
+    Remove {
+        id: InstanceId,
+    },
+    /// Turn a building in place, keeping its origin
+    Rotate {
+        id: InstanceId,
+        rotation: Rotation,
+    },
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    Placement(PlacementError),
+    NoSuchBuilding(InstanceId),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Placement(e) => write!(f, "cannot place building: {:?}", e),
+            CommandError::NoSuchBuilding(id) => write!(f, "no building with id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<PlacementError> for CommandError {
+    fn from(e: PlacementError) -> Self {
+        CommandError::Placement(e)
+    }
+}
+
+/// One recorded effect on the grid.
+#[derive(Clone, Debug)]
+enum Change {
+    /// Building `id` appeared. `undone` holds it while it is undone so redo
+    /// brings back the same id.
+    Added {
+        id: InstanceId,
+        undone: Option<RemovedBuilding>,
+    },
+    /// Building left the grid; `building` is what it looked like
+    Removed { building: RemovedBuilding },
+    Rotated {
+        id: InstanceId,
+        from: Rotation,
+        to: Rotation,
+    },
+}
+
+impl Change {
+    fn undo(&mut self, grid: &mut TileGrid) -> Result<(), CommandError> {
+        match self {
+            Change::Added { id, undone } => {
+                *undone = Some(grid.take(*id).ok_or(CommandError::NoSuchBuilding(*id))?);
+            }
+            Change::Removed { building } => {
+                grid.restore(building.clone())?;
+            }
+            Change::Rotated { id, from, .. } => set_rotation(grid, *id, *from)?,
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, grid: &mut TileGrid) -> Result<(), CommandError> {
+        match self {
+            Change::Added { id, undone } => {
+                let building = undone.take().ok_or(CommandError::NoSuchBuilding(*id))?;
+                if let Err(e) = grid.restore(building.clone()) {
+                    *undone = Some(building);
+                    return Err(e.into());
+                }
+            }
+            Change::Removed { building } => {
+                let id = building.instance.id;
+                *building = grid.take(id).ok_or(CommandError::NoSuchBuilding(id))?;
+            }
+            Change::Rotated { id, to, .. } => set_rotation(grid, *id, *to)?,
+        }
+        Ok(())
+    }
+}
+
+/// Re-place building `id` with `rotation`, putting it back unchanged if the
+/// new footprint does not fit.
+fn set_rotation(
+    grid: &mut TileGrid,
+    id: InstanceId,
+    rotation: Rotation,
+) -> Result<(), CommandError> {
+    let mut building = grid.take(id).ok_or(CommandError::NoSuchBuilding(id))?;
+    let old = building.instance.rotation;
+    building.instance.rotation = rotation;
+    if let Err(e) = grid.restore(building.clone()) {
+        building.instance.rotation = old;
+        grid.restore(building)?;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Undo and redo stacks of grid edits.
+pub struct History {
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    /// Changes collected between `begin_group` and `end_group`
+    group: Option<Vec<Change>>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::with_limit(HISTORY_LIMIT)
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            group: None,
+            limit: limit.max(1),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Collect every following command into one undo step until
+    /// `end_group`, e.g. for the buildings laid down by one drag.
+    pub fn begin_group(&mut self) {
+        if self.group.is_none() {
+            self.group = Some(Vec::new());
+        }
+    }
+
+    pub fn end_group(&mut self) {
+        if let Some(changes) = self.group.take() {
+            self.push_step(changes);
+        }
+    }
+
+    /// Apply `cmd` to `grid` and record it. Returns the id of the building
+    /// placed, removed or rotated. Failed commands are not recorded.
+    pub fn apply(&mut self, grid: &mut TileGrid, cmd: Command) -> Result<InstanceId, CommandError> {
+        let (id, change) = match cmd {
+            Command::Place {
+                spec_id,
+                origin,
+                rotation,
+            } => {
+                let id = try_place_building(grid, spec_id, origin, rotation)?;
+                (id, Change::Added { id, undone: None })
+            }
+            Command::Remove { id } => {
+                let building = grid.take(id).ok_or(CommandError::NoSuchBuilding(id))?;
+                (id, Change::Removed { building })
+            }
+            Command::Rotate { id, rotation } => {
+                let from = grid
+                    .instances
+                    .get(&id)
+                    .ok_or(CommandError::NoSuchBuilding(id))?
+                    .rotation;
+                set_rotation(grid, id, rotation)?;
+                (
+                    id,
+                    Change::Rotated {
+                        id,
+                        from,
+                        to: rotation,
+                    },
+                )
+            }
+        };
+        match &mut self.group {
+            Some(changes) => changes.push(change),
+            None => self.push_step(vec![change]),
+        }
+        Ok(id)
+    }
+
+    /// Apply several commands as a single undo step. Commands that fail are
+    /// skipped; their errors come back in order.
+    pub fn apply_all<I>(
+        &mut self,
+        grid: &mut TileGrid,
+        cmds: I,
+    ) -> Vec<Result<InstanceId, CommandError>>
+    where
+        I: IntoIterator<Item = Command>,
+    {
+        let grouped = self.group.is_some();
+        self.begin_group();
+        let results = cmds.into_iter().map(|c| self.apply(grid, c)).collect();
+        if !grouped {
+            self.end_group();
+        }
+        results
+    }
+
+    /// Revert the latest step. `Ok(false)` if there was nothing to undo.
+    /// If the grid no longer allows it the step stays put and nothing changes.
+    pub fn undo(&mut self, grid: &mut TileGrid) -> Result<bool, CommandError> {
+        self.end_group();
+        let Some(mut step) = self.undo.pop() else {
+            return Ok(false);
+        };
+        for i in (0..step.len()).rev() {
+            if let Err(e) = step[i].undo(grid) {
+                for change in &mut step[i + 1..] {
+                    let _ = change.redo(grid);
+                }
+                self.undo.push(step);
+                return Err(e);
+            }
+        }
+        self.redo.push(step);
+        Ok(true)
+    }
+
+    /// Re-apply the latest undone step. `Ok(false)` if there was none.
+    pub fn redo(&mut self, grid: &mut TileGrid) -> Result<bool, CommandError> {
+        self.end_group();
+        let Some(mut step) = self.redo.pop() else {
+            return Ok(false);
+        };
+        for i in 0..step.len() {
+            if let Err(e) = step[i].redo(grid) {
+                for change in step[..i].iter_mut().rev() {
+                    let _ = change.undo(grid);
+                }
+                self.redo.push(step);
+                return Err(e);
+            }
+        }
+        self.undo.push(step);
+        Ok(true)
+    }
+
+    fn push_step(&mut self, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(changes);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+}
@@ -1,19 +1,25 @@
 //! game_logic: processes inputs, game rules, and AI.
-//! Depends on `game_core` only. It exposes an `InputFrame` and `update_world`.
+//! Depends on `game_core` only. It exposes an `InputFrame`, `update_world`
+//! and the fixed-timestep `Simulation` that drives it.
 
-use game_core::{EntityType, World};
+use game_core::{tile_at, EntityType, LabEvent, MachineEvent, TileGrid, World};
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+use crate::flowfield::FlowField;
+use crate::pathfinding::Pathfinder;
 
 /// `InputFrame` is the platform-agnostic input snapshot.
 /// The platform layer (`game_app`) fills this each frame and passes to logic.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct InputFrame {
     /// Movement direction [-1.0, 1.0] on X
     pub move_x: f32,
     /// Movement direction [-1.0, 1.0] on Y
     pub move_y: f32,
-    /// Whether the primary action (e.g., fire) was pressed this frame
+    /// Whether the primary action (fire toward `pointer`) was pressed this frame
     pub action: bool,
-    /// Optional pointer/touch position in world / screen coords
+    /// Optional pointer/touch position in world coords
     pub pointer: Option<(f32, f32)>,
 }
 
@@ -28,51 +34,200 @@ impl Default for InputFrame {
     }
 }
 
-/// Update the world based on the `input` and a timestep `dt`.
+/// How enemies find their way, kept between ticks. A flow field toward the
+/// player covers the chunks around them; enemies farther out use cached A*
+/// paths.
+#[derive(Default)]
+pub struct Navigation {
+    pub flow: Option<FlowField>,
+    pub paths: Pathfinder,
+}
+
+impl Navigation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aim the flow field at world point (x, y) and bring it up to date
+    /// with the grid.
+    pub fn track(&mut self, grid: &TileGrid, x: f32, y: f32) {
+        let goal = tile_at(x, y);
+        let area = FlowField::area_around(grid, goal);
+        let flow = match &mut self.flow {
+            Some(flow) if flow.area() == area => {
+                flow.set_goals(&[goal]);
+                flow
+            }
+            slot => slot.insert(FlowField::new(area, &[goal])),
+        };
+        flow.update(grid);
+    }
+
+    /// Unit direction an entity at `from` should walk to reach `to`, or
+    /// `None` to head straight for it.
+    pub fn direction(
+        &mut self,
+        grid: &TileGrid,
+        from: (f32, f32),
+        to: (f32, f32),
+    ) -> Option<(f32, f32)> {
+        let covered = |f: &&FlowField| f.area().contains(tile_at(from.0, from.1));
+        if let Some(flow) = self.flow.as_ref().filter(covered) {
+            return flow.sample(from.0, from.1);
+        }
+        let waypoint = self.paths.waypoint(grid, from, to);
+        (waypoint != to).then(|| unit(from, waypoint))
+    }
+}
+
+fn unit(from: (f32, f32), to: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let dist = (dx * dx + dy * dy).sqrt().max(0.001);
+    (dx / dist, dy / dist)
+}
+
+/// Advance the world by one simulation tick of `dt` seconds based on `input`.
+/// Platform code should go through `simulation::Simulation`, which calls this
+/// with a fixed `dt`.
 ///
 /// - moves player by setting its velocity from input
-/// - updates enemy behavior (very simple: move toward player)
+/// - fires a projectile from the player toward the pointer on action
+/// - steers enemies toward the player around buildings, using the flow
+///   field and paths in `nav`
+/// - resolves hits, damage and deaths
+/// - advances the factory (power, drills, belts, inserters, fluids,
+///   machines, labs) by one tick
+///
+/// Returns everything that happened this tick, including buildings placed
+/// or removed since the previous one.
 ///
 /// Note: This function does not render or call Macroquad.
-pub fn update_world(world: &mut World, input: &InputFrame, dt: f32) {
+pub fn update_world(
+    world: &mut World,
+    nav: &mut Navigation,
+    input: &InputFrame,
+    dt: f32,
+) -> Vec<GameEvent> {
     const PLAYER_SPEED: f32 = 180.0;
     const ENEMY_SPEED: f32 = 80.0;
+    let mut events: Vec<GameEvent> = world
+        .grid
+        .take_building_changes()
+        .into_iter()
+        .map(GameEvent::from)
+        .collect();
 
     // Apply player input by setting velocity on the player entity.
     if let Some(player) = world.find_player_mut() {
         player.velocity.vx = input.move_x * PLAYER_SPEED;
         player.velocity.vy = input.move_y * PLAYER_SPEED;
+        let shooter = player.id;
 
-        // Example: if action pressed, do something. Here we just print for headless logs.
-        if input.action {
-            // In a real game we'd spawn bullets or trigger actions.
-            // Keep pure: don't call platform logging here.
-            // You could return an event enum from this function if needed.
+        if let (true, Some((x, y))) = (input.action, input.pointer) {
+            if let Some(projectile) = world.fire_projectile(shooter, x, y) {
+                events.push(GameEvent::ProjectileFired {
+                    projectile,
+                    by: shooter,
+                });
+            }
         }
     }
 
-    // Simple enemy AI: move toward player
+    // Enemy AI: walk toward the player, around buildings if they block us
     if let Some(player_pos) = world.find_player().map(|p| (p.transform.x, p.transform.y)) {
-        for e in &mut world.entities {
-            if e.ty == EntityType::Enemy {
-                let dx = player_pos.0 - e.transform.x;
-                let dy = player_pos.1 - e.transform.y;
-                let dist = (dx * dx + dy * dy).sqrt().max(0.001);
-                let nx = dx / dist;
-                let ny = dy / dist;
-                e.velocity.vx = nx * ENEMY_SPEED;
-                e.velocity.vy = ny * ENEMY_SPEED;
-            }
+        let pathing = world.collision.blocked_by_buildings(EntityType::Enemy);
+        if pathing {
+            nav.track(&world.grid, player_pos.0, player_pos.1);
+        }
+        let steering: Vec<(usize, (f32, f32))> = world
+            .entities()
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.ty == EntityType::Enemy)
+            .map(|(slot, e)| {
+                let from = (e.transform.x, e.transform.y);
+                let dir = if pathing {
+                    nav.direction(&world.grid, from, player_pos)
+                } else {
+                    None
+                };
+                (slot, dir.unwrap_or_else(|| unit(from, player_pos)))
+            })
+            .collect();
+        let entities = world.entities_mut();
+        for (slot, (nx, ny)) in steering {
+            entities[slot].velocity.vx = nx * ENEMY_SPEED;
+            entities[slot].velocity.vy = ny * ENEMY_SPEED;
         }
     }
 
     // Integrate physics for positions (game_core provides deterministic integration).
     world.update_physics(dt);
+    events.extend(world.tick_combat(dt).into_iter().map(GameEvent::from));
+
+    // Factory simulation runs in whole ticks.
+    tick_factory(world, &mut events);
+    events
+}
+
+/// Advance the factory by one tick, reporting what it did.
+fn tick_factory(world: &mut World, events: &mut Vec<GameEvent>) {
+    let grid = &mut world.grid;
+    for network in grid.tick_power() {
+        if let Some(n) = grid.power().network(network) {
+            events.push(GameEvent::PowerShortage {
+                network,
+                production: n.production,
+                demand: n.demand,
+            });
+        }
+    }
+    for (by, item) in grid.tick_drills() {
+        events.push(GameEvent::ItemProduced { by, item, count: 1 });
+    }
+    // Buildings without storage refuse belt items, so those wait at the end
+    // of the belt.
+    grid.tick_belts(|_, _| false);
+    grid.tick_inserters();
+    grid.tick_fluids();
+    for event in grid.tick_machines() {
+        match event {
+            MachineEvent::Started { id, recipe } => {
+                events.push(GameEvent::MachineStarted { id, recipe });
+            }
+            MachineEvent::Finished { id, recipe } => {
+                events.push(GameEvent::MachineFinished { id, recipe });
+                let outputs = grid.recipes().get(recipe).map(|r| r.outputs.clone());
+                for s in outputs.into_iter().flatten() {
+                    events.push(GameEvent::ItemProduced {
+                        by: id,
+                        item: s.item,
+                        count: s.count,
+                    });
+                }
+            }
+        }
+    }
+    for event in grid.tick_labs() {
+        events.push(match event {
+            LabEvent::Started { id, tech } => GameEvent::LabStarted { id, tech },
+            LabEvent::Researched { tech } => GameEvent::ResearchFinished { tech },
+        });
+    }
 }
 
 /// Optional: an abstract drawing trait that UI/app can implement if desired.
 /// game_logic can provide high-level debug draw calls using this trait (optional).
+pub mod camera;
+pub mod events;
+pub mod flowfield;
+pub mod headless;
+pub mod history;
+pub mod pathfinding;
 pub mod placement;
+pub mod replay;
+pub mod simulation;
+pub mod stats;
 
 pub trait DrawBackend {
     fn draw_circle(&mut self, x: f32, y: f32, radius: f32, rgba: (f32, f32, f32, f32));
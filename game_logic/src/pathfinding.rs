@@ -0,0 +1,169 @@
+//! A* over grid tiles, so enemies walk around buildings instead of into them.
+//!
+//! A tile is walkable when it is inside the grid bounds and no building
+//! covers it. Paths move to the eight neighbouring tiles; a diagonal step is
+//! only taken when both tiles beside it are walkable, so paths never cut a
+//! building's corner. Ties are broken by tile position, which makes every
+//! search deterministic.
+//!
+//! `Pathfinder` caches paths by start and goal tile and forgets them all
+//! when `TileGrid::layout_revision` changes. A cached path is exactly what a
+//! fresh search would return, so caching never changes where enemies go and
+//! replays stay exact.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use game_core::{tile_at, TileGrid, TilePos, TILE_SIZE};
+
+/// Tiles a single search may expand before giving up on the goal.
+pub const MAX_SEARCH_NODES: usize = 4096;
+
+/// Cached paths kept before the cache is emptied and starts over.
+pub const PATH_CACHE_LIMIT: usize = 1024;
+
+pub(crate) const STRAIGHT_COST: u32 = 10;
+pub(crate) const DIAGONAL_COST: u32 = 14;
+
+pub(crate) const NEIGHBOURS: [(i32, i32); 8] = [
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+    (1, -1),
+];
+
+/// Whether enemies can walk over `pos`.
+pub fn walkable(grid: &TileGrid, pos: TilePos) -> bool {
+    grid.in_bounds(pos) && grid.tile_occupant(pos).is_none()
+}
+
+/// World coordinates of the centre of `pos`.
+pub fn tile_center(pos: TilePos) -> (f32, f32) {
+    (
+        (pos.x as f32 + 0.5) * TILE_SIZE,
+        (pos.y as f32 + 0.5) * TILE_SIZE,
+    )
+}
+
+pub(crate) fn offset(pos: TilePos, dx: i32, dy: i32) -> TilePos {
+    TilePos {
+        x: pos.x + dx,
+        y: pos.y + dy,
+    }
+}
+
+/// Octile distance, admissible for the costs above.
+fn estimate(a: TilePos, b: TilePos) -> u32 {
+    let (dx, dy) = ((a.x - b.x).unsigned_abs(), (a.y - b.y).unsigned_abs());
+    STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+/// Shortest walkable path from `start` to `goal`, both included. The start
+/// tile itself need not be walkable. `None` if the goal is blocked or was not
+/// reached within `MAX_SEARCH_NODES` expanded tiles.
+pub fn find_path(grid: &TileGrid, start: TilePos, goal: TilePos) -> Option<Vec<TilePos>> {
+    if start == goal {
+        return Some(vec![start]);
+    }
+    if !walkable(grid, goal) {
+        return None;
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut cost: HashMap<TilePos, u32> = HashMap::new();
+    let mut came_from: HashMap<TilePos, TilePos> = HashMap::new();
+    cost.insert(start, 0);
+    open.push(Reverse((estimate(start, goal), 0, start)));
+
+    let mut expanded = 0;
+    while let Some(Reverse((_, g, pos))) = open.pop() {
+        if cost.get(&pos).is_some_and(|&best| best < g) {
+            continue; // stale entry
+        }
+        if pos == goal {
+            let mut path = vec![goal];
+            let mut at = goal;
+            while let Some(&prev) = came_from.get(&at) {
+                path.push(prev);
+                at = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        expanded += 1;
+        if expanded > MAX_SEARCH_NODES {
+            return None;
+        }
+        for (dx, dy) in NEIGHBOURS {
+            let next = offset(pos, dx, dy);
+            if !walkable(grid, next) {
+                continue;
+            }
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal
+                && !(walkable(grid, offset(pos, dx, 0)) && walkable(grid, offset(pos, 0, dy)))
+            {
+                continue;
+            }
+            let step = if diagonal {
+                DIAGONAL_COST
+            } else {
+                STRAIGHT_COST
+            };
+            let g_next = g + step;
+            if cost.get(&next).is_some_and(|&best| best <= g_next) {
+                continue;
+            }
+            cost.insert(next, g_next);
+            came_from.insert(next, pos);
+            open.push(Reverse((g_next + estimate(next, goal), g_next, next)));
+        }
+    }
+    None
+}
+
+/// Path cache shared by every enemy.
+#[derive(Default)]
+pub struct Pathfinder {
+    /// `layout_revision` the cached paths were planned against
+    revision: Option<u64>,
+    paths: HashMap<(TilePos, TilePos), Option<Vec<TilePos>>>,
+}
+
+impl Pathfinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of start/goal pairs currently cached.
+    pub fn cached(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// `find_path`, reusing earlier results while the grid layout is unchanged.
+    pub fn path(&mut self, grid: &TileGrid, start: TilePos, goal: TilePos) -> Option<&[TilePos]> {
+        if self.revision != Some(grid.layout_revision()) || self.paths.len() >= PATH_CACHE_LIMIT {
+            self.paths.clear();
+            self.revision = Some(grid.layout_revision());
+        }
+        self.paths
+            .entry((start, goal))
+            .or_insert_with(|| find_path(grid, start, goal))
+            .as_deref()
+    }
+
+    /// The point an entity at `from` should head for to reach `to`: the
+    /// centre of the next tile on the path, or `to` itself once in the goal
+    /// tile or when no path exists.
+    pub fn waypoint(&mut self, grid: &TileGrid, from: (f32, f32), to: (f32, f32)) -> (f32, f32) {
+        let (start, goal) = (tile_at(from.0, from.1), tile_at(to.0, to.1));
+        match self.path(grid, start, goal) {
+            Some([_, next, ..]) => tile_center(*next),
+            _ => to,
+        }
+    }
+}
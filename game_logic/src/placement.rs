@@ -1,25 +1,126 @@
-use game_core::{BuildingSpec, InstanceId, Rotation, TileGrid, TilePos};
+use game_core::{
+    DrillStatus, InstanceId, ItemId, ItemRegistry, MachineError, MachineStatus, PlacementError,
+    RecipeId, Rotation, SpecId, SpecRegistry, TerrainTile, TileGrid, TilePos, TileRect,
+    BELT_TILE_LEN, FULL_SATISFACTION,
+};
 
+/// Place without recording anything; edits the player should be able to undo
+/// go through `history::History` instead. Specs that are not researched yet
+/// are refused.
 pub fn try_place_building(
     grid: &mut TileGrid,
-    spec: &BuildingSpec,
+    spec_id: SpecId,
     origin: TilePos,
     rot: Rotation,
-) -> Result<InstanceId, game_core::PlacementError> {
-    grid.place(spec, origin, rot)
+) -> Result<InstanceId, PlacementError> {
+    if grid.specs().get(spec_id).is_some() && !grid.is_spec_unlocked(spec_id) {
+        return Err(PlacementError::Locked(spec_id));
+    }
+    grid.place(spec_id, origin, rot)
+}
+
+/// Choose machine `id`'s recipe as the player would, refusing recipes that
+/// are not researched yet.
+pub fn try_set_recipe(
+    grid: &mut TileGrid,
+    id: InstanceId,
+    recipe: Option<RecipeId>,
+) -> Result<(), MachineError> {
+    if let Some(r) = recipe.filter(|&r| !grid.is_recipe_unlocked(r)) {
+        return Err(MachineError::Locked(r));
+    }
+    grid.set_recipe(id, recipe)
 }
 
 // A minimal snapshot type for the renderer
-pub struct TileGridSnapshot {
-    pub width: usize,
-    pub height: usize,
+pub struct TileGridSnapshot<'a> {
+    /// Region the snapshot covers; only buildings touching it are included
+    pub view: TileRect,
     pub instances: Vec<game_core::BuildingInstance>,
+    pub specs: &'a SpecRegistry,
+    pub items: &'a ItemRegistry,
+    /// Tiles in `view` that are not plain ground
+    pub terrain: Vec<(TilePos, TerrainTile)>,
+    /// Items on belts as (x, y, item) in tile units, measured from the grid origin
+    pub belt_items: Vec<(f32, f32, ItemId)>,
+    pub machines: Vec<MachineView>,
+    /// Drills that are not working normally, for status icons
+    pub stalled_drills: Vec<(InstanceId, DrillStatus)>,
+    /// Consumers whose network cannot fully meet demand
+    pub underpowered: Vec<InstanceId>,
+    /// Non-empty fluid boxes as (instance, fluid, fill fraction)
+    pub fluid_levels: Vec<(InstanceId, ItemId, f32)>,
 }
 
-pub fn grid_snapshot(grid: &TileGrid) -> TileGridSnapshot {
+/// Crafting state of one machine instance, for progress display.
+pub struct MachineView {
+    pub id: InstanceId,
+    pub status: MachineStatus,
+    /// Fraction of the current craft completed, 0.0..=1.0
+    pub progress: f32,
+}
+
+/// Capture what the renderer needs to draw the tiles in `view`.
+pub fn grid_snapshot(grid: &TileGrid, view: TileRect) -> TileGridSnapshot<'_> {
+    let visible = grid.instances_in(view);
     TileGridSnapshot {
-        width: grid.width,
-        height: grid.height,
-        instances: grid.instances.values().cloned().collect(),
+        view,
+        instances: visible
+            .iter()
+            .map(|id| grid.instances[id].clone())
+            .collect(),
+        specs: grid.specs(),
+        items: grid.items(),
+        terrain: grid.terrain_in(view).collect(),
+        belt_items: belt_item_positions(grid, &visible),
+        machines: visible
+            .iter()
+            .filter_map(|&id| {
+                grid.machine(id).map(|m| MachineView {
+                    id,
+                    status: m.status(),
+                    progress: m.progress(),
+                })
+            })
+            .collect(),
+        stalled_drills: visible
+            .iter()
+            .filter_map(|&id| grid.drill(id).map(|d| (id, d.status())))
+            .filter(|(_, status)| *status != DrillStatus::Working)
+            .collect(),
+        underpowered: visible
+            .iter()
+            .copied()
+            .filter(|&id| {
+                grid.power_satisfaction(id)
+                    .is_some_and(|s| s < FULL_SATISFACTION)
+            })
+            .collect(),
+        fluid_levels: visible
+            .iter()
+            .filter_map(|&id| {
+                let b = grid.fluid_box(id)?;
+                let fluid = b.fluid()?;
+                Some((id, fluid, b.amount() as f32 / b.capacity.max(1) as f32))
+            })
+            .collect(),
+    }
+}
+
+fn belt_item_positions(grid: &TileGrid, ids: &[InstanceId]) -> Vec<(f32, f32, ItemId)> {
+    let mut out = Vec::new();
+    for inst in ids.iter().map(|id| &grid.instances[id]) {
+        let Some(belt) = grid.belt(inst.id) else {
+            continue;
+        };
+        let (dx, dy) = inst.rotation.offset();
+        let cx = inst.origin.x as f32 + 0.5;
+        let cy = inst.origin.y as f32 + 0.5;
+        for it in belt.items() {
+            // 0 is the entry edge, BELT_TILE_LEN the exit edge
+            let t = it.pos as f32 / BELT_TILE_LEN as f32 - 0.5;
+            out.push((cx + dx as f32 * t, cy + dy as f32 * t, it.item));
+        }
     }
+    out
 }
@@ -0,0 +1,228 @@
+//! Input recording and deterministic replay.
+//!
+//! A `Replay` is the world as it was when recording started plus the
+//! `InputFrame` fed to every tick after that. Every `checkpoint_interval`
+//! ticks the recorder also stores `World::checksum`, so playback can tell
+//! where it stopped matching the original run. On a mismatch, playback runs
+//! the failing interval again from a reloaded copy of the last matching
+//! state and compares it with the uninterrupted run tick by tick. State
+//! that a save does not capture shows up as the first tick they disagree.
+
+use std::fmt;
+
+use game_core::World;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Simulation;
+use crate::InputFrame;
+
+/// Version written by `Replay::to_bytes`.
+pub const REPLAY_VERSION: u32 = 3;
+
+/// First bytes of every replay file.
+pub const REPLAY_MAGIC: [u8; 4] = *b"FGRP";
+
+/// Default ticks between stored checksums.
+pub const CHECKPOINT_INTERVAL: u64 = 60;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The replay could not be serialized
+    Encode(String),
+    /// The data could not be decoded
+    Decode(String),
+    /// Data that does not start with `REPLAY_MAGIC`
+    NotAReplay,
+    /// Written by a format version this build cannot read
+    UnsupportedVersion(u32),
+    /// The initial world inside the replay failed to load
+    InitialState(game_core::SaveError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Encode(msg) => write!(f, "failed to encode replay: {}", msg),
+            ReplayError::Decode(msg) => write!(f, "failed to decode replay: {}", msg),
+            ReplayError::NotAReplay => write!(f, "data is not a replay file"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay version {}", v),
+            ReplayError::InitialState(e) => write!(f, "bad initial state: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub tick_rate: u32,
+    pub checkpoint_interval: u64,
+    /// `World::save_binary` of the world before the first recorded tick
+    pub initial_state: Vec<u8>,
+    /// Input for each tick; index 0 is tick 1
+    pub inputs: Vec<InputFrame>,
+    /// `(tick, World::checksum())` after every `checkpoint_interval` ticks
+    pub checkpoints: Vec<(u64, u64)>,
+}
+
+/// Result of playing a replay back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayOutcome {
+    /// Every checkpoint matched; `checksum` is the final state's
+    Matched { ticks: u64, checksum: u64 },
+    /// The state at `checkpoint` differs from the recording, which still
+    /// matched at `last_matching`. `tick` is the first tick in between where
+    /// a run resumed from the `last_matching` state disagrees with the
+    /// uninterrupted one, or `checkpoint` if they agree throughout.
+    Diverged {
+        tick: u64,
+        checkpoint: u64,
+        last_matching: Option<u64>,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ReplayError> {
+        let body = bincode::serialize(self).map_err(|e| ReplayError::Encode(e.to_string()))?;
+        let mut out = Vec::with_capacity(8 + body.len());
+        out.extend_from_slice(&REPLAY_MAGIC);
+        out.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        out.extend_from_slice(&body);
+        Ok(out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        if bytes.len() < 8 || bytes[..4] != REPLAY_MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        bincode::deserialize(&bytes[8..]).map_err(|e| ReplayError::Decode(e.to_string()))
+    }
+
+    /// Run the recorded inputs headlessly from the initial state, checking
+    /// each checkpoint on the way.
+    pub fn play(&self) -> Result<ReplayOutcome, ReplayError> {
+        let mut sim = self.start()?;
+        let mut checkpoints = self.checkpoints.iter().peekable();
+        let mut last_matching = None;
+        for input in &self.inputs {
+            step(&mut sim, input);
+            let tick = sim.tick();
+            if let Some(&&(at, expected)) = checkpoints.peek() {
+                if at == tick {
+                    checkpoints.next();
+                    let actual = sim.world.checksum();
+                    if actual != expected {
+                        return self.find_divergence(last_matching, tick, expected, actual);
+                    }
+                    last_matching = Some(tick);
+                }
+            }
+        }
+        Ok(ReplayOutcome::Matched {
+            ticks: sim.tick(),
+            checksum: sim.world.checksum(),
+        })
+    }
+
+    /// Re-run to the last matching checkpoint, then play the interval up to
+    /// the failing `checkpoint` twice: on from there, and from a reloaded
+    /// save of that state. The first tick the two disagree is reported.
+    fn find_divergence(
+        &self,
+        last_matching: Option<u64>,
+        checkpoint: u64,
+        expected: u64,
+        actual: u64,
+    ) -> Result<ReplayOutcome, ReplayError> {
+        let verified = last_matching.unwrap_or(0) as usize;
+        let mut uninterrupted = self.start()?;
+        for input in &self.inputs[..verified] {
+            step(&mut uninterrupted, input);
+        }
+        let snapshot = uninterrupted
+            .world
+            .save_binary()
+            .map_err(|e| ReplayError::Encode(e.to_string()))?;
+        let world = World::load_binary(&snapshot).map_err(ReplayError::InitialState)?;
+        let mut resumed = Simulation::with_tick_rate(world, self.tick_rate);
+        let mut tick = checkpoint;
+        for input in &self.inputs[verified..checkpoint as usize] {
+            step(&mut uninterrupted, input);
+            step(&mut resumed, input);
+            if uninterrupted.world.checksum() != resumed.world.checksum() {
+                tick = uninterrupted.tick();
+                break;
+            }
+        }
+        Ok(ReplayOutcome::Diverged {
+            tick,
+            checkpoint,
+            last_matching,
+            expected,
+            actual,
+        })
+    }
+
+    fn start(&self) -> Result<Simulation, ReplayError> {
+        let world = World::load_binary(&self.initial_state).map_err(ReplayError::InitialState)?;
+        Ok(Simulation::with_tick_rate(world, self.tick_rate))
+    }
+}
+
+fn step(sim: &mut Simulation, input: &InputFrame) {
+    sim.step(input);
+    // nobody reads the events of a replay
+    sim.take_events();
+}
+
+/// Collects a `Replay` while a `Simulation` runs.
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    /// Start from `world` as it is now, before the first recorded tick.
+    pub fn new(
+        world: &World,
+        tick_rate: u32,
+        checkpoint_interval: u64,
+    ) -> Result<Self, ReplayError> {
+        let initial_state = world
+            .save_binary()
+            .map_err(|e| ReplayError::Encode(e.to_string()))?;
+        Ok(Self {
+            replay: Replay {
+                tick_rate,
+                checkpoint_interval: checkpoint_interval.max(1),
+                initial_state,
+                inputs: Vec::new(),
+                checkpoints: Vec::new(),
+            },
+        })
+    }
+
+    /// Note the input of a tick that just ran and the world it produced.
+    pub fn record(&mut self, input: &InputFrame, world: &World) {
+        self.replay.inputs.push(input.clone());
+        let tick = self.replay.inputs.len() as u64;
+        if tick.is_multiple_of(self.replay.checkpoint_interval) {
+            self.replay.checkpoints.push((tick, world.checksum()));
+        }
+    }
+
+    /// Close the recording. The final state is always checkpointed so
+    /// playback verifies the very end too.
+    pub fn finish(mut self, world: &World) -> Replay {
+        let tick = self.replay.inputs.len() as u64;
+        if tick > 0 && self.replay.checkpoints.last().map(|c| c.0) != Some(tick) {
+            self.replay.checkpoints.push((tick, world.checksum()));
+        }
+        self.replay
+    }
+}
@@ -0,0 +1,197 @@
+//! Fixed-timestep driver. The platform layer reports how much real time
+//! passed each frame; `Simulation` turns that into whole ticks of
+//! `update_world`, so results depend only on the tick count and inputs, never
+//! on the frame rate.
+
+use std::collections::BTreeMap;
+
+use game_core::{Entity, EntityId, World};
+
+use crate::events::GameEvent;
+use crate::replay::{Replay, ReplayError, ReplayRecorder};
+use crate::stats::ProductionStats;
+use crate::{update_world, InputFrame, Navigation};
+
+/// Default simulation ticks per second.
+pub const TICK_RATE: u32 = 60;
+
+/// Default limit on ticks run for a single frame. Time beyond this is
+/// dropped so a slow frame cannot trigger an ever-growing backlog.
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Frame times are `f32`; this much slack keeps e.g. two 1/120 s frames from
+/// falling a rounding error short of one 1/60 s tick.
+const TIME_EPSILON: f64 = 1e-6;
+
+/// Most events kept for `take_events`; older ones are dropped when nobody
+/// collects them.
+pub const MAX_PENDING_EVENTS: usize = 4096;
+
+pub struct Simulation {
+    pub world: World,
+    tick_rate: u32,
+    max_ticks_per_frame: u32,
+    /// Real time not yet consumed by ticks, in seconds
+    accumulator: f64,
+    /// Ticks run since creation
+    tick: u64,
+    /// Set by an action press and held until a tick consumes it
+    pending_action: bool,
+    /// Entity positions before the most recent tick, for interpolation
+    previous: BTreeMap<EntityId, (f32, f32)>,
+    recorder: Option<ReplayRecorder>,
+    /// Events from ticks run since the last `take_events`
+    events: Vec<GameEvent>,
+    nav: Navigation,
+    stats: ProductionStats,
+}
+
+impl Simulation {
+    pub fn new(world: World) -> Self {
+        Self::with_tick_rate(world, TICK_RATE)
+    }
+
+    pub fn with_tick_rate(world: World, tick_rate: u32) -> Self {
+        let previous = positions(&world);
+        Self {
+            world,
+            tick_rate: tick_rate.max(1),
+            max_ticks_per_frame: MAX_TICKS_PER_FRAME,
+            accumulator: 0.0,
+            tick: 0,
+            pending_action: false,
+            previous,
+            recorder: None,
+            events: Vec::new(),
+            nav: Navigation::new(),
+            stats: ProductionStats::new(tick_rate),
+        }
+    }
+
+    pub fn set_max_ticks_per_frame(&mut self, max: u32) {
+        self.max_ticks_per_frame = max.max(1);
+    }
+
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    /// Seconds of game time in one tick.
+    pub fn tick_dt(&self) -> f32 {
+        1.0 / self.tick_rate as f32
+    }
+
+    /// Ticks run since the simulation was created.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// How far real time has progressed past the last tick, as a fraction
+    /// of a tick (0.0..1.0). Renderers blend previous and current state by it.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator * self.tick_rate as f64).clamp(0.0, 1.0) as f32
+    }
+
+    /// Account for `frame_dt` seconds of real time and run however many
+    /// ticks are due, at most the per-frame cap. Returns the ticks run.
+    pub fn advance(&mut self, frame_dt: f32, input: &InputFrame) -> u32 {
+        self.pending_action |= input.action;
+        self.accumulator += frame_dt.max(0.0) as f64;
+        let tick_secs = 1.0 / self.tick_rate as f64;
+
+        let mut ran = 0;
+        while self.accumulator + TIME_EPSILON >= tick_secs && ran < self.max_ticks_per_frame {
+            self.accumulator = (self.accumulator - tick_secs).max(0.0);
+            let mut tick_input = input.clone();
+            tick_input.action = std::mem::take(&mut self.pending_action);
+            self.step(&tick_input);
+            ran += 1;
+        }
+        if self.accumulator >= tick_secs {
+            // over the cap: give up on the backlog rather than chase it
+            self.accumulator %= tick_secs;
+        }
+        ran
+    }
+
+    /// Run exactly one tick with `input`, ignoring real time.
+    pub fn step(&mut self, input: &InputFrame) {
+        self.previous = positions(&self.world);
+        let dt = self.tick_dt();
+        let events = update_world(&mut self.world, &mut self.nav, input, dt);
+        self.stats.record(self.tick, &events, &self.world.grid);
+        self.tick += 1;
+        self.events.extend(events);
+        if self.events.len() > MAX_PENDING_EVENTS {
+            let excess = self.events.len() - MAX_PENDING_EVENTS;
+            self.events.drain(..excess);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(input, &self.world);
+        }
+    }
+
+    /// Production and activity statistics for the ticks run so far.
+    pub fn stats(&self) -> &ProductionStats {
+        &self.stats
+    }
+
+    /// Events produced by the ticks run since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Record every following tick's input, checksumming the world every
+    /// `checkpoint_interval` ticks. Restarts any recording in progress.
+    pub fn start_recording(&mut self, checkpoint_interval: u64) -> Result<(), ReplayError> {
+        self.recorder = Some(ReplayRecorder::new(
+            &self.world,
+            self.tick_rate,
+            checkpoint_interval,
+        )?);
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// End the recording, if any, and return it.
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        let recorder = self.recorder.take()?;
+        Some(recorder.finish(&self.world))
+    }
+
+    /// Position of entity `id` blended between the last two ticks by `alpha`.
+    /// Drawing every entity should use `interpolated_entities` instead.
+    pub fn interpolated_position(&self, id: EntityId) -> Option<(f32, f32)> {
+        let e = self.world.entities().iter().find(|e| e.id == id)?;
+        Some(self.blend(e, self.alpha()))
+    }
+
+    /// Every entity with its position blended between the last two ticks.
+    pub fn interpolated_entities(&self) -> impl Iterator<Item = (&Entity, (f32, f32))> {
+        let a = self.alpha();
+        self.world
+            .entities()
+            .iter()
+            .map(move |e| (e, self.blend(e, a)))
+    }
+
+    fn blend(&self, e: &Entity, a: f32) -> (f32, f32) {
+        let (x, y) = (e.transform.x, e.transform.y);
+        match self.previous.get(&e.id) {
+            Some(&(px, py)) => (px + (x - px) * a, py + (y - py) * a),
+            // spawned during the last tick
+            None => (x, y),
+        }
+    }
+}
+
+fn positions(world: &World) -> BTreeMap<EntityId, (f32, f32)> {
+    world
+        .entities()
+        .iter()
+        .map(|e| (e.id, (e.transform.x, e.transform.y)))
+        .collect()
+}
@@ -0,0 +1,379 @@
+//! Production statistics: how much of each item the factory makes and uses,
+//! and how much of the time each building spends working.
+//!
+//! Every tick `Simulation` hands `ProductionStats` the tick's events and the
+//! grid. Items produced come from `ItemProduced` events; items consumed are
+//! the ingredients of each craft finished and the science of each research
+//! unit started. Each machine, drill and lab is
+//! sampled as working, idle, blocked or unpowered.
+//!
+//! Totals are kept for the last minute, ten minutes and hour. Each window is
+//! split into `WINDOW_BUCKETS` buckets and drops its oldest bucket as time
+//! moves on, so a window covers its full length minus at most one bucket.
+//! The stats live outside `World`: they are never saved and do not affect
+//! checksums.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::AddAssign;
+
+use game_core::{DrillStatus, InstanceId, ItemId, MachineStatus, SpecId, TileGrid};
+
+use crate::events::GameEvent;
+
+/// Buckets each rolling window is divided into.
+pub const WINDOW_BUCKETS: usize = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Window {
+    Minute,
+    TenMinutes,
+    Hour,
+}
+
+impl Window {
+    pub const ALL: [Window; 3] = [Window::Minute, Window::TenMinutes, Window::Hour];
+
+    pub fn seconds(self) -> u64 {
+        match self {
+            Window::Minute => 60,
+            Window::TenMinutes => 600,
+            Window::Hour => 3600,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Units of one item made and used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flow {
+    pub produced: u64,
+    pub consumed: u64,
+}
+
+impl Flow {
+    /// Produced minus consumed.
+    pub fn net(&self) -> i64 {
+        self.produced as i64 - self.consumed as i64
+    }
+}
+
+impl AddAssign for Flow {
+    fn add_assign(&mut self, other: Flow) {
+        self.produced += other.produced;
+        self.consumed += other.consumed;
+    }
+}
+
+/// What a building was doing during one tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivityState {
+    /// Crafting, mining or researching
+    Working,
+    /// No recipe, missing ingredients, nothing left to mine or nothing to
+    /// research
+    Idle,
+    /// Finished output has nowhere to go
+    Blocked,
+    /// Its power network delivers nothing
+    Unpowered,
+}
+
+/// Ticks a building spent in each `ActivityState`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Activity {
+    pub working: u64,
+    pub idle: u64,
+    pub blocked: u64,
+    pub unpowered: u64,
+}
+
+impl Activity {
+    pub fn ticks(&self) -> u64 {
+        self.working + self.idle + self.blocked + self.unpowered
+    }
+
+    /// Fraction of the sampled ticks spent working, 0.0..=1.0.
+    pub fn uptime(&self) -> f32 {
+        self.fraction(self.working)
+    }
+
+    pub fn idle_fraction(&self) -> f32 {
+        self.fraction(self.idle)
+    }
+
+    pub fn blocked_fraction(&self) -> f32 {
+        self.fraction(self.blocked)
+    }
+
+    pub fn unpowered_fraction(&self) -> f32 {
+        self.fraction(self.unpowered)
+    }
+
+    fn fraction(&self, n: u64) -> f32 {
+        match self.ticks() {
+            0 => 0.0,
+            total => n as f32 / total as f32,
+        }
+    }
+
+    fn count(&mut self, state: ActivityState) {
+        match state {
+            ActivityState::Working => self.working += 1,
+            ActivityState::Idle => self.idle += 1,
+            ActivityState::Blocked => self.blocked += 1,
+            ActivityState::Unpowered => self.unpowered += 1,
+        }
+    }
+}
+
+impl AddAssign for Activity {
+    fn add_assign(&mut self, other: Activity) {
+        self.working += other.working;
+        self.idle += other.idle;
+        self.blocked += other.blocked;
+        self.unpowered += other.unpowered;
+    }
+}
+
+struct Bucket<K, V> {
+    /// Ticks recorded into this bucket
+    ticks: u64,
+    values: BTreeMap<K, V>,
+}
+
+impl<K, V> Default for Bucket<K, V> {
+    fn default() -> Self {
+        Self {
+            ticks: 0,
+            values: BTreeMap::new(),
+        }
+    }
+}
+
+/// Per-key totals over a window, oldest bucket first.
+struct Rolling<K, V> {
+    bucket_ticks: u64,
+    /// `tick / bucket_ticks` of the newest bucket
+    newest: u64,
+    buckets: VecDeque<Bucket<K, V>>,
+}
+
+impl<K: Ord + Copy, V: Default + Copy + AddAssign> Rolling<K, V> {
+    fn new(window: Window, tick_rate: u32) -> Self {
+        let ticks = window.seconds() * tick_rate as u64;
+        Self {
+            bucket_ticks: (ticks / WINDOW_BUCKETS as u64).max(1),
+            newest: 0,
+            buckets: VecDeque::from([Bucket::default()]),
+        }
+    }
+
+    /// Start recording `tick`, dropping buckets that fell out of the window.
+    fn begin(&mut self, tick: u64) {
+        let n = tick / self.bucket_ticks;
+        if n >= self.newest + WINDOW_BUCKETS as u64 {
+            self.buckets.clear();
+            self.newest = n - 1;
+        }
+        while self.newest < n {
+            self.newest += 1;
+            self.buckets.push_back(Bucket::default());
+            if self.buckets.len() > WINDOW_BUCKETS {
+                self.buckets.pop_front();
+            }
+        }
+        if let Some(b) = self.buckets.back_mut() {
+            b.ticks += 1;
+        }
+    }
+
+    fn add(&mut self, key: K, f: impl FnOnce(&mut V)) {
+        if let Some(b) = self.buckets.back_mut() {
+            f(b.values.entry(key).or_default());
+        }
+    }
+
+    fn forget(&mut self, key: K) {
+        for b in &mut self.buckets {
+            b.values.remove(&key);
+        }
+    }
+
+    fn ticks(&self) -> u64 {
+        self.buckets.iter().map(|b| b.ticks).sum()
+    }
+
+    fn total(&self, key: K) -> V {
+        let mut sum = V::default();
+        for v in self.buckets.iter().filter_map(|b| b.values.get(&key)) {
+            sum += *v;
+        }
+        sum
+    }
+
+    fn totals(&self) -> BTreeMap<K, V> {
+        let mut sums: BTreeMap<K, V> = BTreeMap::new();
+        for b in &self.buckets {
+            for (&k, &v) in &b.values {
+                *sums.entry(k).or_default() += v;
+            }
+        }
+        sums
+    }
+}
+
+pub struct ProductionStats {
+    tick_rate: u32,
+    /// One per `Window`, in `Window::ALL` order
+    items: Vec<Rolling<ItemId, Flow>>,
+    activity: Vec<Rolling<InstanceId, Activity>>,
+}
+
+impl ProductionStats {
+    pub fn new(tick_rate: u32) -> Self {
+        let tick_rate = tick_rate.max(1);
+        Self {
+            tick_rate,
+            items: Window::ALL
+                .iter()
+                .map(|&w| Rolling::new(w, tick_rate))
+                .collect(),
+            activity: Window::ALL
+                .iter()
+                .map(|&w| Rolling::new(w, tick_rate))
+                .collect(),
+        }
+    }
+
+    /// Add the tick run after `tick` earlier ones, which reported `events`
+    /// and left the grid as `grid`.
+    pub fn record(&mut self, tick: u64, events: &[GameEvent], grid: &TileGrid) {
+        for w in &mut self.items {
+            w.begin(tick);
+        }
+        for w in &mut self.activity {
+            w.begin(tick);
+        }
+
+        for event in events {
+            match *event {
+                GameEvent::ItemProduced { item, count, .. } => {
+                    self.add_flow(item, |f| f.produced += count as u64);
+                }
+                // crafts cancelled by a recipe change refund their inputs, so
+                // they count as consumed only once the craft completes
+                GameEvent::MachineFinished { recipe, .. } => {
+                    let inputs = grid.recipes().get(recipe).map(|r| r.inputs.clone());
+                    for s in inputs.into_iter().flatten() {
+                        self.add_flow(s.item, |f| f.consumed += s.count as u64);
+                    }
+                }
+                GameEvent::LabStarted { tech, .. } => {
+                    let cost = grid.techs().get(tech).map(|t| t.cost.clone());
+                    for s in cost.into_iter().flatten() {
+                        self.add_flow(s.item, |f| f.consumed += s.count as u64);
+                    }
+                }
+                GameEvent::BuildingRemoved { id, .. } => {
+                    for w in &mut self.activity {
+                        w.forget(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let unpowered = |id| grid.power_satisfaction(id) == Some(0);
+        for (id, machine) in grid.machines() {
+            let state = match machine.status() {
+                _ if unpowered(id) => ActivityState::Unpowered,
+                MachineStatus::Crafting => ActivityState::Working,
+                MachineStatus::OutputBlocked => ActivityState::Blocked,
+                MachineStatus::Idle | MachineStatus::WaitingForInput => ActivityState::Idle,
+            };
+            self.count(id, state);
+        }
+        for (id, drill) in grid.drills() {
+            let state = match drill.status() {
+                _ if unpowered(id) => ActivityState::Unpowered,
+                DrillStatus::Working => ActivityState::Working,
+                DrillStatus::OutputBlocked => ActivityState::Blocked,
+                DrillStatus::Depleted => ActivityState::Idle,
+            };
+            self.count(id, state);
+        }
+        for (id, lab) in grid.labs() {
+            let state = match lab.is_working() {
+                _ if unpowered(id) => ActivityState::Unpowered,
+                true => ActivityState::Working,
+                false => ActivityState::Idle,
+            };
+            self.count(id, state);
+        }
+    }
+
+    fn add_flow(&mut self, item: ItemId, f: impl Fn(&mut Flow)) {
+        for w in &mut self.items {
+            w.add(item, &f);
+        }
+    }
+
+    fn count(&mut self, id: InstanceId, state: ActivityState) {
+        for w in &mut self.activity {
+            w.add(id, |a| a.count(state));
+        }
+    }
+
+    /// Ticks `window` currently covers; less than its full length early on.
+    pub fn window_ticks(&self, window: Window) -> u64 {
+        self.items[window.index()].ticks()
+    }
+
+    /// Units of `item` made and used during `window`.
+    pub fn flow(&self, item: ItemId, window: Window) -> Flow {
+        self.items[window.index()].total(item)
+    }
+
+    /// Every item made or used during `window`.
+    pub fn flows(&self, window: Window) -> BTreeMap<ItemId, Flow> {
+        self.items[window.index()].totals()
+    }
+
+    /// Average units of `item` produced and consumed per minute over
+    /// `window`.
+    pub fn per_minute(&self, item: ItemId, window: Window) -> (f32, f32) {
+        let minutes = self.window_ticks(window) as f32 / (self.tick_rate as f32 * 60.0);
+        if minutes <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let flow = self.flow(item, window);
+        (
+            flow.produced as f32 / minutes,
+            flow.consumed as f32 / minutes,
+        )
+    }
+
+    /// How building `id` spent `window`.
+    pub fn activity(&self, id: InstanceId, window: Window) -> Activity {
+        self.activity[window.index()].total(id)
+    }
+
+    /// Every machine, drill and lab sampled during `window`.
+    pub fn activities(&self, window: Window) -> BTreeMap<InstanceId, Activity> {
+        self.activity[window.index()].totals()
+    }
+
+    /// `activities` summed over the buildings of each spec on `grid`.
+    pub fn activity_by_spec(&self, grid: &TileGrid, window: Window) -> BTreeMap<SpecId, Activity> {
+        let mut by_spec: BTreeMap<SpecId, Activity> = BTreeMap::new();
+        for (id, activity) in self.activities(window) {
+            if let Some(inst) = grid.instances.get(&id) {
+                *by_spec.entry(inst.spec_id).or_default() += activity;
+            }
+        }
+        by_spec
+    }
+}
@@ -0,0 +1,108 @@
+use game_core::{TilePos, TileRect};
+use game_logic::camera::{Camera, MAX_ZOOM, MIN_ZOOM};
+
+fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+}
+
+#[test]
+fn new_camera_maps_screen_onto_world() {
+    let cam = Camera::new(800.0, 600.0);
+    assert!(close(cam.screen_to_world((0.0, 0.0)), (0.0, 0.0)));
+    assert!(close(cam.screen_to_world((123.0, 45.0)), (123.0, 45.0)));
+    assert_eq!(cam.screen_to_tile((70.0, 33.0)), TilePos { x: 2, y: 1 });
+}
+
+#[test]
+fn screen_and_world_round_trip() {
+    let mut cam = Camera::new(800.0, 600.0);
+    cam.target = (-250.0, 1000.0);
+    cam.zoom = 2.5;
+    for p in [(0.0, 0.0), (400.0, 300.0), (799.0, 12.0)] {
+        assert!(close(cam.world_to_screen(cam.screen_to_world(p)), p));
+    }
+    // the viewport centre shows the target
+    assert!(close(cam.screen_to_world((400.0, 300.0)), (-250.0, 1000.0)));
+    // one screen pixel is 1 / zoom world units
+    assert!(close(cam.screen_to_world((410.0, 300.0)), (-246.0, 1000.0)));
+}
+
+#[test]
+fn tiles_floor_toward_negative() {
+    let mut cam = Camera::new(100.0, 100.0);
+    cam.target = (0.0, 0.0);
+    assert_eq!(cam.screen_to_tile((49.0, 49.0)), TilePos { x: -1, y: -1 });
+    assert_eq!(cam.screen_to_tile((50.0, 50.0)), TilePos { x: 0, y: 0 });
+}
+
+#[test]
+fn visible_tiles_cover_the_viewport() {
+    let mut cam = Camera::new(320.0, 320.0);
+    assert_eq!(
+        cam.visible_tiles(),
+        TileRect {
+            min: TilePos { x: 0, y: 0 },
+            max: TilePos { x: 10, y: 10 },
+        }
+    );
+    cam.zoom = 2.0;
+    cam.target = (0.0, 0.0);
+    assert_eq!(
+        cam.visible_tiles(),
+        TileRect {
+            min: TilePos { x: -3, y: -3 },
+            max: TilePos { x: 2, y: 2 },
+        }
+    );
+}
+
+#[test]
+fn drag_moves_the_world_with_the_pointer() {
+    let mut cam = Camera::new(800.0, 600.0);
+    cam.zoom = 2.0;
+    let grabbed = cam.screen_to_world((100.0, 100.0));
+    cam.pan(30.0, -20.0);
+    assert!(close(cam.screen_to_world((130.0, 80.0)), grabbed));
+}
+
+#[test]
+fn zoom_keeps_the_point_under_the_cursor() {
+    let mut cam = Camera::new(800.0, 600.0);
+    let at = (650.0, 120.0);
+    let before = cam.screen_to_world(at);
+    cam.wheel(at, 3.0);
+    assert!(cam.zoom > 1.3);
+    assert!(close(cam.screen_to_world(at), before));
+}
+
+#[test]
+fn zoom_is_clamped() {
+    let mut cam = Camera::new(800.0, 600.0);
+    cam.zoom_at((0.0, 0.0), 100.0);
+    assert_eq!(cam.zoom, MAX_ZOOM);
+    cam.wheel((0.0, 0.0), -200.0);
+    assert_eq!(cam.zoom, MIN_ZOOM);
+}
+
+#[test]
+fn pinch_zooms_by_spread_and_pans_with_midpoint() {
+    let mut cam = Camera::new(800.0, 600.0);
+    let from = [(300.0, 300.0), (500.0, 300.0)];
+    let grabbed = cam.screen_to_world((400.0, 300.0));
+    cam.pinch(from, [(250.0, 350.0), (650.0, 350.0)]);
+    assert!((cam.zoom - 2.0).abs() < 1e-4);
+    assert!(close(cam.screen_to_world((450.0, 350.0)), grabbed));
+}
+
+#[test]
+fn target_stays_over_bounds() {
+    let mut cam = Camera::new(800.0, 600.0);
+    cam.set_bounds(Some(TileRect {
+        min: TilePos { x: 0, y: 0 },
+        max: TilePos { x: 9, y: 9 },
+    }));
+    cam.pan(10_000.0, 10_000.0);
+    assert!(close(cam.target, (0.0, 0.0)));
+    cam.pan(-10_000.0, -10_000.0);
+    assert!(close(cam.target, (320.0, 320.0)));
+}
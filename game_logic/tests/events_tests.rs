@@ -0,0 +1,208 @@
+use game_core::*;
+use game_logic::events::{dispatch, EventSubscriber, GameEvent};
+use game_logic::{update_world, InputFrame, Navigation};
+
+mod common;
+use common::*;
+
+/// Run `n` ticks and return every event, in order.
+fn run(world: &mut World, n: u32) -> Vec<GameEvent> {
+    let mut nav = Navigation::new();
+    let mut events = Vec::new();
+    for _ in 0..n {
+        events.extend(update_world(
+            world,
+            &mut nav,
+            &InputFrame::default(),
+            1.0 / 60.0,
+        ));
+    }
+    events
+}
+
+#[test]
+fn building_changes_are_reported_on_the_next_tick() {
+    let mut world = World::with_grid(TileGrid::new(10, 10));
+    let chest = world
+        .grid
+        .place(spec("chest"), at(3, 4), Rotation::R0)
+        .unwrap();
+    world.grid.remove(chest);
+    let again = world
+        .grid
+        .place(spec("chest"), at(5, 5), Rotation::R0)
+        .unwrap();
+
+    assert_eq!(
+        run(&mut world, 1),
+        vec![
+            GameEvent::BuildingPlaced {
+                id: chest,
+                spec_id: spec("chest"),
+                origin: at(3, 4),
+            },
+            GameEvent::BuildingRemoved {
+                id: chest,
+                spec_id: spec("chest"),
+                origin: at(3, 4),
+            },
+            GameEvent::BuildingPlaced {
+                id: again,
+                spec_id: spec("chest"),
+                origin: at(5, 5),
+            },
+        ]
+    );
+    assert!(run(&mut world, 1).is_empty());
+}
+
+#[test]
+fn crafting_reports_start_finish_and_products() {
+    let mut world = World::with_grid(TileGrid::new(10, 10));
+    let f = world
+        .grid
+        .place(spec("furnace"), at(0, 0), Rotation::R0)
+        .unwrap();
+    power(&mut world.grid, at(3, 0));
+    world.grid.take_building_changes();
+    world.grid.offer_item(f, item("copper-ore"));
+
+    let events = run(&mut world, 1 + 192);
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::MachineStarted {
+                id: f,
+                recipe: recipe("copper-plate"),
+            },
+            GameEvent::MachineFinished {
+                id: f,
+                recipe: recipe("copper-plate"),
+            },
+            GameEvent::ItemProduced {
+                by: f,
+                item: item("copper-plate"),
+                count: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn drills_report_each_unit_mined() {
+    let (mut world, d) = mining_world(10, true);
+    world.grid.take_building_changes();
+
+    let events = run(&mut world, 180);
+    let mined = GameEvent::ItemProduced {
+        by: d,
+        item: item("iron-ore"),
+        count: 1,
+    };
+    assert_eq!(events, vec![mined; 3]);
+}
+
+#[test]
+fn power_shortage_is_reported_once_when_it_starts() {
+    let mut world = World::with_grid(TileGrid::new(20, 20));
+    let grid = &mut world.grid;
+    for x in [0, 4] {
+        let a = grid
+            .place(spec("assembler"), at(x, 0), Rotation::R0)
+            .unwrap();
+        grid.set_recipe(a, Some(recipe("iron-gear"))).unwrap();
+    }
+    grid.place(spec("pole"), at(3, 3), Rotation::R0).unwrap();
+    grid.place(spec("generator"), at(7, 6), Rotation::R0)
+        .unwrap();
+    grid.place(spec("pole"), at(7, 5), Rotation::R0).unwrap();
+    grid.take_building_changes();
+
+    // one 300 kW generator cannot run two more 150 kW assemblers
+    assert!(run(&mut world, 10).is_empty());
+    for x in [0, 4] {
+        world
+            .grid
+            .place(spec("assembler"), at(x, 3), Rotation::R0)
+            .unwrap();
+    }
+    let shortages: Vec<GameEvent> = run(&mut world, 10)
+        .into_iter()
+        .filter(|e| matches!(e, GameEvent::PowerShortage { .. }))
+        .collect();
+    assert!(matches!(
+        shortages[..],
+        [GameEvent::PowerShortage {
+            production: 300,
+            demand: 600,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn lab_start_and_finished_research_are_reported_once() {
+    let mut world = World::with_grid(TileGrid::new(20, 20));
+    let grid = &mut world.grid;
+    grid.techs_mut().insert(Technology {
+        tech_id: 100,
+        name: "quick".to_string(),
+        prerequisites: Vec::new(),
+        cost: vec![ItemStack {
+            item: item("automation-science"),
+            count: 1,
+        }],
+        units: 1,
+        unit_ticks: 10,
+        unlocks: Vec::new(),
+    });
+    let lab = grid.place(spec("lab"), at(0, 0), Rotation::R0).unwrap();
+    power(grid, at(3, 0));
+    grid.offer_item(lab, item("automation-science"));
+    grid.set_research(Some(100)).unwrap();
+    grid.take_building_changes();
+
+    let events = run(&mut world, 30);
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::LabStarted { id: lab, tech: 100 },
+            GameEvent::ResearchFinished { tech: 100 },
+        ]
+    );
+    assert!(world.grid.research().is_researched(100));
+}
+
+#[derive(Default)]
+struct Tally(Vec<&'static str>);
+
+impl EventSubscriber for Tally {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.0.push(match event {
+            GameEvent::BuildingPlaced { .. } => "placed",
+            GameEvent::EntityDied { .. } => "died",
+            _ => "other",
+        });
+    }
+}
+
+#[test]
+fn every_subscriber_sees_every_event_in_order() {
+    let events = [
+        GameEvent::BuildingPlaced {
+            id: 1,
+            spec_id: spec("chest"),
+            origin: at(0, 0),
+        },
+        GameEvent::EntityDied {
+            id: 3,
+            ty: EntityType::Enemy,
+            x: 0.0,
+            y: 0.0,
+        },
+    ];
+    let (mut a, mut b) = (Tally::default(), Tally::default());
+    dispatch(&events, &mut [&mut a, &mut b]);
+    assert_eq!(a.0, vec!["placed", "died"]);
+    assert_eq!(b.0, a.0);
+}
@@ -0,0 +1,224 @@
+use game_core::{Rotation, TileGrid, TilePos, TileRect, World, TILE_SIZE};
+use game_logic::flowfield::FlowField;
+use game_logic::pathfinding::find_path;
+use game_logic::simulation::Simulation;
+use game_logic::InputFrame;
+
+mod common;
+use common::*;
+
+/// Grid sized to an ascii map: `#` is a building, `G` a goal, anything else
+/// open ground.
+fn parse(map: &str) -> (TileGrid, Vec<TilePos>) {
+    let rows: Vec<&str> = map
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    let mut grid = TileGrid::new(rows[0].len(), rows.len());
+    let mut goals = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let pos = at(x as i32, y as i32);
+            match c {
+                '#' => {
+                    grid.place(spec("chest"), pos, Rotation::R0).unwrap();
+                }
+                'G' => goals.push(pos),
+                _ => {}
+            }
+        }
+    }
+    (grid, goals)
+}
+
+fn field(grid: &TileGrid, goals: &[TilePos]) -> FlowField {
+    let mut field = FlowField::new(grid.bounds().unwrap(), goals);
+    field.update(grid);
+    field
+}
+
+/// Follow the direction field from `from` until it stops.
+fn walk(field: &FlowField, from: TilePos) -> Vec<TilePos> {
+    let mut route = vec![from];
+    while let Some(next) = field.next_tile(*route.last().unwrap()) {
+        route.push(next);
+        assert!(route.len() < 1000, "direction field loops");
+    }
+    route
+}
+
+#[test]
+fn integration_field_counts_steps_to_the_goal() {
+    let (grid, goals) = parse(
+        "
+        G....
+        .....
+        ",
+    );
+    let field = field(&grid, &goals);
+    assert_eq!(field.cost(at(0, 0)), Some(0));
+    assert_eq!(field.cost(at(4, 0)), Some(40));
+    assert_eq!(field.cost(at(1, 1)), Some(14));
+    assert_eq!(field.next_tile(at(4, 0)), Some(at(3, 0)));
+    assert_eq!(field.next_tile(at(0, 0)), None);
+    assert_eq!(field.cost(at(9, 9)), None);
+}
+
+#[test]
+fn directions_lead_around_walls_without_cutting_corners() {
+    let (grid, goals) = parse(
+        "
+        ...........
+        .#########.
+        .#...G...#.
+        .#.#####.#.
+        .#.#...#.#.
+        .#.#.#.#.#.
+        ...#.#...#.
+        ",
+    );
+    let field = field(&grid, &goals);
+    for start in [at(0, 0), at(4, 4), at(10, 6), at(6, 5)] {
+        let route = walk(&field, start);
+        assert_eq!(route.last(), Some(&goals[0]));
+        for step in route.windows(2) {
+            let (a, b) = (step[0], step[1]);
+            assert!(grid.tile_occupant(b).is_none());
+            assert!(grid.tile_occupant(at(b.x, a.y)).is_none());
+            assert!(grid.tile_occupant(at(a.x, b.y)).is_none());
+        }
+        // as short as a direct search
+        let path = find_path(&grid, start, goals[0]).unwrap();
+        let cost: u32 = path
+            .windows(2)
+            .map(|s| {
+                if s[0].x != s[1].x && s[0].y != s[1].y {
+                    14
+                } else {
+                    10
+                }
+            })
+            .sum();
+        assert_eq!(field.cost(start), Some(cost));
+    }
+}
+
+#[test]
+fn sealed_off_tiles_have_no_direction() {
+    let (grid, goals) = parse(
+        "
+        G..###
+        ...#.#
+        ...###
+        ",
+    );
+    let field = field(&grid, &goals);
+    assert_eq!(field.cost(at(4, 1)), None);
+    assert_eq!(field.next_tile(at(4, 1)), None);
+    assert_eq!(field.sample(4.5 * TILE_SIZE, 1.5 * TILE_SIZE), None);
+    assert!(field.sample(2.5 * TILE_SIZE, 1.5 * TILE_SIZE).is_some());
+}
+
+#[test]
+fn each_tile_heads_for_its_nearest_goal() {
+    let (grid, goals) = parse(
+        "
+        G...#....G
+        ....#.....
+        ..........
+        ",
+    );
+    let field = field(&grid, &goals);
+    assert_eq!(walk(&field, at(3, 0)).last(), Some(&at(0, 0)));
+    assert_eq!(walk(&field, at(5, 0)).last(), Some(&at(9, 0)));
+    let (x, y) = field.sample(1.5 * TILE_SIZE, 0.5 * TILE_SIZE).unwrap();
+    assert!((x + 1.0).abs() < 1e-6 && y.abs() < 1e-6);
+}
+
+#[test]
+fn repairs_match_a_full_rebuild() {
+    let mut grid = TileGrid::new(40, 30);
+    let goals = [at(20, 15), at(3, 27)];
+    let mut live = FlowField::new(grid.bounds().unwrap(), &goals);
+    live.update(&grid);
+
+    let mut placed = Vec::new();
+    let mut seed = 7u32;
+    for round in 0..300 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let r = seed >> 8;
+        if round % 3 == 2 && !placed.is_empty() {
+            let id = placed.swap_remove(r as usize % placed.len());
+            grid.remove(id);
+        } else {
+            let pos = at((r % 40) as i32, ((r / 40) % 30) as i32);
+            if let Ok(id) = grid.place(spec("chest"), pos, Rotation::R0) {
+                placed.push(id);
+            }
+        }
+        live.update(&grid);
+
+        let fresh = field(&grid, &goals);
+        for y in 0..30 {
+            for x in 0..40 {
+                let t = at(x, y);
+                assert_eq!(
+                    live.cost(t),
+                    fresh.cost(t),
+                    "cost at {:?}, round {}",
+                    t,
+                    round
+                );
+                assert_eq!(live.next_tile(t), fresh.next_tile(t), "next at {:?}", t);
+            }
+        }
+    }
+}
+
+#[test]
+fn area_follows_the_goal_chunk() {
+    let unbounded = TileGrid::unbounded();
+    let area = FlowField::area_around(&unbounded, at(5, -3));
+    assert_eq!(
+        area,
+        TileRect {
+            min: at(-32, -64),
+            max: at(63, 31),
+        }
+    );
+    assert_eq!(FlowField::area_around(&unbounded, at(31, -32)), area);
+
+    let bounded = TileGrid::new(20, 10);
+    assert_eq!(
+        FlowField::area_around(&bounded, at(5, 5)),
+        bounded.bounds().unwrap()
+    );
+}
+
+#[test]
+fn swarms_funnel_through_the_gap() {
+    let mut grid = TileGrid::new(40, 40);
+    // wall across the middle with a single gap at x = 20
+    for x in (0..40).filter(|&x| x != 20) {
+        grid.place(spec("chest"), at(x, 20), Rotation::R0).unwrap();
+    }
+    let mut world = World::with_grid(grid);
+    world.spawn_player(20.0 * TILE_SIZE, 35.0 * TILE_SIZE);
+    for i in 0..200 {
+        let (x, y) = (2 + i % 36, 2 + i / 36 * 2);
+        world.spawn_enemy((x as f32 + 0.5) * TILE_SIZE, (y as f32 + 0.5) * TILE_SIZE);
+    }
+    let mut sim = Simulation::new(world);
+    for _ in 0..60 * 6 {
+        sim.step(&InputFrame::default());
+    }
+    let through = sim
+        .world
+        .entities()
+        .iter()
+        .filter(|e| e.transform.y > 21.0 * TILE_SIZE)
+        .count();
+    // the player and a queue of enemies that made it past the wall
+    assert!(through > 10, "only {} got through", through);
+}
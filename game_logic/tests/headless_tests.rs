@@ -0,0 +1,69 @@
+use game_core::*;
+use game_logic::headless::{run, Scenario, ScenarioError};
+use game_logic::simulation::Simulation;
+use game_logic::InputFrame;
+
+mod common;
+use common::*;
+
+#[test]
+fn save_runs_without_input_and_tallies_production() {
+    let bytes = mining_world(10, true).0.save_binary().unwrap();
+    let scenario = Scenario::load(&bytes).unwrap();
+    assert!(scenario.inputs.is_empty());
+
+    let (sim, stats) = run(scenario, 180);
+    assert_eq!(stats.ticks, 180);
+    assert_eq!(sim.tick(), 180);
+    assert_eq!(stats.produced.get(&item("iron-ore")), Some(&3));
+    assert!(stats.crafted.is_empty());
+    assert_eq!(stats.checksum, sim.world.checksum());
+}
+
+#[test]
+fn binary_and_text_saves_give_the_same_run() {
+    let world = mining_world(10, true).0;
+    let binary = Scenario::load(&world.save_binary().unwrap()).unwrap();
+    let text = Scenario::load(world.save_text().unwrap().as_bytes()).unwrap();
+    assert_eq!(run(binary, 300).1, run(text, 300).1);
+}
+
+#[test]
+fn replay_inputs_drive_the_run() {
+    let mut world = World::with_grid(TileGrid::new(8, 8));
+    world.spawn_player(100.0, 100.0);
+    world.spawn_enemy(200.0, 150.0);
+    let mut sim = Simulation::with_tick_rate(world, 30);
+    sim.start_recording(10).unwrap();
+    for t in 0..90 {
+        sim.step(&InputFrame {
+            move_x: 1.0,
+            action: t % 10 == 0,
+            pointer: Some((200.0, 150.0)),
+            ..Default::default()
+        });
+    }
+    let replay = sim.stop_recording().unwrap();
+
+    let scenario = Scenario::load(&replay.to_bytes().unwrap()).unwrap();
+    assert_eq!(scenario.tick_rate, 30);
+    assert_eq!(scenario.inputs.len(), 90);
+    let (_, stats) = run(scenario, 90);
+    assert_eq!(stats.checksum, sim.world.checksum());
+
+    // past the recording the world keeps running with no input
+    let scenario = Scenario::load(&replay.to_bytes().unwrap()).unwrap();
+    assert_eq!(run(scenario, 120).1.ticks, 120);
+}
+
+#[test]
+fn unreadable_files_are_rejected() {
+    assert!(matches!(
+        Scenario::load(&[0xff, 0x00, 0x12]),
+        Err(ScenarioError::Save(SaveError::NotASave))
+    ));
+    assert!(matches!(
+        Scenario::load(b"FGRP\x09\x00\x00\x00"),
+        Err(ScenarioError::Replay(_))
+    ));
+}
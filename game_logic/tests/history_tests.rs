@@ -0,0 +1,219 @@
+use game_core::{ItemRegistry, PlacementError, Rotation, SpecId, SpecRegistry, TileGrid};
+use game_logic::history::{Command, CommandError, History};
+
+mod common;
+use common::*;
+
+fn place(spec_id: SpecId, x: i32, y: i32) -> Command {
+    Command::Place {
+        spec_id,
+        origin: at(x, y),
+        rotation: Rotation::R0,
+    }
+}
+
+#[test]
+fn undo_and_redo_place_keep_the_id() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    let id = history
+        .apply(&mut grid, place(spec("furnace"), 1, 1))
+        .unwrap();
+
+    assert!(history.undo(&mut grid).unwrap());
+    assert_eq!(grid.tile_occupant(at(1, 1)), None);
+    assert!(history.redo(&mut grid).unwrap());
+    assert_eq!(grid.tile_occupant(at(2, 2)), Some(id));
+
+    // nothing further to redo; the next fresh placement does not reuse the id
+    assert!(!history.redo(&mut grid).unwrap());
+    let next = history
+        .apply(&mut grid, place(spec("chest"), 5, 5))
+        .unwrap();
+    assert!(next > id);
+}
+
+#[test]
+fn undoing_a_removal_brings_back_contents_and_id() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    let items = ItemRegistry::builtin();
+    let chest = history
+        .apply(&mut grid, place(spec("chest"), 2, 2))
+        .unwrap();
+    let belt = history
+        .apply(&mut grid, place(spec("conveyor"), 4, 2))
+        .unwrap();
+    grid.inventory_mut(chest)
+        .unwrap()
+        .insert(&items, item("coal"), 9);
+    assert!(grid.belt_insert(belt, item("coal")));
+
+    history
+        .apply(&mut grid, Command::Remove { id: chest })
+        .unwrap();
+    history
+        .apply(&mut grid, Command::Remove { id: belt })
+        .unwrap();
+    assert!(grid.instances.is_empty());
+
+    history.undo(&mut grid).unwrap();
+    history.undo(&mut grid).unwrap();
+    assert_eq!(grid.tile_occupant(at(2, 2)), Some(chest));
+    assert_eq!(grid.inventory(chest).unwrap().count(item("coal")), 9);
+    assert_eq!(grid.belt(belt).unwrap().len(), 1);
+
+    // redoing the removal and undoing it again still finds the same building
+    grid.inventory_mut(chest)
+        .unwrap()
+        .insert(&items, item("coal"), 1);
+    history.redo(&mut grid).unwrap();
+    assert!(grid.inventory(chest).is_none());
+    history.undo(&mut grid).unwrap();
+    assert_eq!(grid.inventory(chest).unwrap().count(item("coal")), 10);
+}
+
+#[test]
+fn later_commands_survive_undoing_earlier_removals() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    let id = history
+        .apply(&mut grid, place(spec("conveyor"), 0, 0))
+        .unwrap();
+    history
+        .apply(
+            &mut grid,
+            Command::Rotate {
+                id,
+                rotation: Rotation::R90,
+            },
+        )
+        .unwrap();
+    history.apply(&mut grid, Command::Remove { id }).unwrap();
+
+    // undoing the removal must restore `id` itself or the rotate step would
+    // point at nothing
+    while history.undo(&mut grid).unwrap() {}
+    assert!(grid.instances.is_empty());
+    while history.redo(&mut grid).unwrap() {}
+    assert!(grid.instances.is_empty());
+    history.undo(&mut grid).unwrap();
+    assert_eq!(grid.instances[&id].rotation, Rotation::R90);
+    history.undo(&mut grid).unwrap();
+    assert_eq!(grid.instances[&id].rotation, Rotation::R0);
+}
+
+#[test]
+fn rotate_that_does_not_fit_leaves_the_building_alone() {
+    let specs = SpecRegistry::from_ron_str(
+        r#"[(spec_id: 1, name: "long", size: (w: 3, h: 1)),
+            (spec_id: 2, name: "block", size: (w: 1, h: 1))]"#,
+    )
+    .unwrap();
+    let mut grid = TileGrid::with_specs(6, 6, specs);
+    let mut history = History::new();
+    let long = history.apply(&mut grid, place(1, 0, 0)).unwrap();
+    history.apply(&mut grid, place(2, 0, 2)).unwrap();
+
+    let err = history.apply(
+        &mut grid,
+        Command::Rotate {
+            id: long,
+            rotation: Rotation::R90,
+        },
+    );
+    assert!(matches!(err, Err(CommandError::Placement(_))));
+    assert_eq!(grid.instances[&long].rotation, Rotation::R0);
+    assert_eq!(grid.tile_occupant(at(2, 0)), Some(long));
+
+    // the failed command was not recorded
+    history.undo(&mut grid).unwrap();
+    assert_eq!(grid.tile_occupant(at(0, 2)), None);
+    assert_eq!(grid.tile_occupant(at(0, 0)), Some(long));
+}
+
+#[test]
+fn a_drag_is_one_undo_step() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    history.begin_group();
+    for x in 0..5 {
+        history
+            .apply(&mut grid, place(spec("conveyor"), x, 3))
+            .unwrap();
+    }
+    history.end_group();
+    assert_eq!(grid.instances.len(), 5);
+
+    history.undo(&mut grid).unwrap();
+    assert!(grid.instances.is_empty());
+    assert!(!history.can_undo());
+    history.redo(&mut grid).unwrap();
+    assert_eq!(grid.instances.len(), 5);
+}
+
+#[test]
+fn bulk_apply_skips_failures_and_undoes_together() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    let results = history.apply_all(
+        &mut grid,
+        [
+            place(spec("chest"), 0, 0),
+            place(spec("chest"), 0, 0),
+            place(spec("chest"), 1, 0),
+        ],
+    );
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(CommandError::Placement(_))));
+    assert!(results[2].is_ok());
+
+    let ids: Vec<_> = grid.instances.keys().copied().collect();
+    history.apply_all(&mut grid, ids.into_iter().map(|id| Command::Remove { id }));
+    assert!(grid.instances.is_empty());
+    history.undo(&mut grid).unwrap();
+    assert_eq!(grid.instances.len(), 2);
+}
+
+#[test]
+fn new_command_clears_redo() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    history
+        .apply(&mut grid, place(spec("chest"), 0, 0))
+        .unwrap();
+    history.undo(&mut grid).unwrap();
+    assert!(history.can_redo());
+    history
+        .apply(&mut grid, place(spec("chest"), 3, 3))
+        .unwrap();
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn redo_fails_cleanly_when_the_spot_was_taken() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    history
+        .apply(&mut grid, place(spec("chest"), 0, 0))
+        .unwrap();
+    history.undo(&mut grid).unwrap();
+    // built outside the history, on the same tile
+    let other = grid.place(spec("chest"), at(0, 0), Rotation::R0).unwrap();
+
+    assert!(history.redo(&mut grid).is_err());
+    assert!(history.can_redo());
+    assert_eq!(grid.tile_occupant(at(0, 0)), Some(other));
+}
+
+#[test]
+fn locked_specs_are_not_placed_or_recorded() {
+    let mut grid = TileGrid::new(8, 8);
+    let mut history = History::new();
+    assert!(matches!(
+        history.apply(&mut grid, place(spec("pipe"), 0, 0)),
+        Err(CommandError::Placement(PlacementError::Locked(id))) if id == spec("pipe")
+    ));
+    assert!(grid.instances.is_empty());
+    assert!(!history.can_undo());
+}
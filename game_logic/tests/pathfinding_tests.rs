@@ -0,0 +1,112 @@
+use game_core::{Rotation, TileGrid, TilePos, World};
+use game_logic::pathfinding::{find_path, walkable, Pathfinder};
+use game_logic::simulation::Simulation;
+use game_logic::InputFrame;
+
+mod common;
+use common::*;
+
+/// 12x10 grid with a wall down column 5 leaving only row 9 open.
+fn walled() -> TileGrid {
+    let mut grid = TileGrid::new(12, 10);
+    for y in 0..9 {
+        grid.place(spec("chest"), at(5, y), Rotation::R0).unwrap();
+    }
+    grid
+}
+
+fn assert_valid(grid: &TileGrid, path: &[TilePos]) {
+    for step in path.windows(2) {
+        let (a, b) = (step[0], step[1]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        assert!(dx.abs() <= 1 && dy.abs() <= 1 && (dx, dy) != (0, 0));
+        assert!(walkable(grid, b));
+        // never squeeze past a building corner
+        assert!(walkable(grid, at(a.x + dx, a.y)) && walkable(grid, at(a.x, a.y + dy)));
+    }
+}
+
+#[test]
+fn open_ground_takes_the_direct_route() {
+    let grid = TileGrid::new(10, 10);
+    let path = find_path(&grid, at(1, 1), at(7, 4)).unwrap();
+    assert_eq!(path.first(), Some(&at(1, 1)));
+    assert_eq!(path.last(), Some(&at(7, 4)));
+    assert_eq!(path.len(), 7);
+    assert_valid(&grid, &path);
+}
+
+#[test]
+fn paths_go_around_walls() {
+    let grid = walled();
+    let path = find_path(&grid, at(2, 4), at(8, 4)).unwrap();
+    assert_valid(&grid, &path);
+    assert!(path.contains(&at(5, 9)));
+    // same answer every time
+    assert_eq!(find_path(&grid, at(2, 4), at(8, 4)).unwrap(), path);
+}
+
+#[test]
+fn unreachable_or_blocked_goals_have_no_path() {
+    let mut grid = walled();
+    grid.place(spec("chest"), at(5, 9), Rotation::R0).unwrap();
+    assert_eq!(find_path(&grid, at(2, 4), at(8, 4)), None);
+    assert_eq!(find_path(&grid, at(2, 4), at(5, 3)), None);
+    assert_eq!(find_path(&grid, at(2, 4), at(2, 4)), Some(vec![at(2, 4)]));
+}
+
+#[test]
+fn cache_is_dropped_when_buildings_change() {
+    let mut grid = TileGrid::new(10, 10);
+    let mut paths = Pathfinder::new();
+    let direct = paths.path(&grid, at(0, 5), at(9, 5)).unwrap().to_vec();
+    assert_eq!(direct.len(), 10);
+    paths.path(&grid, at(0, 5), at(9, 5));
+    assert_eq!(paths.cached(), 1);
+
+    let wall = grid.place(spec("chest"), at(4, 5), Rotation::R0).unwrap();
+    let around = paths.path(&grid, at(0, 5), at(9, 5)).unwrap().to_vec();
+    assert!(!around.contains(&at(4, 5)));
+    assert_valid(&grid, &around);
+    assert_eq!(paths.cached(), 1);
+
+    grid.remove(wall);
+    assert_eq!(paths.path(&grid, at(0, 5), at(9, 5)).unwrap(), direct);
+}
+
+#[test]
+fn waypoints_lead_to_the_next_tile_then_the_target() {
+    let grid = walled();
+    let mut paths = Pathfinder::new();
+    // from tile (4, 8) the only way on is down to row 9
+    assert_eq!(
+        paths.waypoint(&grid, (140.0, 270.0), (300.0, 140.0)),
+        (144.0, 304.0)
+    );
+    assert_eq!(
+        paths.waypoint(&grid, (300.0, 130.0), (310.0, 140.0)),
+        (310.0, 140.0)
+    );
+}
+
+#[test]
+fn enemies_walk_around_walls_to_the_player() {
+    let mut world = World::with_grid(walled());
+    world.spawn_player(9.0 * 32.0, 4.5 * 32.0);
+    world.spawn_enemy(2.0 * 32.0, 4.5 * 32.0);
+    let mut sim = Simulation::new(world);
+    let touching = |sim: &Simulation| {
+        let (player, enemy) = (&sim.world.entities()[0], &sim.world.entities()[1]);
+        let gap =
+            (enemy.transform.x - player.transform.x).hypot(enemy.transform.y - player.transform.y);
+        gap < enemy.radius + player.radius + 2.0
+    };
+    let mut ticks = 0;
+    while !touching(&sim) && ticks < 60 * 10 {
+        sim.step(&InputFrame::default());
+        ticks += 1;
+    }
+    assert!(touching(&sim), "enemy never reached the player");
+    // 7 tiles of straight line would take under 3 s; the detour takes longer
+    assert!(ticks > 60 * 3);
+}
@@ -0,0 +1,54 @@
+use game_core::*;
+use game_logic::placement::{try_place_building, try_set_recipe};
+
+mod common;
+use common::*;
+
+#[test]
+fn locked_specs_cannot_be_placed_until_researched() {
+    let mut grid = TileGrid::new(10, 10);
+    assert!(matches!(
+        try_place_building(&mut grid, spec("pipe"), at(0, 0), Rotation::R0),
+        Err(PlacementError::Locked(id)) if id == spec("pipe")
+    ));
+    assert!(grid.instances.is_empty());
+
+    grid.finish_research(tech("fluid-handling")).unwrap();
+    assert!(try_place_building(&mut grid, spec("pipe"), at(0, 0), Rotation::R0).is_ok());
+    // unknown specs still report as unknown
+    assert!(matches!(
+        try_place_building(&mut grid, 99, at(5, 5), Rotation::R0),
+        Err(PlacementError::UnknownSpec(99))
+    ));
+}
+
+#[test]
+fn locked_recipes_cannot_be_chosen() {
+    let mut grid = TileGrid::new(10, 10);
+    // a second technology that unlocks only the plant
+    grid.techs_mut().insert(Technology {
+        tech_id: 50,
+        name: "plant-only".to_string(),
+        prerequisites: Vec::new(),
+        cost: Vec::new(),
+        units: 1,
+        unit_ticks: 1,
+        unlocks: vec![Unlock::Building(spec("chemical-plant"))],
+    });
+    grid.finish_research(50).unwrap();
+    let plant =
+        try_place_building(&mut grid, spec("chemical-plant"), at(0, 0), Rotation::R0).unwrap();
+
+    assert_eq!(
+        try_set_recipe(&mut grid, plant, Some(recipe("plastic-bar"))),
+        Err(MachineError::Locked(recipe("plastic-bar")))
+    );
+    assert_eq!(grid.machine(plant).unwrap().recipe(), None);
+
+    grid.finish_research(tech("oil-processing")).unwrap();
+    try_set_recipe(&mut grid, plant, Some(recipe("plastic-bar"))).unwrap();
+    assert_eq!(
+        grid.machine(plant).unwrap().recipe(),
+        Some(recipe("plastic-bar"))
+    );
+}
@@ -0,0 +1,96 @@
+use game_core::{TileGrid, World};
+use game_logic::replay::{Replay, ReplayError, ReplayOutcome};
+use game_logic::simulation::Simulation;
+use game_logic::InputFrame;
+
+fn world() -> World {
+    let mut w = World::with_grid(TileGrid::new(8, 8));
+    w.spawn_player(100.0, 100.0);
+    w.spawn_enemy(400.0, 50.0);
+    w
+}
+
+/// Input that wanders around so every tick differs.
+fn input(tick: u32) -> InputFrame {
+    InputFrame {
+        move_x: ((tick / 25) % 3) as f32 - 1.0,
+        move_y: ((tick / 40) % 3) as f32 - 1.0,
+        action: tick.is_multiple_of(17),
+        pointer: Some((tick as f32, 2.0 * tick as f32)),
+    }
+}
+
+fn record(ticks: u32, interval: u64) -> (Replay, u64) {
+    let mut sim = Simulation::new(world());
+    // a few unrecorded ticks first: the replay starts from the later state
+    for t in 0..5 {
+        sim.step(&input(t));
+    }
+    sim.start_recording(interval).unwrap();
+    for t in 0..ticks {
+        sim.step(&input(t));
+    }
+    let replay = sim.stop_recording().unwrap();
+    (replay, sim.world.checksum())
+}
+
+#[test]
+fn replay_reproduces_the_recorded_run() {
+    let (replay, final_checksum) = record(200, 10);
+    assert_eq!(replay.inputs.len(), 200);
+    assert_eq!(replay.checkpoints.len(), 20);
+
+    let replay = Replay::from_bytes(&replay.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        replay.play().unwrap(),
+        ReplayOutcome::Matched {
+            ticks: 200,
+            checksum: final_checksum
+        }
+    );
+}
+
+#[test]
+fn final_state_is_always_checkpointed() {
+    let (replay, final_checksum) = record(25, 10);
+    assert_eq!(replay.checkpoints.last(), Some(&(25, final_checksum)));
+}
+
+#[test]
+fn divergence_reports_the_first_bad_checkpoint() {
+    let (mut replay, _) = record(200, 10);
+    // tick 58 gets different input than was recorded
+    replay.inputs[57].move_x = 0.5;
+    match replay.play().unwrap() {
+        ReplayOutcome::Diverged {
+            tick,
+            checkpoint,
+            last_matching,
+            expected,
+            actual,
+        } => {
+            // the replay itself is deterministic, so it cannot narrow this
+            // down past the checkpoint
+            assert_eq!((tick, checkpoint), (60, 60));
+            assert_eq!(last_matching, Some(50));
+            assert_eq!(replay.checkpoints[5], (60, expected));
+            assert_ne!(expected, actual);
+        }
+        other => panic!("expected divergence, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_other_files() {
+    assert!(matches!(
+        Replay::from_bytes(b"FGSV\x01\0\0\0"),
+        Err(ReplayError::NotAReplay)
+    ));
+    let (replay, _) = record(3, 1);
+    let mut bytes = replay.to_bytes().unwrap();
+    bytes[4] = 7;
+    assert!(matches!(
+        Replay::from_bytes(&bytes),
+        Err(ReplayError::UnsupportedVersion(7))
+    ));
+}
@@ -0,0 +1,98 @@
+use game_core::World;
+use game_logic::events::GameEvent;
+use game_logic::simulation::{Simulation, MAX_TICKS_PER_FRAME};
+use game_logic::InputFrame;
+
+fn world() -> World {
+    let mut w = World::with_grid(game_core::TileGrid::new(8, 8));
+    w.spawn_player(0.0, 0.0);
+    w.spawn_enemy(300.0, 100.0);
+    w
+}
+
+fn moving_right() -> InputFrame {
+    InputFrame {
+        move_x: 1.0,
+        ..InputFrame::default()
+    }
+}
+
+#[test]
+fn ticks_follow_real_time_not_frames() {
+    let mut sim = Simulation::new(world());
+    assert_eq!(sim.advance(1.0 / 240.0, &InputFrame::default()), 0);
+    assert_eq!(sim.advance(1.0 / 240.0, &InputFrame::default()), 0);
+    assert!((sim.alpha() - 0.5).abs() < 1e-3);
+    assert_eq!(sim.advance(1.0 / 120.0, &InputFrame::default()), 1);
+    assert_eq!(sim.advance(1.0 / 30.0, &InputFrame::default()), 2);
+    assert_eq!(sim.tick(), 3);
+}
+
+#[test]
+fn catch_up_is_capped() {
+    let mut sim = Simulation::new(world());
+    assert_eq!(
+        sim.advance(5.0, &InputFrame::default()),
+        MAX_TICKS_PER_FRAME
+    );
+    assert!(sim.alpha() < 1.0);
+    // the dropped backlog is not replayed later
+    assert_eq!(sim.advance(0.0, &InputFrame::default()), 0);
+}
+
+#[test]
+fn frame_rate_does_not_change_the_outcome() {
+    let mut fast = Simulation::new(world());
+    let mut slow = Simulation::new(world());
+    for _ in 0..240 {
+        fast.advance(1.0 / 120.0, &moving_right());
+    }
+    for _ in 0..80 {
+        slow.advance(1.0 / 40.0, &moving_right());
+    }
+    assert_eq!(fast.tick(), 120);
+    assert_eq!(slow.tick(), 120);
+    assert_eq!(
+        fast.world.save_binary().unwrap(),
+        slow.world.save_binary().unwrap()
+    );
+}
+
+#[test]
+fn interpolation_blends_last_two_ticks() {
+    let mut sim = Simulation::new(world());
+    let player = sim.world.find_player().unwrap().id;
+    sim.advance(1.0 / 60.0, &moving_right());
+    let x1 = sim.world.find_player().unwrap().transform.x;
+    sim.advance(0.5 / 60.0, &moving_right());
+    let (x, _) = sim.interpolated_position(player).unwrap();
+    assert!(x > 0.0 && x < x1);
+    let drawn: Vec<_> = sim.interpolated_entities().collect();
+    assert_eq!(drawn.len(), sim.world.entities().len());
+    assert!(drawn.iter().any(|(e, p)| e.id == player && p.0 == x));
+}
+
+#[test]
+fn action_fires_toward_the_pointer_and_reports_events() {
+    let mut sim = Simulation::new(world());
+    let fire = InputFrame {
+        action: true,
+        pointer: Some((300.0, 100.0)),
+        ..InputFrame::default()
+    };
+    sim.advance(1.0 / 60.0, &fire);
+    let events = sim.take_events();
+    assert!(matches!(
+        events[..],
+        [GameEvent::ProjectileFired { by: 1, .. }]
+    ));
+    assert!(sim.take_events().is_empty());
+
+    for _ in 0..60 {
+        sim.advance(1.0 / 60.0, &InputFrame::default());
+    }
+    assert!(sim
+        .take_events()
+        .iter()
+        .any(|e| matches!(e, GameEvent::EntityDamaged { target: 2, .. })));
+}
@@ -0,0 +1,181 @@
+use game_core::*;
+use game_logic::simulation::Simulation;
+use game_logic::stats::{Flow, Window};
+use game_logic::InputFrame;
+
+mod common;
+use common::*;
+
+/// A powered drill on `ore` units of iron, feeding a chest if `chest`.
+fn run(sim: &mut Simulation, ticks: u32) {
+    for _ in 0..ticks {
+        sim.step(&InputFrame::default());
+    }
+}
+
+#[test]
+fn drill_output_and_uptime_are_tracked() {
+    let (world, drill) = mining_world(10, true);
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 180);
+
+    let stats = sim.stats();
+    assert_eq!(stats.window_ticks(Window::Minute), 180);
+    assert_eq!(
+        stats.flow(item("iron-ore"), Window::Minute),
+        Flow {
+            produced: 3,
+            consumed: 0,
+        }
+    );
+    let activity = stats.activity(drill, Window::Hour);
+    assert_eq!(activity.working, 180);
+    assert_eq!(activity.uptime(), 1.0);
+}
+
+#[test]
+fn crafting_consumes_ingredients() {
+    let mut world = World::with_grid(TileGrid::new(10, 10));
+    let furnace = world
+        .grid
+        .place(spec("furnace"), at(0, 0), Rotation::R0)
+        .unwrap();
+    power(&mut world.grid, at(3, 0));
+    world.grid.offer_item(furnace, item("copper-ore"));
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 400);
+
+    let flows = sim.stats().flows(Window::Minute);
+    assert_eq!(flows[&item("copper-ore")].consumed, 1);
+    assert_eq!(flows[&item("copper-plate")].produced, 1);
+    assert_eq!(flows[&item("copper-ore")].net(), -1);
+
+    // one craft, then waiting for more ore
+    let activity = sim.stats().activity(furnace, Window::Minute);
+    assert_eq!(activity.ticks(), 400);
+    assert!(activity.working > 0 && activity.idle > 0);
+    assert!((activity.uptime() + activity.idle_fraction() - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn cancelled_crafts_consume_nothing() {
+    let mut world = World::with_grid(TileGrid::new(10, 10));
+    let assembler = world
+        .grid
+        .place(spec("assembler"), at(0, 0), Rotation::R0)
+        .unwrap();
+    power(&mut world.grid, at(3, 0));
+    world
+        .grid
+        .set_recipe(assembler, Some(recipe("iron-gear")))
+        .unwrap();
+    world.grid.offer_item(assembler, item("iron-plate"));
+    world.grid.offer_item(assembler, item("iron-plate"));
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 10);
+    assert!(sim.world.grid.machine(assembler).unwrap().progress() > 0.0);
+
+    // the plates go back into the input buffer
+    sim.world.grid.set_recipe(assembler, None).unwrap();
+    run(&mut sim, 10);
+    assert_eq!(
+        sim.stats().flow(item("iron-plate"), Window::Minute),
+        Flow {
+            produced: 0,
+            consumed: 0,
+        }
+    );
+}
+
+#[test]
+fn labs_consume_science() {
+    let mut world = World::with_grid(TileGrid::new(10, 10));
+    let lab = world
+        .grid
+        .place(spec("lab"), at(0, 0), Rotation::R0)
+        .unwrap();
+    power(&mut world.grid, at(3, 0));
+    world
+        .grid
+        .set_research(Some(tech("fluid-handling")))
+        .unwrap();
+    world.grid.offer_item(lab, item("automation-science"));
+    world.grid.offer_item(lab, item("automation-science"));
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 10);
+
+    // one unit started, the second waits for it to finish
+    let flow = sim.stats().flow(item("automation-science"), Window::Minute);
+    assert_eq!(flow.consumed, 1);
+    assert_eq!(flow.produced, 0);
+}
+
+#[test]
+fn old_ticks_roll_out_of_short_windows() {
+    // one tick per second: the minute window holds 60 ticks
+    let (world, _) = mining_world(100, true);
+    let mut sim = Simulation::with_tick_rate(world, 1);
+    run(&mut sim, 600);
+
+    let stats = sim.stats();
+    assert_eq!(stats.window_ticks(Window::Minute), 60);
+    assert_eq!(stats.window_ticks(Window::TenMinutes), 600);
+    assert_eq!(stats.flow(item("iron-ore"), Window::Minute).produced, 1);
+    assert_eq!(
+        stats.flow(item("iron-ore"), Window::TenMinutes).produced,
+        10
+    );
+    assert_eq!(stats.flow(item("iron-ore"), Window::Hour).produced, 10);
+}
+
+#[test]
+fn rates_are_per_minute() {
+    let (world, _) = mining_world(1000, true);
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 3 * 3600);
+
+    let (produced, consumed) = sim.stats().per_minute(item("iron-ore"), Window::Minute);
+    assert!((produced - 60.0).abs() < 2.0, "{}", produced);
+    assert_eq!(consumed, 0.0);
+    let (produced, _) = sim.stats().per_minute(item("iron-ore"), Window::TenMinutes);
+    assert!((produced - 60.0).abs() < 2.0, "{}", produced);
+}
+
+#[test]
+fn blocked_and_unpowered_buildings_are_told_apart() {
+    let (mut world, drill) = mining_world(10, false);
+    let assembler = world
+        .grid
+        .place(spec("assembler"), at(4, 4), Rotation::R0)
+        .unwrap();
+    world
+        .grid
+        .set_recipe(assembler, Some(recipe("iron-gear")))
+        .unwrap();
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 120);
+
+    let stats = sim.stats();
+    let drill = stats.activity(drill, Window::Minute);
+    assert!(drill.blocked > 0 && drill.blocked_fraction() > 0.4);
+    assert_eq!(stats.activity(assembler, Window::Minute).unpowered, 120);
+    assert_eq!(
+        stats
+            .activity_by_spec(&sim.world.grid, Window::Minute)
+            .get(&spec("assembler"))
+            .map(|a| a.unpowered_fraction()),
+        Some(1.0)
+    );
+}
+
+#[test]
+fn removed_buildings_are_forgotten() {
+    let (world, drill) = mining_world(10, true);
+    let mut sim = Simulation::new(world);
+    run(&mut sim, 30);
+    assert!(sim.stats().activities(Window::Minute).contains_key(&drill));
+
+    sim.world.grid.remove(drill);
+    run(&mut sim, 1);
+    assert!(!sim.stats().activities(Window::Minute).contains_key(&drill));
+}
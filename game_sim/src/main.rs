@@ -0,0 +1,203 @@
+//! factory-sim: headless simulation runner.
+//! - loads a save or replay as a `game_logic::headless::Scenario`
+//! - runs it for a fixed number of ticks at full speed, with no window
+//! - prints production statistics, the final checksum and timing
+//! - prints throughput and building activity over the last ten minutes
+//!
+//! Exits non-zero when the file fails to load, the arguments are wrong, or
+//! the final checksum differs from `--expect-checksum`.
+
+use std::process::ExitCode;
+use std::time::Instant;
+
+use game_logic::headless::{run, RunStats, Scenario};
+use game_logic::simulation::Simulation;
+use game_logic::stats::Window;
+
+const DEFAULT_TICKS: u64 = 3600;
+
+const USAGE: &str = "usage: factory-sim <save-or-replay> [--ticks N] [--expect-checksum HEX]";
+
+struct Args {
+    path: String,
+    ticks: u64,
+    expect_checksum: Option<u64>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut path = None;
+    let mut ticks = DEFAULT_TICKS;
+    let mut expect_checksum = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" => {
+                let n = args.next().ok_or("--ticks needs a value")?;
+                ticks = n.parse().map_err(|_| format!("bad tick count {:?}", n))?;
+            }
+            "--expect-checksum" => {
+                let hex = args.next().ok_or("--expect-checksum needs a value")?;
+                let digits = hex.trim_start_matches("0x");
+                let sum = u64::from_str_radix(digits, 16)
+                    .map_err(|_| format!("bad checksum {:?}", hex))?;
+                expect_checksum = Some(sum);
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Args {
+        path: path.ok_or(USAGE)?,
+        ticks,
+        expect_checksum,
+    })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+
+    let started = Instant::now();
+    let scenario = match std::fs::read(&args.path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Scenario::load(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("failed to load {}: {}", args.path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let load_secs = started.elapsed().as_secs_f64();
+    println!(
+        "loaded {} in {:.3} s: {} entities, {} buildings, {} recorded inputs",
+        args.path,
+        load_secs,
+        scenario.world.entities().len(),
+        scenario.world.grid.instances.len(),
+        scenario.inputs.len()
+    );
+
+    let tick_rate = scenario.tick_rate;
+    let started = Instant::now();
+    let (sim, stats) = run(scenario, args.ticks);
+    let run_secs = started.elapsed().as_secs_f64();
+    println!(
+        "ran {} ticks ({:.1} s of game time) in {:.3} s, {:.0} ticks/s",
+        stats.ticks,
+        stats.ticks as f64 / tick_rate as f64,
+        run_secs,
+        stats.ticks as f64 / run_secs.max(1e-9)
+    );
+    print_stats(&sim.world, &stats, tick_rate);
+    print_recent(&sim);
+    println!("checksum {:016x}", stats.checksum);
+
+    match args.expect_checksum {
+        Some(expected) if expected != stats.checksum => {
+            eprintln!(
+                "checksum mismatch: expected {:016x}, got {:016x}",
+                expected, stats.checksum
+            );
+            ExitCode::FAILURE
+        }
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn print_stats(world: &game_core::World, stats: &RunStats, tick_rate: u32) {
+    let minutes = stats.ticks as f64 / tick_rate as f64 / 60.0;
+    let per_minute = |n: u64| {
+        if minutes > 0.0 {
+            n as f64 / minutes
+        } else {
+            0.0
+        }
+    };
+
+    println!("produced:");
+    if stats.produced.is_empty() {
+        println!("  nothing");
+    }
+    for (&item, &count) in &stats.produced {
+        let name = world
+            .grid
+            .items()
+            .get(item)
+            .map_or("?", |d| d.name.as_str());
+        println!(
+            "  {:<24} {:>10} ({:.1}/min)",
+            name,
+            count,
+            per_minute(count)
+        );
+    }
+    println!("crafted:");
+    if stats.crafted.is_empty() {
+        println!("  nothing");
+    }
+    for (&recipe, &count) in &stats.crafted {
+        let name = world
+            .grid
+            .recipes()
+            .get(recipe)
+            .map_or("?", |r| r.name.as_str());
+        println!(
+            "  {:<24} {:>10} ({:.1}/min)",
+            name,
+            count,
+            per_minute(count)
+        );
+    }
+    println!(
+        "buildings placed {}, removed {}; power shortages {}; enemies killed {}, player deaths {}",
+        stats.buildings_placed,
+        stats.buildings_removed,
+        stats.power_shortages,
+        stats.enemies_killed,
+        stats.player_deaths
+    );
+    if !stats.researched.is_empty() {
+        let names: Vec<&str> = stats
+            .researched
+            .iter()
+            .map(|&t| world.grid.techs().get(t).map_or("?", |t| t.name.as_str()))
+            .collect();
+        println!("researched: {}", names.join(", "));
+    }
+}
+
+/// Throughput per item and activity per building type over the last ten
+/// minutes of game time.
+fn print_recent(sim: &Simulation) {
+    let (stats, grid) = (sim.stats(), &sim.world.grid);
+    let window = Window::TenMinutes;
+    println!(
+        "last {:.1} min of game time:",
+        stats.window_ticks(window) as f64 / sim.tick_rate() as f64 / 60.0
+    );
+    println!("  {:<24} {:>10} {:>10}", "item", "made/min", "used/min");
+    for &item in stats.flows(window).keys() {
+        let name = grid.items().get(item).map_or("?", |d| d.name.as_str());
+        let (made, used) = stats.per_minute(item, window);
+        println!("  {:<24} {:>10.1} {:>10.1}", name, made, used);
+    }
+    println!("  building                 working  idle  blocked  unpowered");
+    for (spec, a) in stats.activity_by_spec(grid, window) {
+        let name = grid.specs().get(spec).map_or("?", |s| s.name.as_str());
+        println!(
+            "  {:<24} {:>6.0}% {:>4.0}% {:>7.0}% {:>9.0}%",
+            name,
+            a.uptime() * 100.0,
+            a.idle_fraction() * 100.0,
+            a.blocked_fraction() * 100.0,
+            a.unpowered_fraction() * 100.0
+        );
+    }
+}